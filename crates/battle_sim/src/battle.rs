@@ -0,0 +1,108 @@
+use crate::scenario::Scenario;
+
+use luminara_core::ai;
+use luminara_core::combat;
+use luminara_core::command::Command;
+use luminara_core::pathfinding::DijkstraMap;
+use luminara_core::rng::Rng;
+use luminara_core::unit::{Unit, UnitId};
+use luminara_core::world::{Faction, WorldState};
+
+use std::collections::BTreeMap;
+
+const FACTIONS: [Faction; 2] = [Faction::Player, Faction::Enemy];
+
+#[derive(Debug)]
+pub struct BattleResult {
+    /// `None` if both sides were still standing after the turn limit
+    pub winner: Option<Faction>,
+    pub turns: u32,
+    /// Damage dealt by each class, per faction
+    pub damage: [BTreeMap<&'static str, i32>; 2],
+    pub survivors: [usize; 2],
+}
+
+/// Plays the scenario with the AI controlling both sides. The seed decides the order units act
+/// in and where units with nobody to reach wander to.
+pub fn run(scenario: &Scenario, seed: u64, max_turns: u32) -> BattleResult {
+    let mut world = WorldState::new(scenario.build_map(), seed);
+    let mut order = Rng::new(seed);
+    for unit in scenario.units() {
+        world.spawn_units(unit);
+    }
+    let mut damage = [BTreeMap::new(), BTreeMap::new()];
+
+    let winner = 'battle: loop {
+        for faction in FACTIONS {
+            world.apply_command(Command::SetupTurn(faction));
+            while let Some(unit) = next_unit(&world, faction, &mut order) {
+                play_unit(&mut world, unit, &mut damage[side(faction)]);
+                if let Some(winner) = winner(&world) {
+                    break 'battle Some(winner);
+                }
+            }
+        }
+
+        if world.turn >= max_turns {
+            break None;
+        }
+    };
+
+    BattleResult {
+        winner,
+        turns: world.turn,
+        damage,
+        survivors: FACTIONS
+            .map(|faction| world.units().filter(|unit| unit.faction == faction).count()),
+    }
+}
+
+/// Random unmoved unit of the faction, picked from them in id order
+fn next_unit(world: &WorldState, faction: Faction, order: &mut Rng) -> Option<UnitId> {
+    let mut unmoved: Vec<UnitId> = world
+        .units()
+        .filter(|unit| unit.faction == faction && !unit.turn_complete)
+        .map(Unit::id)
+        .collect();
+    unmoved.sort_unstable_by_key(|id| **id);
+    order.choose(&unmoved).copied()
+}
+
+pub fn side(faction: Faction) -> usize {
+    match faction {
+        Faction::Player => 0,
+        Faction::Enemy => 1,
+    }
+}
+
+/// Same decisions `SimulatedManager` makes, without the animations in between
+fn play_unit(world: &mut WorldState, id: UnitId, damage: &mut BTreeMap<&'static str, i32>) {
+    let unit = world.get_unit(id).unwrap();
+    let dijkstra_map = DijkstraMap::new(world, unit);
+    let mut moved = unit.clone();
+    moved.pos = ai::choose_destination(world, unit, &dijkstra_map);
+
+    let mut commands = Vec::new();
+    if let Some(target) = ai::choose_target(world, &moved) {
+        let dealt = moved.attack_damage(target).clamp(0, target.curr_health);
+        *damage.entry(moved.class().name()).or_default() += dealt;
+        commands = combat::attack(&mut moved, target).commands;
+    }
+    moved.turn_complete = true;
+    commands.push(Command::CommitUnit(moved));
+
+    for command in commands {
+        world.apply_command(command);
+    }
+    // Nothing listens for the events, this only keeps them from piling up
+    world.evaluate_triggers();
+}
+
+fn winner(world: &WorldState) -> Option<Faction> {
+    let [player, enemy] = FACTIONS.map(|faction| world.units().any(|unit| unit.faction == faction));
+    match (player, enemy) {
+        (true, false) => Some(Faction::Player),
+        (false, true) => Some(Faction::Enemy),
+        _ => None,
+    }
+}
@@ -0,0 +1,116 @@
+#![warn(clippy::pedantic, clippy::all)]
+//! Runs many AI-vs-AI battles between two rosters to help balance classes and maps.
+//!
+//! ```text
+//! battle_sim <map.json> <roster_a.json> <roster_b.json>
+//!     [--battles N] [--seed S] [--max-turns T] [--format json|csv] [--output FILE]
+//! ```
+//!
+//! See the `data` directory for the file formats.
+
+mod battle;
+mod report;
+mod scenario;
+
+use luminara_core::rng::Rng;
+
+use report::Report;
+use scenario::Scenario;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Clone, Copy, Debug)]
+enum Format {
+    Json,
+    Csv,
+}
+
+#[derive(Debug)]
+struct Args {
+    map: PathBuf,
+    roster_a: PathBuf,
+    roster_b: PathBuf,
+    battles: u32,
+    seed: u64,
+    max_turns: u32,
+    format: Format,
+    output: Option<PathBuf>,
+}
+
+const USAGE: &str = "Usage: battle_sim <map.json> <roster_a.json> <roster_b.json> \
+[--battles N] [--seed S] [--max-turns T] [--format json|csv] [--output FILE]";
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut paths = Vec::new();
+        let mut battles = 100;
+        let mut seed = 0;
+        let mut max_turns = 50;
+        let mut format = Format::Json;
+        let mut output = None;
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+            match arg.as_str() {
+                "--battles" => battles = value()?.parse().map_err(|_| "Invalid --battles")?,
+                "--seed" => seed = value()?.parse().map_err(|_| "Invalid --seed")?,
+                "--max-turns" => max_turns = value()?.parse().map_err(|_| "Invalid --max-turns")?,
+                "--format" => {
+                    format = match value()?.as_str() {
+                        "json" => Format::Json,
+                        "csv" => Format::Csv,
+                        other => return Err(format!("Unknown format {other}")),
+                    }
+                }
+                "--output" => output = Some(PathBuf::from(value()?)),
+                flag if flag.starts_with("--") => return Err(format!("Unknown flag {flag}")),
+                _ => paths.push(PathBuf::from(arg)),
+            }
+        }
+
+        let [map, roster_a, roster_b] = <[PathBuf; 3]>::try_from(paths)
+            .map_err(|_| "Expected a map and two rosters".to_string())?;
+        Ok(Self {
+            map,
+            roster_a,
+            roster_b,
+            battles,
+            seed,
+            max_turns,
+            format,
+            output,
+        })
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let scenario = Scenario::load(&args.map, &args.roster_a, &args.roster_b)?;
+
+    let mut seeds = Rng::new(args.seed);
+    let results: Vec<_> = (0..args.battles)
+        .map(|_| battle::run(&scenario, seeds.next_u64(), args.max_turns))
+        .collect();
+
+    let report = Report::new(&results, args.seed, args.max_turns);
+    let text = match args.format {
+        Format::Json => report.to_json(),
+        Format::Csv => report.to_csv(),
+    };
+
+    if let Some(path) = &args.output {
+        return std::fs::write(path, text)
+            .map_err(|err| format!("Could not write {}: {err}", path.display()));
+    }
+    println!("{text}");
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let result = Args::parse(std::env::args().skip(1)).and_then(|args| run(&args));
+    if let Err(err) = result {
+        eprintln!("{err}\n{USAGE}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,107 @@
+use crate::battle::{BattleResult, side};
+
+use luminara_core::world::Faction;
+
+use serde::Serialize;
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Serialize, Debug)]
+pub struct Report {
+    pub battles: u32,
+    pub seed: u64,
+    pub max_turns: u32,
+    pub draws: u32,
+    pub average_turns: f64,
+    pub side_a: SideReport,
+    pub side_b: SideReport,
+}
+
+#[derive(Serialize, Default, Debug)]
+pub struct SideReport {
+    pub wins: u32,
+    pub win_rate: f64,
+    pub average_survivors: f64,
+    /// Damage all units of a class dealt, averaged over the battles
+    pub damage_per_class: BTreeMap<String, f64>,
+}
+
+impl Report {
+    pub fn new(results: &[BattleResult], seed: u64, max_turns: u32) -> Self {
+        let battles = u32::try_from(results.len()).unwrap();
+        let count = f64::from(battles.max(1));
+        let side_report = |faction: Faction| {
+            let wins = results
+                .iter()
+                .filter(|result| result.winner == Some(faction))
+                .count();
+            let survivors: usize = results
+                .iter()
+                .map(|result| result.survivors[side(faction)])
+                .sum();
+            let mut total_damage: BTreeMap<&str, i32> = BTreeMap::new();
+            for result in results {
+                for (class, damage) in &result.damage[side(faction)] {
+                    *total_damage.entry(class).or_default() += damage;
+                }
+            }
+            let damage_per_class = total_damage
+                .into_iter()
+                .map(|(class, damage)| (class.to_string(), f64::from(damage) / count))
+                .collect();
+
+            SideReport {
+                wins: u32::try_from(wins).unwrap(),
+                #[allow(clippy::cast_precision_loss)]
+                win_rate: wins as f64 / count,
+                #[allow(clippy::cast_precision_loss)]
+                average_survivors: survivors as f64 / count,
+                damage_per_class,
+            }
+        };
+
+        Self {
+            battles,
+            seed,
+            max_turns,
+            draws: u32::try_from(
+                results
+                    .iter()
+                    .filter(|result| result.winner.is_none())
+                    .count(),
+            )
+            .unwrap(),
+            average_turns: results
+                .iter()
+                .map(|result| f64::from(result.turns))
+                .sum::<f64>()
+                / count,
+            side_a: side_report(Faction::Player),
+            side_b: side_report(Faction::Enemy),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// One `side,metric,value` row per number, the side is empty for whole battle metrics
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("side,metric,value\n");
+        let _ = writeln!(csv, ",battles,{}", self.battles);
+        let _ = writeln!(csv, ",seed,{}", self.seed);
+        let _ = writeln!(csv, ",max_turns,{}", self.max_turns);
+        let _ = writeln!(csv, ",draws,{}", self.draws);
+        let _ = writeln!(csv, ",average_turns,{}", self.average_turns);
+        for (name, side) in [("a", &self.side_a), ("b", &self.side_b)] {
+            let _ = writeln!(csv, "{name},wins,{}", side.wins);
+            let _ = writeln!(csv, "{name},win_rate,{}", side.win_rate);
+            let _ = writeln!(csv, "{name},average_survivors,{}", side.average_survivors);
+            for (class, damage) in &side.damage_per_class {
+                let _ = writeln!(csv, "{name},damage.{class},{damage}");
+            }
+        }
+        csv
+    }
+}
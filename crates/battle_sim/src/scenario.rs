@@ -0,0 +1,136 @@
+use luminara_core::class::UnitClass;
+use luminara_core::grid::GridTopology;
+use luminara_core::map::Map;
+use luminara_core::math::Point;
+use luminara_core::pathfinding::DijkstraMap;
+use luminara_core::status::StatusEffect;
+use luminara_core::unit::{ErasedUnit, Item, Skill, Weapon, WeaponId, WeaponKind};
+use luminara_core::world::Faction;
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+use std::collections::HashSet;
+use std::path::Path;
+
+#[derive(Deserialize, Debug)]
+pub struct MapFile {
+    #[serde(default)]
+    pub topology: GridTopology,
+    #[serde(default)]
+    pub zone_of_control: bool,
+    /// One string per row, see [`luminara_core::map::Terrain::symbol`]
+    pub rows: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RosterUnit {
+    pub class: UnitClass,
+    pub pos: (i32, i32),
+    #[serde(default = "first_level")]
+    pub level: u32,
+    pub weapon: Option<WeaponSpec>,
+    #[serde(default)]
+    pub items: Vec<Item>,
+    #[serde(default)]
+    pub skills: Vec<Skill>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct WeaponSpec {
+    pub kind: WeaponKind,
+    pub might: i32,
+    pub range: i32,
+    pub effect: Option<StatusEffect>,
+}
+
+/// Everything needed to set up the same battle again and again
+#[derive(Debug)]
+pub struct Scenario {
+    map: MapFile,
+    units: Vec<ErasedUnit>,
+}
+
+fn first_level() -> u32 {
+    1
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|err| format!("Could not read {}: {err}", path.display()))?;
+    serde_json::from_str(&text).map_err(|err| format!("Could not parse {}: {err}", path.display()))
+}
+
+impl Scenario {
+    /// Roster `a` plays as [`Faction::Player`] and moves first, roster `b` as [`Faction::Enemy`]
+    /// Every unit has to stand on its own tile of the map, on terrain its class can cross
+    pub fn load(map: &Path, roster_a: &Path, roster_b: &Path) -> Result<Self, String> {
+        let map: MapFile = read_json(map)?;
+        let Some(built) = Map::from_ascii(&map.rows, map.topology) else {
+            return Err("Map rows must be equally long and only use . T ^ ~".to_string());
+        };
+
+        let mut weapon_id = WeaponId::new(0);
+        let mut taken = HashSet::new();
+        let mut units = Vec::new();
+        for (path, faction) in [(roster_a, Faction::Player), (roster_b, Faction::Enemy)] {
+            let roster: Vec<RosterUnit> = read_json(path)?;
+            for entry in roster {
+                let pos = entry.pos;
+                if !taken.insert(pos) {
+                    return Err(format!(
+                        "{}: {pos:?} is taken by another unit",
+                        path.display()
+                    ));
+                }
+                let unit = entry
+                    .into_erased(faction, &built, &mut weapon_id)
+                    .map_err(|err| format!("{}: {err}", path.display()))?;
+                units.push(unit);
+            }
+        }
+
+        Ok(Self { map, units })
+    }
+
+    pub fn build_map(&self) -> Map {
+        let mut map = Map::from_ascii(&self.map.rows, self.map.topology).unwrap();
+        map.zone_of_control = self.map.zone_of_control;
+        map
+    }
+
+    pub fn units(&self) -> &[ErasedUnit] {
+        &self.units
+    }
+}
+
+impl RosterUnit {
+    fn into_erased(
+        self,
+        faction: Faction,
+        map: &Map,
+        weapon_id: &mut WeaponId,
+    ) -> Result<ErasedUnit, String> {
+        let pos = Point::from(self.pos);
+        if !map.in_bounds(pos) {
+            return Err(format!("{:?} is off the map", self.pos));
+        }
+        let class = self.class.data();
+        if class.movement_class.get_movement_cost(map.get_terrain(pos)) == DijkstraMap::UNREACHABLE
+        {
+            return Err(format!("A {} can not stand on {:?}", class.name, self.pos));
+        }
+
+        let mut unit = ErasedUnit::with_class(self.class, faction, self.pos, "");
+        unit.level = self.level;
+        unit.weapon = self.weapon.map(|spec| {
+            let weapon = Weapon::new(*weapon_id, spec.kind, spec.might, spec.range);
+            weapon_id.next();
+            spec.effect
+                .map_or(weapon, |effect| weapon.with_effect(effect))
+        });
+        unit.items = self.items;
+        unit.skills = self.skills;
+        Ok(unit)
+    }
+}
@@ -0,0 +1,318 @@
+use crate::virtual_controller::{ButtonKind, Buttons, DPadButtons};
+
+use macroquad::input::KeyCode;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use std::fmt::Debug;
+
+/// Keys that can be bound, named by their `Debug` output
+const KEYS: [KeyCode; 63] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Backspace,
+    KeyCode::Tab,
+    KeyCode::Escape,
+    KeyCode::LeftShift,
+    KeyCode::RightShift,
+    KeyCode::LeftControl,
+    KeyCode::RightControl,
+    KeyCode::LeftAlt,
+    KeyCode::RightAlt,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Apostrophe,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::LeftBracket,
+    KeyCode::RightBracket,
+    KeyCode::Backslash,
+    KeyCode::Home,
+    KeyCode::End,
+];
+
+const PAD_PREFIX: &str = "Pad";
+
+/// Something the player physically presses
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Physical {
+    Key(KeyCode),
+    /// Button number of a gamepad, left to the gamepad's [`InputSource`](crate::InputSource)
+    Pad(u32),
+}
+
+impl Physical {
+    /// Every key that has a name, in the order a rebinding screen should look for them
+    pub fn keys() -> impl Iterator<Item = Self> {
+        KEYS.into_iter().map(Self::Key)
+    }
+
+    #[must_use]
+    pub fn name(self) -> String {
+        match self {
+            Self::Key(key) => format!("{key:?}"),
+            Self::Pad(button) => format!("{PAD_PREFIX}{button}"),
+        }
+    }
+
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        if let Some(button) = name.strip_prefix(PAD_PREFIX) {
+            return button.parse().ok().map(Self::Pad);
+        }
+        Self::keys().find(|key| key.name() == name)
+    }
+
+    fn same_kind(self, other: Self) -> bool {
+        std::mem::discriminant(&self) == std::mem::discriminant(&other)
+    }
+}
+
+/// What a physical input can be bound to, [`ButtonKind`] for the controller
+pub trait BindingTarget: Copy + Eq + Debug + 'static {
+    /// Every target, in the order a rebinding screen lists them
+    const ALL: &'static [Self];
+
+    fn name(self) -> &'static str;
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|target| target.name() == name)
+    }
+}
+
+impl BindingTarget for ButtonKind {
+    const ALL: &'static [Self] = &[
+        Self::DPad(DPadButtons::Up),
+        Self::DPad(DPadButtons::Down),
+        Self::DPad(DPadButtons::Left),
+        Self::DPad(DPadButtons::Right),
+        Self::Action(Buttons::A),
+        Self::Action(Buttons::B),
+        Self::Action(Buttons::X),
+        Self::Action(Buttons::Y),
+        Self::Action(Buttons::L),
+        Self::Action(Buttons::R),
+        Self::Action(Buttons::Start),
+        Self::Action(Buttons::Select),
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::DPad(DPadButtons::Up) => "Up",
+            Self::DPad(DPadButtons::Down) => "Down",
+            Self::DPad(DPadButtons::Left) => "Left",
+            Self::DPad(DPadButtons::Right) => "Right",
+            Self::Action(Buttons::A) => "A",
+            Self::Action(Buttons::B) => "B",
+            Self::Action(Buttons::X) => "X",
+            Self::Action(Buttons::Y) => "Y",
+            Self::Action(Buttons::L) => "L",
+            Self::Action(Buttons::R) => "R",
+            Self::Action(Buttons::Start) => "Start",
+            Self::Action(Buttons::Select) => "Select",
+        }
+    }
+}
+
+/// Table from physical inputs to targets. An input drives at most one target, a target can
+/// have several inputs. Saved as a list of `[input, target]` name pairs.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Bindings<T: BindingTarget> {
+    entries: Vec<(Physical, T)>,
+}
+
+impl<T: BindingTarget> Bindings<T> {
+    #[must_use]
+    pub fn new(entries: &[(Physical, T)]) -> Self {
+        let mut bindings = Self {
+            entries: Vec::with_capacity(entries.len()),
+        };
+        for (input, target) in entries {
+            let _ = bindings.bind(*target, *input);
+        }
+        bindings
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (Physical, T)> + '_ {
+        self.entries.iter().copied()
+    }
+
+    #[must_use]
+    pub fn target_of(&self, input: Physical) -> Option<T> {
+        self.entries
+            .iter()
+            .find(|(bound, _)| *bound == input)
+            .map(|(_, target)| *target)
+    }
+
+    pub fn inputs_of(&self, target: T) -> impl Iterator<Item = Physical> + '_ {
+        self.entries
+            .iter()
+            .filter(move |(_, bound)| *bound == target)
+            .map(|(input, _)| *input)
+    }
+
+    /// Adds `input` to the inputs of `target`
+    ///
+    /// # Errors
+    /// With the other target if `input` already drives one
+    pub fn bind(&mut self, target: T, input: Physical) -> Result<(), T> {
+        match self.target_of(input) {
+            Some(other) if other != target => Err(other),
+            Some(_) => Ok(()),
+            None => {
+                self.entries.push((input, target));
+                Ok(())
+            }
+        }
+    }
+
+    /// Makes `input` the only input of its kind for `target`
+    ///
+    /// # Errors
+    /// With the other target if `input` already drives one, nothing changes then
+    pub fn rebind(&mut self, target: T, input: Physical) -> Result<(), T> {
+        if let Some(other) = self.target_of(input).filter(|other| *other != target) {
+            return Err(other);
+        }
+        self.entries
+            .retain(|(bound, bound_target)| *bound_target != target || !bound.same_kind(input));
+        self.entries.push((input, target));
+        Ok(())
+    }
+
+    /// Rebinds `target` to `input` and hands the target's old input of that kind to whatever
+    /// `input` drove before
+    pub fn swap(&mut self, target: T, input: Physical) {
+        let old = self
+            .inputs_of(target)
+            .find(|bound| bound.same_kind(input) && *bound != input);
+        let displaced = self.target_of(input).filter(|other| *other != target);
+        self.entries.retain(|(bound, bound_target)| {
+            *bound != input && (*bound_target != target || !bound.same_kind(input))
+        });
+        self.entries.push((input, target));
+        if let (Some(old), Some(displaced)) = (old, displaced) {
+            self.entries.push((old, displaced));
+        }
+    }
+}
+
+impl Default for Bindings<ButtonKind> {
+    /// Arrows or WASD move and Z/X/C/V are A/B/X/Y, as in the terminal frontend. WASD takes A
+    /// and S, so X and Y sit on C and V. L/R are on Q/E and Start/Select on Enter/Backspace,
+    /// where the terminal uses `[`/`]` and `e`/`r`.
+    fn default() -> Self {
+        let key = |key, target| (Physical::Key(key), target);
+        Self::new(&[
+            key(KeyCode::Up, ButtonKind::DPad(DPadButtons::Up)),
+            key(KeyCode::Down, ButtonKind::DPad(DPadButtons::Down)),
+            key(KeyCode::Left, ButtonKind::DPad(DPadButtons::Left)),
+            key(KeyCode::Right, ButtonKind::DPad(DPadButtons::Right)),
+            key(KeyCode::W, ButtonKind::DPad(DPadButtons::Up)),
+            key(KeyCode::S, ButtonKind::DPad(DPadButtons::Down)),
+            key(KeyCode::A, ButtonKind::DPad(DPadButtons::Left)),
+            key(KeyCode::D, ButtonKind::DPad(DPadButtons::Right)),
+            key(KeyCode::Z, ButtonKind::Action(Buttons::A)),
+            key(KeyCode::X, ButtonKind::Action(Buttons::B)),
+            key(KeyCode::C, ButtonKind::Action(Buttons::X)),
+            key(KeyCode::V, ButtonKind::Action(Buttons::Y)),
+            key(KeyCode::Q, ButtonKind::Action(Buttons::L)),
+            key(KeyCode::E, ButtonKind::Action(Buttons::R)),
+            key(KeyCode::Enter, ButtonKind::Action(Buttons::Start)),
+            key(KeyCode::Backspace, ButtonKind::Action(Buttons::Select)),
+        ])
+    }
+}
+
+impl Serialize for Physical {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.name().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Physical {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Self::parse(&name).ok_or_else(|| D::Error::custom(format!("Unknown input {name}")))
+    }
+}
+
+impl<T: BindingTarget> Serialize for Bindings<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.entries
+            .iter()
+            .map(|(input, target)| (input.name(), target.name()))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+}
+
+impl<'de, T: BindingTarget> Deserialize<'de> for Bindings<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pairs = Vec::<(String, String)>::deserialize(deserializer)?;
+        let mut bindings = Self {
+            entries: Vec::with_capacity(pairs.len()),
+        };
+        for (input, target) in pairs {
+            let input = Physical::parse(&input)
+                .ok_or_else(|| D::Error::custom(format!("Unknown input {input}")))?;
+            let target = T::parse(&target)
+                .ok_or_else(|| D::Error::custom(format!("Unknown target {target}")))?;
+            bindings.bind(target, input).map_err(|other| {
+                D::Error::custom(format!(
+                    "{} drives both {} and {}",
+                    input.name(),
+                    other.name(),
+                    target.name()
+                ))
+            })?;
+        }
+        Ok(bindings)
+    }
+}
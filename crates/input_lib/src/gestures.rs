@@ -0,0 +1,128 @@
+use crate::virtual_controller::Controller;
+
+use macroquad::math::Vec2;
+
+/// How far a finger may wander, in pixels, before a tap becomes a drag
+const TAP_SLOP: f32 = 12.0;
+/// Zoom of one mouse wheel notch
+const WHEEL_ZOOM: f32 = 1.1;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Gesture {
+    /// A finger went down and up again without moving much
+    Tap(Vec2),
+    /// One finger moved this far since the last frame
+    Drag(Vec2),
+    /// Two fingers moved apart by `scale` since the last frame, around `center`
+    Pinch { center: Vec2, scale: f32 },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Pointer {
+    id: u64,
+    start: Vec2,
+    pos: Vec2,
+}
+
+/// Turns the fingers on the screen into taps, drags and pinches, in screen pixels. Fingers that
+/// come down on the on screen buttons belong to the controller and never get here.
+#[derive(Debug, Default)]
+pub struct Gestures {
+    pointers: Vec<Pointer>,
+    /// Once a finger drags or a second one joins, lifting it is no tap
+    dragging: bool,
+    /// What the fingers did this frame
+    this_frame: Vec<Gesture>,
+}
+
+impl Gestures {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the fingers the controller left over, and the mouse wheel
+    pub fn update(&mut self, controller: &Controller) {
+        self.feed(controller.pointers());
+        if let Some((center, notches)) = controller.wheel() {
+            self.this_frame.push(Gesture::Pinch {
+                center,
+                scale: WHEEL_ZOOM.powf(notches.signum()),
+            });
+        }
+    }
+
+    /// Advances with the fingers currently down, for input that does not come from the screen
+    pub fn feed(&mut self, down: &[(u64, Vec2)]) {
+        self.this_frame.clear();
+        let last = self.pointers.clone();
+        if down.is_empty() {
+            if let [pointer] = last.as_slice()
+                && !self.dragging
+            {
+                self.this_frame.push(Gesture::Tap(pointer.pos));
+            }
+            self.pointers.clear();
+            self.dragging = false;
+            return;
+        }
+
+        self.pointers = down
+            .iter()
+            .map(|(id, pos)| {
+                let start = last
+                    .iter()
+                    .find(|pointer| pointer.id == *id)
+                    .map_or(*pos, |pointer| pointer.start);
+                Pointer {
+                    id: *id,
+                    start,
+                    pos: *pos,
+                }
+            })
+            .collect();
+        let moved = |pointer: &Pointer| {
+            last.iter()
+                .find(|old| old.id == pointer.id)
+                .map(|old| (old.pos, pointer.pos))
+        };
+
+        match self.pointers.as_slice() {
+            [pointer] => {
+                if pointer.pos.distance(pointer.start) > TAP_SLOP {
+                    self.dragging = true;
+                }
+                if self.dragging
+                    && let Some((from, to)) = moved(pointer)
+                    && from != to
+                {
+                    self.this_frame.push(Gesture::Drag(to - from));
+                }
+            }
+            [first, second, ..] => {
+                self.dragging = true;
+                if let (Some((first_from, first_to)), Some((second_from, second_to))) =
+                    (moved(first), moved(second))
+                {
+                    let (before, after) = (
+                        first_from.distance(second_from),
+                        first_to.distance(second_to),
+                    );
+                    if before > 0.0 && (after - before).abs() > f32::EPSILON {
+                        self.this_frame.push(Gesture::Pinch {
+                            center: (first_to + second_to) / 2.0,
+                            scale: after / before,
+                        });
+                    }
+                }
+            }
+            [] => {}
+        }
+    }
+
+    /// What the fingers did this frame
+    #[must_use]
+    pub fn gestures(&self) -> &[Gesture] {
+        &self.this_frame
+    }
+}
@@ -0,0 +1,205 @@
+use crate::bindings::BindingTarget;
+use crate::virtual_controller::{ButtonKind, Buttons, DPadButtons};
+
+use macroquad::math::{Rect, Vec2};
+use serde::{Deserialize, Serialize};
+
+/// Button size at a scale of 1, as a fraction of the longer screen side
+const BUTTON_SIZE: f32 = 0.05;
+pub const MIN_SCALE: f32 = 0.5;
+pub const MAX_SCALE: f32 = 2.0;
+pub const MIN_OPACITY: f32 = 0.2;
+
+/// Buttons that move together in the layout editor
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Cluster {
+    /// The d-pad and the shoulder button above it
+    DPad,
+    /// A, B, X, Y and the shoulder button above them
+    Actions,
+    /// Start and Select
+    Bar,
+}
+
+impl Cluster {
+    pub const ALL: [Self; 3] = [Self::DPad, Self::Actions, Self::Bar];
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::DPad => "D-pad",
+            Self::Actions => "Buttons",
+            Self::Bar => "Start/Select",
+        }
+    }
+}
+
+/// Where the on screen buttons go. Positions are cluster centres as fractions of the screen, so
+/// one layout fits every screen size. Missing fields fall back to their defaults.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Layout {
+    pub dpad: [f32; 2],
+    pub actions: [f32; 2],
+    pub bar: [f32; 2],
+    /// Button size relative to the default one
+    pub scale: f32,
+    pub opacity: f32,
+    /// Mirrors the positions, putting the d-pad on the right. L and R stay on their sides.
+    pub left_handed: bool,
+    /// Hides the buttons once a key or gamepad is used, until the screen is touched again
+    pub auto_hide: bool,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            dpad: [0.135, 0.8],
+            actions: [0.865, 0.8],
+            bar: [0.5, 0.915],
+            scale: 1.0,
+            opacity: 1.0,
+            left_handed: false,
+            auto_hide: true,
+        }
+    }
+}
+
+impl Layout {
+    /// Centre of the cluster on the screen, as a fraction of it
+    #[must_use]
+    pub fn center(&self, cluster: Cluster) -> Vec2 {
+        let [x, y] = self.stored(cluster);
+        let x = if self.left_handed { 1.0 - x } else { x };
+        Vec2::new(x, y)
+    }
+
+    /// Moves the cluster by `delta` fractions of the screen, as seen on it
+    pub fn move_cluster(&mut self, cluster: Cluster, delta: Vec2) {
+        let dx = if self.left_handed { -delta.x } else { delta.x };
+        let [x, y] = self.stored_mut(cluster);
+        *x = (*x + dx).clamp(0.0, 1.0);
+        *y = (*y + delta.y).clamp(0.0, 1.0);
+    }
+
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.clamp(MIN_SCALE, MAX_SCALE);
+    }
+
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity.clamp(MIN_OPACITY, 1.0);
+    }
+
+    /// Puts the scale and opacity back in range and every cluster centre on the screen, for
+    /// layouts read from a file. Values that are not numbers fall back to the defaults.
+    #[must_use]
+    pub fn sanitized(mut self) -> Self {
+        let default = Self::default();
+        let or_default = |value: f32, default: f32| if value.is_nan() { default } else { value };
+        self.set_scale(or_default(self.scale, default.scale));
+        self.set_opacity(or_default(self.opacity, default.opacity));
+        for cluster in Cluster::ALL {
+            let fallback = default.stored(cluster);
+            for (value, fallback) in self.stored_mut(cluster).iter_mut().zip(fallback) {
+                *value = or_default(*value, fallback).clamp(0.0, 1.0);
+            }
+        }
+        self
+    }
+
+    /// The cluster the button moves with, shoulders go with the cluster below them
+    #[must_use]
+    pub fn cluster_of(&self, kind: ButtonKind) -> Cluster {
+        match kind {
+            ButtonKind::DPad(_) => Cluster::DPad,
+            ButtonKind::Action(Buttons::Start | Buttons::Select) => Cluster::Bar,
+            ButtonKind::Action(Buttons::L) if !self.left_handed => Cluster::DPad,
+            ButtonKind::Action(Buttons::R) if self.left_handed => Cluster::DPad,
+            ButtonKind::Action(_) => Cluster::Actions,
+        }
+    }
+
+    /// Where the button goes on a screen of this size, in pixels
+    #[must_use]
+    pub fn rect(&self, kind: ButtonKind, screen: Vec2) -> Rect {
+        let btn = screen.max_element() * BUTTON_SIZE * self.scale;
+        let (bar_w, bar_h) = (btn * 1.5, btn * 0.75);
+        // Top left corner of the middle of the cluster
+        let middle = |cluster| self.center(cluster) * screen - btn / 2.0;
+        let square = |pos: Vec2| Rect::new(pos.x, pos.y, btn, btn);
+        let (dpad, actions) = (middle(Cluster::DPad), middle(Cluster::Actions));
+        let bar = self.center(Cluster::Bar) * screen;
+        let (left, right) = if self.left_handed {
+            (actions, dpad)
+        } else {
+            (dpad, actions)
+        };
+
+        match kind {
+            ButtonKind::DPad(DPadButtons::Up) => square(dpad - Vec2::Y * btn),
+            ButtonKind::DPad(DPadButtons::Down) => square(dpad + Vec2::Y * btn),
+            ButtonKind::DPad(DPadButtons::Left) => square(dpad - Vec2::X * btn),
+            ButtonKind::DPad(DPadButtons::Right) => square(dpad + Vec2::X * btn),
+
+            ButtonKind::Action(Buttons::A) => square(actions + Vec2::X * btn),
+            ButtonKind::Action(Buttons::B) => square(actions + Vec2::Y * btn),
+            ButtonKind::Action(Buttons::X) => square(actions - Vec2::Y * btn),
+            ButtonKind::Action(Buttons::Y) => square(actions - Vec2::X * btn),
+
+            ButtonKind::Action(Buttons::Start) => {
+                Rect::new(bar.x - bar_w * 1.5, bar.y - bar_h / 2.0, bar_w, bar_h)
+            }
+            ButtonKind::Action(Buttons::Select) => {
+                Rect::new(bar.x + bar_w * 0.5, bar.y - bar_h / 2.0, bar_w, bar_h)
+            }
+
+            // Shoulders sit above the clusters, L on the left and R on the right like on a
+            // handheld
+            ButtonKind::Action(Buttons::L) => {
+                Rect::new(left.x - btn, left.y - btn * 3.0, bar_w, bar_h)
+            }
+            ButtonKind::Action(Buttons::R) => Rect::new(
+                right.x + btn * 2.0 - bar_w,
+                right.y - btn * 3.0,
+                bar_w,
+                bar_h,
+            ),
+        }
+    }
+
+    /// Smallest rectangle around every button of the cluster, in pixels
+    #[must_use]
+    pub fn cluster_rect(&self, cluster: Cluster, screen: Vec2) -> Rect {
+        ButtonKind::ALL
+            .iter()
+            .filter(|kind| self.cluster_of(**kind) == cluster)
+            .map(|kind| self.rect(*kind, screen))
+            .reduce(Rect::combine_with)
+            .unwrap_or_default()
+    }
+
+    /// The cluster with a button at this screen position
+    #[must_use]
+    pub fn cluster_at(&self, pos: Vec2, screen: Vec2) -> Option<Cluster> {
+        ButtonKind::ALL
+            .iter()
+            .find(|kind| self.rect(**kind, screen).contains(pos))
+            .map(|kind| self.cluster_of(*kind))
+    }
+
+    fn stored(&self, cluster: Cluster) -> [f32; 2] {
+        match cluster {
+            Cluster::DPad => self.dpad,
+            Cluster::Actions => self.actions,
+            Cluster::Bar => self.bar,
+        }
+    }
+
+    fn stored_mut(&mut self, cluster: Cluster) -> &mut [f32; 2] {
+        match cluster {
+            Cluster::DPad => &mut self.dpad,
+            Cluster::Actions => &mut self.actions,
+            Cluster::Bar => &mut self.bar,
+        }
+    }
+}
@@ -1,8 +1,27 @@
-#[warn(clippy::all, clippy::pedantic)]
+#![warn(clippy::all, clippy::pedantic)]
+mod bindings;
+mod gestures;
+mod layout;
+mod recording;
+mod source;
 mod virtual_controller;
 
+pub use self::bindings::BindingTarget;
+pub use self::bindings::Bindings;
+pub use self::bindings::Physical;
+pub use self::gestures::Gesture;
+pub use self::gestures::Gestures;
+pub use self::layout::Cluster;
+pub use self::layout::Layout;
+pub use self::layout::{MAX_SCALE, MIN_OPACITY, MIN_SCALE};
+pub use self::recording::Frame;
+pub use self::source::InputSource;
+pub use self::source::Keyboard;
+
+pub use self::virtual_controller::ButtonKind;
 pub use self::virtual_controller::ButtonState;
 pub use self::virtual_controller::Buttons;
 pub use self::virtual_controller::Controller;
+pub use self::virtual_controller::DPadButtons;
 
 pub use enumset;
@@ -0,0 +1,44 @@
+use crate::bindings::Physical;
+use crate::virtual_controller::ButtonState;
+
+use macroquad::math::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Everything the controller read in one frame, enough to play the frame back without a
+/// screen or keyboard. See [`Controller::frame`](crate::Controller::frame) and
+/// [`Controller::play`](crate::Controller::play).
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Frame {
+    pub state: ButtonState,
+    pub frame_time: f32,
+    /// Physical input that went down, for rebinding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pressed: Option<Physical>,
+    /// Fingers on the screen that are not on a button, in pixels
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pointers: Vec<(u64, [f32; 2])>,
+    /// Where the mouse was and how far its wheel turned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wheel: Option<([f32; 2], f32)>,
+}
+
+impl Frame {
+    /// The frame as a screen of size `to` would have read it, pointers keep their place relative
+    /// to the screen. Unchanged if `from` is not a size.
+    #[must_use]
+    pub fn rescaled(&self, from: Vec2, to: Vec2) -> Self {
+        if from.min_element() <= 0.0 || from == to {
+            return self.clone();
+        }
+        let scale = |pos: [f32; 2]| (Vec2::from_array(pos) * to / from).to_array();
+        Self {
+            pointers: self
+                .pointers
+                .iter()
+                .map(|(id, pos)| (*id, scale(*pos)))
+                .collect(),
+            wheel: self.wheel.map(|(pos, notches)| (scale(pos), notches)),
+            ..self.clone()
+        }
+    }
+}
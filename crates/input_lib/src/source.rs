@@ -0,0 +1,65 @@
+use crate::bindings::{Bindings, Physical};
+use crate::virtual_controller::{ButtonKind, ButtonState};
+
+use macroquad::input::{is_key_down, is_key_pressed};
+
+/// Anything that presses buttons besides the on screen ones. The controller polls every source
+/// once per frame and merges what they report, so a gamepad backend only has to implement this
+/// and be handed to [`Controller::add_source`](crate::Controller::add_source).
+pub trait InputSource {
+    fn poll(&mut self) -> ButtonState;
+}
+
+/// Physical keys through a rebindable table
+#[derive(Debug, Default)]
+pub struct Keyboard {
+    bindings: Bindings<ButtonKind>,
+    /// First nameable key that went down this frame
+    pressed: Option<Physical>,
+}
+
+impl Keyboard {
+    #[must_use]
+    pub fn new(bindings: Bindings<ButtonKind>) -> Self {
+        Self {
+            bindings,
+            pressed: None,
+        }
+    }
+
+    #[must_use]
+    pub fn bindings(&self) -> &Bindings<ButtonKind> {
+        &self.bindings
+    }
+
+    pub fn set_bindings(&mut self, bindings: Bindings<ButtonKind>) {
+        self.bindings = bindings;
+    }
+
+    #[must_use]
+    pub fn pressed(&self) -> Option<Physical> {
+        self.pressed
+    }
+}
+
+impl InputSource for Keyboard {
+    fn poll(&mut self) -> ButtonState {
+        self.pressed = Physical::keys().find(|input| match input {
+            Physical::Key(key) => is_key_pressed(*key),
+            Physical::Pad(_) => false,
+        });
+
+        let mut state = ButtonState::default();
+        for (input, target) in self.bindings.entries() {
+            if let Physical::Key(key) = input
+                && is_key_down(key)
+            {
+                state.set(target);
+            }
+        }
+        // Arrows and WASD held together still move one tile
+        state.dpad_x = state.dpad_x.signum();
+        state.dpad_y = state.dpad_y.signum();
+        state
+    }
+}
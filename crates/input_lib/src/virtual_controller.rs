@@ -2,6 +2,14 @@ use enumset::EnumSet;
 use enumset::EnumSetType;
 use macroquad::prelude::*;
 use macroquad_ex_ui::XButton;
+use serde::{Deserialize, Serialize};
+
+use std::collections::VecDeque;
+
+use crate::bindings::{Bindings, Physical};
+use crate::layout::Layout;
+use crate::recording::Frame;
+use crate::source::{InputSource, Keyboard};
 
 #[derive(EnumSetType, Debug)]
 pub enum Buttons {
@@ -11,9 +19,11 @@ pub enum Buttons {
     Y,
     Start,
     Select,
+    L,
+    R,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum DPadButtons {
     Left,
     Right,
@@ -21,13 +31,13 @@ pub enum DPadButtons {
     Down,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum ButtonKind {
     DPad(DPadButtons),
     Action(Buttons),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ButtonState {
     pub dpad_x: i32,
     pub dpad_y: i32,
@@ -44,13 +54,7 @@ impl ButtonState {
         }
     }
 
-    fn reset(&mut self) {
-        self.dpad_x = 0;
-        self.dpad_y = 0;
-        self.buttons.clear();
-    }
-
-    fn set(&mut self, button: ButtonKind) {
+    pub fn set(&mut self, button: ButtonKind) {
         match button {
             ButtonKind::DPad(dpad_buttons) => match dpad_buttons {
                 DPadButtons::Left => self.dpad_x -= 1,
@@ -65,33 +69,80 @@ impl ButtonState {
     }
 }
 
+impl ButtonState {
+    /// Everything either state presses, opposite directions cancel out
+    #[must_use]
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            dpad_x: (self.dpad_x + other.dpad_x).signum(),
+            dpad_y: (self.dpad_y + other.dpad_y).signum(),
+            buttons: self.buttons | other.buttons,
+        }
+    }
+}
+
 impl Default for ButtonState {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// The mouse pretends to be a finger with this id when nothing touches the screen
+const MOUSE_ID: u64 = u64::MAX;
+
 const INITIAL_DELAY: f32 = 0.25;
 const REPEAT_DELAY: f32 = 0.06;
 pub struct Controller {
     // TODO This should probably be a Vec or HashMap
-    buttons: [(XButton, ButtonKind); 10],
+    buttons: [(XButton, ButtonKind); 12],
+    keyboard: Keyboard,
+    /// Polled along with the on screen buttons and the keyboard
+    sources: Vec<Box<dyn InputSource>>,
+    /// Physical input that went down this frame, for rebinding
+    pressed_input: Option<Physical>,
+    layout: Layout,
+    /// Hidden after a key or gamepad press, see [`Layout::auto_hide`]
+    hidden: bool,
+    editing: bool,
+    /// Fingers that are not on a button, see [`Controller::pointers`]
+    pointers: Vec<(u64, Vec2)>,
+    /// Fingers that came down on a button, they belong to it until they are lifted
+    claimed: Vec<u64>,
+    wheel: Option<(Vec2, f32)>,
+    /// Frames fed instead of the devices, see [`Controller::play`]
+    playback: VecDeque<Frame>,
+    /// Screen size the played frames were recorded on
+    playback_screen: Vec2,
     button_state: ButtonState,
     last_state: ButtonState,
     screen_width: f32,
     screen_height: f32,
     timer: f32,
+    frame_time: f32,
 }
 
 impl Controller {
     #[must_use]
     pub fn new() -> Self {
-        let mut controller = Self {
+        // Buttons are laid out on the first update so this works without a window
+        Self {
             screen_width: 0.0,
             screen_height: 0.0,
             timer: 0.0,
+            frame_time: 0.0,
             button_state: ButtonState::new(),
             last_state: ButtonState::new(),
+            keyboard: Keyboard::default(),
+            sources: Vec::new(),
+            pressed_input: None,
+            layout: Layout::default(),
+            hidden: false,
+            editing: false,
+            pointers: Vec::new(),
+            claimed: Vec::new(),
+            wheel: None,
+            playback: VecDeque::new(),
+            playback_screen: Vec2::ZERO,
             buttons: [
                 (xbutton("↑"), ButtonKind::DPad(DPadButtons::Up)),
                 (xbutton("↓"), ButtonKind::DPad(DPadButtons::Down)),
@@ -103,76 +154,25 @@ impl Controller {
                 (xbutton("Y"), ButtonKind::Action(Buttons::Y)),
                 (xbutton("Start"), ButtonKind::Action(Buttons::Start)),
                 (xbutton("Select"), ButtonKind::Action(Buttons::Select)),
+                (xbutton("L"), ButtonKind::Action(Buttons::L)),
+                (xbutton("R"), ButtonKind::Action(Buttons::R)),
             ],
-        };
-
-        controller.resize();
-        controller
+        }
     }
 
     fn resize(&mut self) {
         info!("Controller resize requested.");
 
-        let sw = screen_width();
-        let sh = screen_height();
-        self.screen_width = sw;
-        self.screen_height = sh;
-
-        let btn_size = f32::max(sh, sw) * 0.05;
-        let bar_btn_size = (btn_size * 1.5, btn_size * 0.75);
-
-        let (dpad_x, dpad_y) = (sw * 0.06 + btn_size, sh * 0.85 - btn_size);
-        let (act_x, act_y) = (sw * 0.94 - 2.0 * btn_size, sh * 0.85 - btn_size);
-        let (bar_x, bar_y) = (sw * 0.5, sh * 0.95 - bar_btn_size.1);
-
+        self.screen_width = screen_width();
+        self.screen_height = screen_height();
+        let screen = self.screen_size();
+        let color = Color {
+            a: self.layout.opacity,
+            ..RED
+        };
         for (btn, kind) in &mut self.buttons {
-            match kind {
-                ButtonKind::DPad(DPadButtons::Up) => {
-                    btn.rect = Rect::new(dpad_x, dpad_y - btn_size, btn_size, btn_size);
-                }
-                ButtonKind::DPad(DPadButtons::Down) => {
-                    btn.rect = Rect::new(dpad_x, dpad_y + btn_size, btn_size, btn_size);
-                }
-                ButtonKind::DPad(DPadButtons::Left) => {
-                    btn.rect = Rect::new(dpad_x - btn_size, dpad_y, btn_size, btn_size);
-                }
-                ButtonKind::DPad(DPadButtons::Right) => {
-                    btn.rect = Rect::new(dpad_x + btn_size, dpad_y, btn_size, btn_size);
-                }
-
-                ButtonKind::Action(Buttons::A) => {
-                    btn.rect = Rect::new(act_x + btn_size, act_y, btn_size, btn_size);
-                }
-                ButtonKind::Action(Buttons::B) => {
-                    btn.rect = Rect::new(act_x, act_y + btn_size, btn_size, btn_size);
-                }
-
-                ButtonKind::Action(Buttons::X) => {
-                    btn.rect = Rect::new(act_x, act_y - btn_size, btn_size, btn_size);
-                }
-
-                ButtonKind::Action(Buttons::Y) => {
-                    btn.rect = Rect::new(act_x - btn_size, act_y, btn_size, btn_size);
-                }
-
-                ButtonKind::Action(Buttons::Start) => {
-                    btn.rect = Rect::new(
-                        bar_x - bar_btn_size.0 * 1.5,
-                        bar_y,
-                        bar_btn_size.0,
-                        bar_btn_size.1,
-                    );
-                }
-
-                ButtonKind::Action(Buttons::Select) => {
-                    btn.rect = Rect::new(
-                        bar_x + bar_btn_size.0 * 0.5,
-                        bar_y,
-                        bar_btn_size.0,
-                        bar_btn_size.1,
-                    );
-                }
-            }
+            btn.rect = self.layout.rect(*kind, screen);
+            btn.color = color;
         }
     }
 
@@ -182,20 +182,189 @@ impl Controller {
         {
             self.resize();
         }
+        if let Some(frame) = self.playback.pop_front() {
+            self.feed_frame(&frame.rescaled(self.playback_screen, self.screen_size()));
+            return;
+        }
 
-        self.last_state = self.button_state;
-        self.button_state.reset();
+        self.read_pointers();
+        let mut physical = self.keyboard.poll();
+        for source in &mut self.sources {
+            physical = physical.merge(source.poll());
+        }
+        if self.pointers.iter().any(|(id, _)| *id != MOUSE_ID) {
+            self.hidden = false;
+        } else if self.layout.auto_hide
+            && (physical != ButtonState::default() || self.keyboard.pressed().is_some())
+        {
+            self.hidden = true;
+        }
+
+        let mut state = ButtonState::new();
         for (btn, flag) in &mut self.buttons {
+            // Buttons being moved around must not press
+            if self.editing || self.hidden {
+                continue;
+            }
             btn.update();
             if btn.is_pressed() {
-                self.button_state.set(*flag);
+                state.set(*flag);
             }
         }
 
+        self.feed(state.merge(physical), get_frame_time());
+        self.pressed_input = self.keyboard.pressed();
+    }
+
+    /// Splits the fingers between the buttons and everything else
+    fn read_pointers(&mut self) {
+        let mut down: Vec<(u64, Vec2)> = touches()
+            .iter()
+            .filter(|touch| !matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled))
+            .map(|touch| (touch.id, touch.position))
+            .collect();
+        if down.is_empty() && is_mouse_button_down(MouseButton::Left) {
+            down.push((MOUSE_ID, mouse_position().into()));
+        }
+
+        self.claimed
+            .retain(|id| down.iter().any(|(down_id, _)| down_id == id));
+        let new_on_button: Vec<u64> = down
+            .iter()
+            .filter(|(id, pos)| {
+                !self.pointers.iter().any(|(known, _)| known == id) && self.covers(*pos)
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        self.claimed.extend(new_on_button);
+        down.retain(|(id, _)| !self.claimed.contains(id));
+        self.pointers = down;
+
+        let wheel = mouse_wheel().1;
+        self.wheel = (wheel != 0.0).then(|| (mouse_position().into(), wheel));
+    }
+
+    /// Merges another source, like a gamepad, into the state read by every update
+    pub fn add_source(&mut self, source: Box<dyn InputSource>) {
+        self.sources.push(source);
+    }
+
+    #[must_use]
+    pub fn bindings(&self) -> &Bindings<ButtonKind> {
+        self.keyboard.bindings()
+    }
+
+    pub fn set_bindings(&mut self, bindings: Bindings<ButtonKind>) {
+        self.keyboard.set_bindings(bindings);
+    }
+
+    #[must_use]
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Lays the buttons out again on the next update
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout.sanitized();
+        self.screen_width = 0.0;
+        self.screen_height = 0.0;
+    }
+
+    /// While editing, the buttons are always shown and never press, fingers on them are left to
+    /// the layout editor
+    pub fn set_editing(&mut self, editing: bool) {
+        self.editing = editing;
+    }
+
+    #[must_use]
+    pub fn screen_size(&self) -> Vec2 {
+        Vec2::new(self.screen_width, self.screen_height)
+    }
+
+    /// Reports a physical input for the frame last fed, for frontends without a keyboard poll
+    pub fn feed_pressed(&mut self, input: Physical) {
+        self.pressed_input = Some(input);
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn pressed_input(&self) -> Option<Physical> {
+        self.pressed_input
+    }
+
+    /// What this frame read, for recording
+    #[must_use]
+    pub fn frame(&self) -> Frame {
+        Frame {
+            state: self.button_state,
+            frame_time: self.frame_time,
+            pressed: self.pressed_input,
+            pointers: self
+                .pointers
+                .iter()
+                .map(|(id, pos)| (*id, pos.to_array()))
+                .collect(),
+            wheel: self.wheel.map(|(pos, notches)| (pos.to_array(), notches)),
+        }
+    }
+
+    /// Advances the controller with a recorded frame, needs no window
+    pub fn feed_frame(&mut self, frame: &Frame) {
+        self.feed(frame.state, frame.frame_time);
+        self.pressed_input = frame.pressed;
+        self.pointers = frame
+            .pointers
+            .iter()
+            .map(|(id, pos)| (*id, Vec2::from_array(*pos)))
+            .collect();
+        self.wheel = frame
+            .wheel
+            .map(|(pos, notches)| (Vec2::from_array(pos), notches));
+    }
+
+    /// Feeds these frames on the next updates instead of reading the screen and keyboard, then
+    /// goes back to them. Pointers are moved from the `screen` the frames were recorded on to the
+    /// current one.
+    pub fn play(&mut self, frames: impl IntoIterator<Item = Frame>, screen: Vec2) {
+        self.playback.extend(frames);
+        self.playback_screen = screen;
+    }
+
+    #[must_use]
+    pub fn is_playing(&self) -> bool {
+        !self.playback.is_empty()
+    }
+
+    /// Fingers on the screen that are not on a button, in pixels. The mouse counts as one
+    /// finger while nothing touches the screen.
+    #[must_use]
+    pub fn pointers(&self) -> &[(u64, Vec2)] {
+        &self.pointers
+    }
+
+    /// Where the mouse was when its wheel turned, and by how much
+    #[must_use]
+    pub fn wheel(&self) -> Option<(Vec2, f32)> {
+        self.wheel
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn frame_time(&self) -> f32 {
+        self.frame_time
+    }
+
+    /// Advances the controller with input from somewhere other than the on screen buttons
+    pub fn feed(&mut self, state: ButtonState, frame_time: f32) {
+        self.last_state = self.button_state;
+        self.button_state = state;
+        self.frame_time = frame_time;
+        self.pressed_input = None;
+
         if self.button_state == ButtonState::default() || self.button_state != self.last_state {
             self.timer = 0.0;
         } else {
-            self.timer += get_frame_time();
+            self.timer += frame_time;
         }
     }
 
@@ -210,7 +379,7 @@ impl Controller {
     pub fn timed_hold(&self) -> ButtonState {
         if self.button_state != self.last_state
             || (self.timer > INITIAL_DELAY
-                && (self.timer - INITIAL_DELAY) % REPEAT_DELAY < get_frame_time())
+                && (self.timer - INITIAL_DELAY) % REPEAT_DELAY < self.frame_time)
         {
             return self.button_state;
         }
@@ -229,8 +398,17 @@ impl Controller {
         self.button_state.buttons.contains(button) && !self.last_state.buttons.contains(button)
     }
 
+    /// Whether an on screen button is at this screen position
+    #[must_use]
+    pub fn covers(&self, pos: Vec2) -> bool {
+        !self.editing && !self.hidden && self.buttons.iter().any(|(btn, _)| btn.rect.contains(pos))
+    }
+
     #[inline]
     pub fn draw(&self, font: Option<&Font>) {
+        if self.hidden && !self.editing {
+            return;
+        }
         for (btn, _) in &self.buttons {
             btn.draw(font);
         }
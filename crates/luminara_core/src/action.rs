@@ -0,0 +1,110 @@
+use crate::math::Point;
+use crate::pathfinding::DijkstraMap;
+use crate::status::StatusKind;
+use crate::unit::{Unit, UnitId};
+use crate::world::{Faction, WorldState};
+
+use serde::{Deserialize, Serialize};
+
+/// A complete decision for one unit, or for the whole phase
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    /// Moves the unit and ends its turn there
+    Move { unit: UnitId, to: Point },
+    /// Ends the unit's turn where it stands
+    Wait { unit: UnitId },
+    /// Moves the unit to `to`, which may be where it stands, and attacks from there
+    Attack {
+        unit: UnitId,
+        to: Point,
+        target: UnitId,
+    },
+    /// Ends the turn of every unit that has not acted yet
+    EndTurn,
+}
+
+impl Action {
+    pub fn unit(self) -> Option<UnitId> {
+        match self {
+            Self::Move { unit, .. } | Self::Wait { unit } | Self::Attack { unit, .. } => Some(unit),
+            Self::EndTurn => None,
+        }
+    }
+}
+
+/// Units of `faction` that still have to act this phase, berserk units act on their own
+pub fn controllable_units(world: &WorldState, faction: Faction) -> impl Iterator<Item = &Unit> {
+    world.units().filter(move |unit| {
+        unit.faction == faction && !unit.turn_complete && !unit.has_status(StatusKind::Berserk)
+    })
+}
+
+/// Every action `faction` may take right now, ordered by unit id and then by tile
+pub fn legal_actions(world: &WorldState, faction: Faction) -> Vec<Action> {
+    let mut units: Vec<&Unit> = controllable_units(world, faction).collect();
+    units.sort_by_key(|unit| *unit.id());
+
+    let mut actions = Vec::new();
+    for unit in units {
+        actions.extend(unit_actions(world, unit));
+    }
+    actions.push(Action::EndTurn);
+
+    actions
+}
+
+fn unit_actions(world: &WorldState, unit: &Unit) -> Vec<Action> {
+    let dijkstra_map = DijkstraMap::new(world, unit);
+    let mut reachables: Vec<Point> = dijkstra_map.get_reachables().iter().copied().collect();
+    reachables.sort();
+
+    let mut actions = vec![Action::Wait { unit: unit.id() }];
+    for to in &reachables {
+        if *to != unit.pos {
+            actions.push(Action::Move {
+                unit: unit.id(),
+                to: *to,
+            });
+        }
+    }
+    for to in reachables {
+        let mut targets: Vec<UnitId> = world
+            .units_in_range(to, unit.get_attack_range())
+            .filter(|other| other.id() != unit.id() && other.is_hostile_to(unit))
+            .map(Unit::id)
+            .collect();
+        targets.sort_by_key(|id| **id);
+        actions.extend(targets.into_iter().map(|target| Action::Attack {
+            unit: unit.id(),
+            to,
+            target,
+        }));
+    }
+
+    actions
+}
+
+/// Checks the action against the rules without listing every other choice
+pub fn is_legal(world: &WorldState, faction: Faction, action: Action) -> bool {
+    let Some(id) = action.unit() else {
+        return true;
+    };
+    let Some(unit) = controllable_units(world, faction).find(|unit| unit.id() == id) else {
+        return false;
+    };
+
+    match action {
+        Action::Wait { .. } | Action::EndTurn => true,
+        Action::Move { to, .. } => DijkstraMap::new(world, unit).get_reachables().contains(&to),
+        Action::Attack { to, target, .. } => {
+            DijkstraMap::new(world, unit).get_reachables().contains(&to)
+                && world.get_unit(target).is_some_and(|target| {
+                    let distance = world.map.topology.distance(to, target.pos);
+                    target.id() != unit.id()
+                        && target.is_hostile_to(unit)
+                        && (1..=unit.get_attack_range()).contains(&distance)
+                })
+        }
+    }
+}
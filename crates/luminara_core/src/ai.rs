@@ -0,0 +1,38 @@
+use crate::math::Point;
+use crate::pathfinding::DijkstraMap;
+use crate::rng::Rng;
+use crate::unit::Unit;
+use crate::world::WorldState;
+
+/// First reachable tile next to a hostile unit, or a random reachable one if there is none.
+/// Hostiles are taken in id order and the random pick is seeded from the world, so the choice
+/// does not depend on hashing order.
+pub fn choose_destination(world: &WorldState, unit: &Unit, dijkstra_map: &DijkstraMap) -> Point {
+    // TODO Calculate best point to move to
+    let topology = world.map.topology;
+    let mut hostiles: Vec<&Unit> = world.enemies_of(unit).collect();
+    hostiles.sort_by_key(|other| *other.id());
+    let reachables = dijkstra_map.get_reachables();
+    let maybe_dest = hostiles
+        .iter()
+        .flat_map(|other| topology.neighbours(other.pos))
+        .find(|pt| reachables.contains(pt));
+
+    maybe_dest.unwrap_or_else(|| {
+        let mut reachable: Vec<_> = reachables.iter().copied().collect();
+        reachable.sort_unstable();
+        // Copied so choosing does not advance the world's generator
+        let mut world_rng = world.rng;
+        let salt = u64::from(world.turn) << 32 | u64::from(*unit.id());
+        let mut rng = Rng::new(world_rng.next_u64() ^ salt);
+        rng.choose(&reachable).copied().unwrap_or(unit.pos)
+    })
+}
+
+/// First hostile unit in range
+pub fn choose_target<'a>(world: &'a WorldState, unit: &Unit) -> Option<&'a Unit> {
+    // TODO Calculate best unit to target
+    world
+        .units_in_range(unit.pos, unit.get_attack_range())
+        .find(|other| other.id() != unit.id() && other.is_hostile_to(unit))
+}
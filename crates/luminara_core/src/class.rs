@@ -0,0 +1,273 @@
+use crate::unit::MovementClass;
+use crate::unit::Skill;
+use crate::unit::WeaponKind;
+use serde::{Deserialize, Serialize};
+
+/// Level from which a [`Item::MasterSeal`](crate::unit::Item::MasterSeal) can promote a unit.
+pub const PROMOTION_LEVEL: u32 = 10;
+/// Units in a base class promote on their own once they reach this level.
+pub const MAX_LEVEL: u32 = 20;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum UnitClass {
+    Fighter,
+    Warrior,
+    Cavalier,
+    Paladin,
+    Archer,
+    Sniper,
+    PegasusKnight,
+    FalconKnight,
+    Mage,
+    Sage,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug, Serialize, Deserialize)]
+pub struct Stats {
+    pub hp: i32,
+    pub strength: i32,
+    pub magic: i32,
+    pub skill: i32,
+    pub speed: i32,
+    pub luck: i32,
+    pub defence: i32,
+    pub resistance: i32,
+}
+
+#[derive(Debug)]
+pub struct ClassData {
+    pub name: &'static str,
+    pub movement_class: MovementClass,
+    pub movement: u32,
+    pub weapons: &'static [WeaponKind],
+    /// Skills every unit of the class has
+    pub skills: &'static [Skill],
+    pub base_stats: Stats,
+    pub stat_caps: Stats,
+    /// Added to a unit's personal growth rates, in percentage points
+    pub growth_modifiers: Stats,
+    pub promotes_to: Option<UnitClass>,
+}
+
+impl UnitClass {
+    pub fn data(self) -> &'static ClassData {
+        match self {
+            Self::Fighter => &FIGHTER,
+            Self::Warrior => &WARRIOR,
+            Self::Cavalier => &CAVALIER,
+            Self::Paladin => &PALADIN,
+            Self::Archer => &ARCHER,
+            Self::Sniper => &SNIPER,
+            Self::PegasusKnight => &PEGASUS_KNIGHT,
+            Self::FalconKnight => &FALCON_KNIGHT,
+            Self::Mage => &MAGE,
+            Self::Sage => &SAGE,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        self.data().name
+    }
+
+    pub fn can_use(self, kind: WeaponKind) -> bool {
+        self.data().weapons.contains(&kind)
+    }
+
+    pub fn promotes_to(self) -> Option<UnitClass> {
+        self.data().promotes_to
+    }
+}
+
+impl Stats {
+    #[allow(clippy::too_many_arguments)]
+    pub const fn new(
+        hp: i32,
+        strength: i32,
+        magic: i32,
+        skill: i32,
+        speed: i32,
+        luck: i32,
+        defence: i32,
+        resistance: i32,
+    ) -> Self {
+        Self {
+            hp,
+            strength,
+            magic,
+            skill,
+            speed,
+            luck,
+            defence,
+            resistance,
+        }
+    }
+
+    /// Applies `f` to every pair of matching fields
+    #[must_use]
+    pub fn zip_with(self, other: Self, mut f: impl FnMut(i32, i32) -> i32) -> Self {
+        Self {
+            hp: f(self.hp, other.hp),
+            strength: f(self.strength, other.strength),
+            magic: f(self.magic, other.magic),
+            skill: f(self.skill, other.skill),
+            speed: f(self.speed, other.speed),
+            luck: f(self.luck, other.luck),
+            defence: f(self.defence, other.defence),
+            resistance: f(self.resistance, other.resistance),
+        }
+    }
+
+    #[must_use]
+    pub fn clamp_to(self, caps: Self) -> Self {
+        self.zip_with(caps, i32::min)
+    }
+}
+
+impl std::ops::Add for Stats {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.zip_with(rhs, |a, b| a + b)
+    }
+}
+
+impl std::ops::Sub for Stats {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.zip_with(rhs, |a, b| a - b)
+    }
+}
+
+const BASE_CAPS: Stats = Stats::new(60, 20, 20, 20, 20, 30, 20, 20);
+const PROMOTED_CAPS: Stats = Stats::new(60, 25, 25, 25, 25, 30, 25, 25);
+
+#[rustfmt::skip]
+const FIGHTER: ClassData = ClassData {
+    name: "Fighter",
+    movement_class: MovementClass::Infantry,
+    movement: 5,
+    weapons: &[WeaponKind::Axe],
+    skills: &[],
+    base_stats:       Stats::new(20, 5, 0, 2, 4, 0, 2, 0),
+    stat_caps:        BASE_CAPS,
+    growth_modifiers: Stats::new(20, 10, -10, 0, 0, 0, 0, -5),
+    promotes_to: Some(UnitClass::Warrior),
+};
+
+#[rustfmt::skip]
+const WARRIOR: ClassData = ClassData {
+    name: "Warrior",
+    movement_class: MovementClass::Infantry,
+    movement: 6,
+    weapons: &[WeaponKind::Axe, WeaponKind::Bow],
+    skills: &[],
+    base_stats:       Stats::new(28, 8, 0, 5, 6, 0, 5, 0),
+    stat_caps:        Stats { strength: 30, ..PROMOTED_CAPS },
+    growth_modifiers: Stats::new(20, 10, -10, 0, 0, 0, 0, -5),
+    promotes_to: None,
+};
+
+#[rustfmt::skip]
+const CAVALIER: ClassData = ClassData {
+    name: "Cavalier",
+    movement_class: MovementClass::Mounted,
+    movement: 7,
+    weapons: &[WeaponKind::Sword, WeaponKind::Lance],
+    skills: &[],
+    base_stats:       Stats::new(20, 5, 0, 2, 5, 0, 5, 0),
+    stat_caps:        BASE_CAPS,
+    growth_modifiers: Stats::new(10, 5, -5, 0, 0, 0, 5, 0),
+    promotes_to: Some(UnitClass::Paladin),
+};
+
+#[rustfmt::skip]
+const PALADIN: ClassData = ClassData {
+    name: "Paladin",
+    movement_class: MovementClass::Mounted,
+    movement: 8,
+    weapons: &[WeaponKind::Sword, WeaponKind::Lance, WeaponKind::Axe],
+    skills: &[],
+    base_stats:       Stats::new(25, 7, 1, 4, 7, 0, 8, 3),
+    stat_caps:        PROMOTED_CAPS,
+    growth_modifiers: Stats::new(10, 5, -5, 0, 0, 0, 5, 0),
+    promotes_to: None,
+};
+
+#[rustfmt::skip]
+const ARCHER: ClassData = ClassData {
+    name: "Archer",
+    movement_class: MovementClass::Infantry,
+    movement: 5,
+    weapons: &[WeaponKind::Bow],
+    skills: &[],
+    base_stats:       Stats::new(18, 4, 0, 5, 3, 0, 3, 0),
+    stat_caps:        BASE_CAPS,
+    growth_modifiers: Stats::new(0, 0, -5, 15, 5, 0, 0, 0),
+    promotes_to: Some(UnitClass::Sniper),
+};
+
+#[rustfmt::skip]
+const SNIPER: ClassData = ClassData {
+    name: "Sniper",
+    movement_class: MovementClass::Infantry,
+    movement: 6,
+    weapons: &[WeaponKind::Bow],
+    skills: &[],
+    base_stats:       Stats::new(21, 7, 1, 9, 6, 0, 5, 2),
+    stat_caps:        Stats { skill: 30, ..PROMOTED_CAPS },
+    growth_modifiers: Stats::new(0, 0, -5, 15, 5, 0, 0, 0),
+    promotes_to: None,
+};
+
+#[rustfmt::skip]
+const PEGASUS_KNIGHT: ClassData = ClassData {
+    name: "Pegasus Knight",
+    movement_class: MovementClass::Flying,
+    movement: 7,
+    weapons: &[WeaponKind::Lance],
+    skills: &[],
+    base_stats:       Stats::new(16, 3, 1, 4, 6, 0, 2, 4),
+    stat_caps:        BASE_CAPS,
+    growth_modifiers: Stats::new(-5, -5, 0, 5, 15, 5, -5, 10),
+    promotes_to: Some(UnitClass::FalconKnight),
+};
+
+#[rustfmt::skip]
+const FALCON_KNIGHT: ClassData = ClassData {
+    name: "Falcon Knight",
+    movement_class: MovementClass::Flying,
+    movement: 8,
+    weapons: &[WeaponKind::Lance, WeaponKind::Sword],
+    skills: &[Skill::Pass],
+    base_stats:       Stats::new(20, 5, 2, 6, 9, 0, 4, 7),
+    stat_caps:        Stats { speed: 30, ..PROMOTED_CAPS },
+    growth_modifiers: Stats::new(-5, -5, 0, 5, 15, 5, -5, 10),
+    promotes_to: None,
+};
+
+#[rustfmt::skip]
+const MAGE: ClassData = ClassData {
+    name: "Mage",
+    movement_class: MovementClass::Infantry,
+    movement: 5,
+    weapons: &[WeaponKind::Tome],
+    skills: &[],
+    base_stats:       Stats::new(15, 0, 4, 3, 3, 0, 1, 3),
+    stat_caps:        BASE_CAPS,
+    growth_modifiers: Stats::new(-5, -10, 15, 0, 0, 0, -5, 10),
+    promotes_to: Some(UnitClass::Sage),
+};
+
+#[rustfmt::skip]
+const SAGE: ClassData = ClassData {
+    name: "Sage",
+    movement_class: MovementClass::Infantry,
+    movement: 6,
+    weapons: &[WeaponKind::Tome],
+    skills: &[],
+    base_stats:       Stats::new(18, 1, 7, 5, 5, 0, 3, 6),
+    stat_caps:        Stats { magic: 30, ..PROMOTED_CAPS },
+    growth_modifiers: Stats::new(-5, -10, 15, 0, 0, 0, -5, 10),
+    promotes_to: None,
+};
@@ -0,0 +1,27 @@
+use crate::class::Stats;
+use crate::command::Command;
+use crate::unit::{COMBAT_EXP, Unit};
+
+#[derive(Debug)]
+pub struct AttackOutcome {
+    /// Apply the hit to the world
+    pub commands: Vec<Command>,
+    /// Stat increases if the attacker levelled up
+    pub level_up: Option<Stats>,
+}
+
+/// Resolves `attacker` hitting `target`, the attacker gains experience for it
+pub fn attack(attacker: &mut Unit, target: &Unit) -> AttackOutcome {
+    let mut commands = vec![Command::DamageUnit(
+        target.id(),
+        attacker.attack_damage(target),
+    )];
+    if let Some(effect) = attacker.usable_weapon().and_then(|weapon| weapon.effect) {
+        commands.push(Command::ApplyStatus(target.id(), effect));
+    }
+
+    AttackOutcome {
+        commands,
+        level_up: attacker.gain_exp(COMBAT_EXP),
+    }
+}
@@ -0,0 +1,16 @@
+use crate::status::StatusEffect;
+use crate::unit::{Unit, UnitId};
+use crate::world::Faction;
+
+use serde::{Deserialize, Serialize};
+
+/// Changes to the world, states queue these and they are applied once the state has updated
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+pub enum Command {
+    CommitUnit(Unit),
+    DamageUnit(UnitId, i32),
+    ApplyStatus(UnitId, StatusEffect),
+    SetupTurn(Faction),
+    Talk(UnitId, UnitId),
+}
@@ -0,0 +1,135 @@
+use crate::map::Terrain;
+use crate::math::{Point, TileRect};
+use crate::unit::{ErasedUnit, Item, UnitId};
+use crate::world::{Faction, Objective, WorldState};
+
+/// Facts recorded by the world while commands are applied
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WorldEvent {
+    TurnStarted { turn: u32, faction: Faction },
+    UnitMoved { id: UnitId, to: Point },
+    UnitDefeated(UnitId),
+    UnitsTalked(UnitId, UnitId),
+}
+
+#[derive(Clone, Debug)]
+pub enum Condition {
+    TurnStart {
+        turn: u32,
+        faction: Faction,
+    },
+    UnitEntersRegion {
+        faction: Faction,
+        region: TileRect,
+    },
+    UnitDefeated(UnitId),
+    HealthBelow {
+        unit: UnitId,
+        threshold: i32,
+    },
+    /// Either unit may start the conversation
+    Talk(UnitId, UnitId),
+}
+
+#[derive(Clone, Debug)]
+pub enum TriggerAction {
+    /// Path of the dialogue script to play
+    Dialogue(String),
+    SpawnUnits(Vec<ErasedUnit>),
+    ChangeTerrain(Point, Terrain),
+    ChangeObjective(Objective),
+    GiveItem(UnitId, Item),
+}
+
+/// A unit scheduled to join the battle at the start of its faction's phase,
+/// it arrives on the unit's position or the nearest free tile
+#[derive(Clone, Debug)]
+pub struct Reinforcement {
+    pub turn: u32,
+    pub unit: ErasedUnit,
+    /// Lets the unit act in the phase it arrives in
+    pub ambush: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Trigger {
+    pub condition: Condition,
+    pub actions: Vec<TriggerAction>,
+    fired: bool,
+}
+
+impl Trigger {
+    pub fn new(condition: Condition, actions: Vec<TriggerAction>) -> Self {
+        Self {
+            condition,
+            actions,
+            fired: false,
+        }
+    }
+
+    /// Triggers only fire once
+    pub fn is_active(&self) -> bool {
+        !self.fired
+    }
+
+    /// For restoring a saved battle, the actions are not run
+    pub fn set_fired(&mut self, fired: bool) {
+        self.fired = fired;
+    }
+
+    pub fn is_talk_between(&self, a: UnitId, b: UnitId) -> bool {
+        self.is_active()
+            && matches!(self.condition, Condition::Talk(x, y) if (x, y) == (a, b) || (x, y) == (b, a))
+    }
+
+    fn is_met(&self, world: &WorldState, events: &[WorldEvent]) -> bool {
+        match &self.condition {
+            Condition::HealthBelow { unit, threshold } => world
+                .get_unit(*unit)
+                .is_some_and(|unit| unit.curr_health < *threshold),
+            condition => events.iter().any(|event| condition.matches(world, event)),
+        }
+    }
+}
+
+impl Condition {
+    fn matches(&self, world: &WorldState, event: &WorldEvent) -> bool {
+        match (self, event) {
+            (
+                Self::TurnStart { turn, faction },
+                WorldEvent::TurnStarted {
+                    turn: ev_turn,
+                    faction: ev_faction,
+                },
+            ) => turn == ev_turn && faction == ev_faction,
+            (Self::UnitEntersRegion { faction, region }, WorldEvent::UnitMoved { id, to }) => {
+                region.point_in_rect(*to)
+                    && world
+                        .get_unit(*id)
+                        .is_some_and(|unit| unit.faction == *faction)
+            }
+            (Self::UnitDefeated(unit), WorldEvent::UnitDefeated(defeated)) => unit == defeated,
+            (Self::Talk(a, b), WorldEvent::UnitsTalked(x, y)) => {
+                (a, b) == (x, y) || (a, b) == (y, x)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Fires every active trigger whose condition is met and returns their actions in order
+pub fn evaluate_triggers(
+    triggers: &mut [Trigger],
+    world: &WorldState,
+    events: &[WorldEvent],
+) -> Vec<TriggerAction> {
+    let mut actions = Vec::new();
+    for trigger in triggers.iter_mut().filter(|trigger| trigger.is_active()) {
+        if trigger.is_met(world, events) {
+            trigger.fired = true;
+            actions.extend(trigger.actions.iter().cloned());
+        }
+    }
+
+    actions
+}
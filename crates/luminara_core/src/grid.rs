@@ -0,0 +1,131 @@
+use crate::math::Point;
+
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+const SQUARE_DIRS: [Point; 4] = [
+    Point::new(0, 1),
+    Point::new(0, -1),
+    Point::new(1, 0),
+    Point::new(-1, 0),
+];
+
+const HEX_EVEN_ROW_DIRS: [Point; 6] = [
+    Point::new(1, 0),
+    Point::new(-1, 0),
+    Point::new(0, -1),
+    Point::new(-1, -1),
+    Point::new(0, 1),
+    Point::new(-1, 1),
+];
+
+const HEX_ODD_ROW_DIRS: [Point; 6] = [
+    Point::new(1, 0),
+    Point::new(-1, 0),
+    Point::new(1, -1),
+    Point::new(0, -1),
+    Point::new(1, 1),
+    Point::new(0, 1),
+];
+
+/// How the tiles of a map connect to each other
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+pub enum GridTopology {
+    #[default]
+    Square,
+    /// Pointy top hexes stored in offset coordinates, odd rows are shifted right by half a tile
+    Hex,
+}
+
+impl GridTopology {
+    fn dirs(self, row: i32) -> &'static [Point] {
+        match self {
+            Self::Square => &SQUARE_DIRS,
+            Self::Hex if row & 1 == 0 => &HEX_EVEN_ROW_DIRS,
+            Self::Hex => &HEX_ODD_ROW_DIRS,
+        }
+    }
+
+    /// Tiles sharing an edge with `pos`, these may be out of bounds
+    pub fn neighbours(self, pos: Point) -> impl Iterator<Item = Point> {
+        self.dirs(pos.y).iter().map(move |dir| pos + *dir)
+    }
+
+    /// Number of steps between two tiles ignoring terrain
+    pub fn distance(self, a: Point, b: Point) -> i32 {
+        match self {
+            Self::Square => (a.x - b.x).abs() + (a.y - b.y).abs(),
+            Self::Hex => {
+                let (aq, ar) = Self::axial(a);
+                let (bq, br) = Self::axial(b);
+                let (dq, dr) = (aq - bq, ar - br);
+                (dq.abs() + dr.abs() + (dq + dr).abs()) / 2
+            }
+        }
+    }
+
+    /// Every tile within `range` steps of `from`, not including `from` itself
+    pub fn tiles_in_range(self, from: Point, range: i32) -> impl Iterator<Item = Point> {
+        (-range..=range).flat_map(move |dy| {
+            (-range..=range).filter_map(move |dx| {
+                let pt = from + (dx, dy);
+                (pt != from && self.distance(from, pt) <= range).then_some(pt)
+            })
+        })
+    }
+
+    /// Moves `pos` one tile in the direction of the d-pad, on hex grids a diagonal
+    /// press picks the neighbour in that direction while a vertical press keeps the column
+    pub fn step(self, pos: Point, (dx, dy): (i32, i32)) -> Point {
+        match self {
+            Self::Hex if dx != 0 && dy != 0 => {
+                let odd_row = pos.y & 1 == 1;
+                let shift = match (odd_row, dx > 0) {
+                    (true, true) => 1,
+                    (false, false) => -1,
+                    _ => 0,
+                };
+                pos + (shift, dy)
+            }
+            _ => pos + (dx, dy),
+        }
+    }
+
+    /// Where a tile position is laid out on the map, in tiles
+    pub fn layout_pos(self, tile_pos: Vec2) -> Vec2 {
+        match self {
+            Self::Square => tile_pos,
+            Self::Hex => {
+                // Interpolate the row shift so units moving between rows slide smoothly
+                let row = tile_pos.y.floor();
+                let frac = tile_pos.y - row;
+                let shift = |row: f32| if row.rem_euclid(2.0) >= 1.0 { 0.5 } else { 0.0 };
+                let offset = shift(row) * (1.0 - frac) + shift(row + 1.0) * frac;
+                Vec2::new(tile_pos.x + offset, tile_pos.y)
+            }
+        }
+    }
+
+    /// The tile laid out under a position on the map, the inverse of [`Self::layout_pos`]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn tile_at(self, layout_pos: Vec2) -> Point {
+        let row = layout_pos.y.floor();
+        let shift = match self {
+            Self::Hex if row.rem_euclid(2.0) >= 1.0 => 0.5,
+            Self::Square | Self::Hex => 0.0,
+        };
+        Point::new((layout_pos.x - shift).floor() as i32, row as i32)
+    }
+
+    /// Extra width taken by the layout over a square grid, in tiles
+    pub fn layout_padding(self) -> f32 {
+        match self {
+            Self::Square => 0.0,
+            Self::Hex => 0.5,
+        }
+    }
+
+    fn axial(pos: Point) -> (i32, i32) {
+        (pos.x - (pos.y - (pos.y & 1)) / 2, pos.y)
+    }
+}
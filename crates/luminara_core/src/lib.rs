@@ -0,0 +1,21 @@
+#![warn(clippy::pedantic, clippy::all)]
+#![allow(clippy::must_use_candidate, clippy::missing_panics_doc)]
+//! Game rules of Luminara without any rendering or windowing, so battles can
+//! run headless in tools and tests as well as in the game itself.
+
+pub mod action;
+pub mod ai;
+pub mod class;
+pub mod combat;
+pub mod command;
+pub mod event;
+pub mod grid;
+pub mod map;
+pub mod math;
+pub mod pathfinding;
+pub mod prelude;
+pub mod rng;
+pub mod snapshot;
+pub mod status;
+pub mod unit;
+pub mod world;
@@ -0,0 +1,147 @@
+use crate::event::{Reinforcement, Trigger};
+use crate::grid::GridTopology;
+use crate::math::Point;
+use crate::math::TileRect;
+use crate::prelude::Bounds2D;
+use crate::rng::Rng;
+use serde::{Deserialize, Serialize};
+
+pub struct Map {
+    terrain: Vec<Terrain>,
+    pub width: usize,
+    pub height: usize,
+    pub topology: GridTopology,
+    /// Units have to stop once they move next to a hostile unit
+    pub zone_of_control: bool,
+    pub triggers: Vec<Trigger>,
+    pub reinforcements: Vec<Reinforcement>,
+}
+
+impl Map {
+    fn empty(width: u32, height: u32, topology: GridTopology) -> Self {
+        assert!(width * height < u32::MAX);
+        let capacity = (width * height) as usize;
+        Self {
+            terrain: Vec::with_capacity(capacity),
+            width: width as usize,
+            height: height as usize,
+            topology,
+            zone_of_control: false,
+            triggers: Vec::new(),
+            reinforcements: Vec::new(),
+        }
+    }
+
+    pub fn random(width: u32, height: u32, topology: GridTopology, rng: &mut Rng) -> Self {
+        let mut map = Self::empty(width, height, topology);
+
+        for _ in 0..width * height {
+            let terrain = *rng.choose(&[Terrain::Ground, Terrain::Forest]).unwrap();
+            map.terrain.push(terrain);
+        }
+
+        map
+    }
+
+    /// Builds a map from one string per row using the glyphs of [`Terrain::symbol`]
+    ///
+    /// Returns `None` if the rows differ in length or contain an unknown glyph
+    pub fn from_ascii(rows: &[impl AsRef<str>], topology: GridTopology) -> Option<Self> {
+        let width = rows.first()?.as_ref().chars().count();
+        let mut map = Self::empty(
+            width.try_into().ok()?,
+            rows.len().try_into().ok()?,
+            topology,
+        );
+
+        for row in rows {
+            let row = row.as_ref();
+            if row.chars().count() != width {
+                return None;
+            }
+            for symbol in row.chars() {
+                map.terrain.push(Terrain::from_symbol(symbol)?);
+            }
+        }
+
+        Some(map)
+    }
+
+    /// Inverse of [`Map::from_ascii`]
+    pub fn to_ascii(&self) -> Vec<String> {
+        self.terrain
+            .chunks(self.width)
+            .map(|row| row.iter().map(|terrain| terrain.symbol()).collect())
+            .collect()
+    }
+
+    pub fn get_terrain(&self, pos: impl Into<Point>) -> Terrain {
+        *self.terrain.get(self.point_to_idx(pos.into())).unwrap()
+    }
+
+    pub fn set_terrain(&mut self, pos: impl Into<Point>, terrain: Terrain) {
+        let idx = self.point_to_idx(pos.into());
+        self.terrain[idx] = terrain;
+    }
+
+    pub fn get_bounds(&self) -> Bounds2D {
+        (
+            0..self.width.try_into().unwrap(),
+            0..self.height.try_into().unwrap(),
+        )
+    }
+
+    pub fn point_to_idx(&self, point: impl Into<Point>) -> usize {
+        let point = point.into();
+        let (x, y) = (point.x, point.y);
+        (TryInto::<usize>::try_into(y).unwrap() * self.width)
+            + TryInto::<usize>::try_into(x).unwrap()
+    }
+
+    pub fn in_bounds(&self, pt: Point) -> bool {
+        let map_rect = TileRect::with_size(
+            0,
+            0,
+            self.width.try_into().unwrap(),
+            self.height.try_into().unwrap(),
+        );
+        map_rect.point_in_rect(pt)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Terrain {
+    Ground,
+    Forest,
+    Mountain,
+    River,
+}
+
+impl Terrain {
+    pub fn texture_path(self) -> &'static str {
+        match self {
+            Self::Ground => "grass1.png",
+            // TODO Add textures for mountains and rivers
+            Self::Forest | Self::Mountain | Self::River => "forest1.png",
+        }
+    }
+
+    pub fn symbol(self) -> char {
+        match self {
+            Self::Ground => '.',
+            Self::Forest => 'T',
+            Self::Mountain => '^',
+            Self::River => '~',
+        }
+    }
+
+    pub fn from_symbol(symbol: char) -> Option<Self> {
+        match symbol {
+            '.' => Some(Self::Ground),
+            'T' => Some(Self::Forest),
+            '^' => Some(Self::Mountain),
+            '~' => Some(Self::River),
+            _ => None,
+        }
+    }
+}
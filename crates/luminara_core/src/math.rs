@@ -1,6 +1,7 @@
-use macroquad::math::{Rect, Vec2};
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Serialize, Deserialize)]
 pub struct Point {
     pub x: i32,
     pub y: i32,
@@ -14,14 +15,6 @@ pub struct TileRect {
     pub h: i32,
 }
 
-#[must_use]
-pub fn clamp_rect_to_bounds(mut rect: Rect, width: f32, height: f32) -> Rect {
-    rect.x = rect.x.clamp(0.0, width - rect.w);
-    rect.y = rect.y.clamp(0.0, height - rect.h);
-
-    rect
-}
-
 impl Point {
     pub const fn new(x: i32, y: i32) -> Self {
         Self { x, y }
@@ -104,15 +97,3 @@ impl From<Point> for Vec2 {
         Vec2::new(val.x as f32, val.y as f32)
     }
 }
-
-#[allow(clippy::cast_precision_loss)]
-impl From<TileRect> for Rect {
-    fn from(value: TileRect) -> Self {
-        Self {
-            x: value.x as f32,
-            y: value.y as f32,
-            w: value.w as f32,
-            h: value.h as f32,
-        }
-    }
-}
@@ -1,35 +1,38 @@
-use macroquad::prelude::warn;
-
-use crate::map::Map;
+use crate::grid::GridTopology;
 use crate::math::Point;
+use crate::unit::Skill;
 use crate::unit::Unit;
-use crate::unit::UnitId;
+use crate::world::WorldState;
 
+use std::collections::BinaryHeap;
 use std::collections::HashSet;
-use std::collections::{BinaryHeap, HashMap};
 
 #[derive(Debug)]
 pub struct DijkstraMap {
+    /// Tiles the unit can end its movement on
     reachables: HashSet<Point>,
+    /// Tiles the unit can move through but not stop on, i.e. ones held by other units
+    passables: HashSet<Point>,
+    /// Tiles inside a hostile zone of control, movement can not continue past them
+    stops: HashSet<Point>,
     map: Vec<u32>,
     came_from: Vec<Option<Point>>,
     start: Point,
+    topology: GridTopology,
     width: usize,
-    heigth: usize,
 }
 impl DijkstraMap {
     pub const UNREACHABLE: u32 = u32::MAX;
-    pub const DIRS: [Point; 4] = [
-        Point::new(0, 1),
-        Point::new(0, -1),
-        Point::new(1, 0),
-        Point::new(-1, 0),
-    ];
-
-    pub fn new(map: &Map, target: &Unit, units: &HashMap<UnitId, Unit>) -> Self {
+
+    pub fn new(world: &WorldState, target: &Unit) -> Self {
+        let map = &world.map;
         let mut dijkstra_map = vec![Self::UNREACHABLE; map.width * map.height];
         let mut came_from = vec![None; map.width * map.height];
         let mut reachables = HashSet::new();
+        let mut passables = HashSet::new();
+        let mut stops = HashSet::new();
+        let can_pass = target.has_skill(Skill::Pass);
+        let zone_of_control = map.zone_of_control && !can_pass;
 
         let mut heap = BinaryHeap::new();
 
@@ -43,23 +46,34 @@ impl DijkstraMap {
             if dist > dijkstra_map[map.point_to_idx(pos)] {
                 continue;
             }
-            if dist > target.movement {
+            if dist > target.movement() {
                 continue;
             }
 
-            reachables.insert(pos);
+            if world
+                .unit_at(pos)
+                .is_some_and(|unit| unit.id() != target.id())
+            {
+                passables.insert(pos);
+            } else {
+                reachables.insert(pos);
+            }
 
-            for dir in Self::DIRS {
-                let npos = pos + dir;
+            // Entering a tile next to a hostile unit ends movement
+            if zone_of_control
+                && pos != target.pos
+                && Self::is_in_zone_of_control(world, pos, target)
+            {
+                stops.insert(pos);
+                continue;
+            }
 
+            for npos in map.topology.neighbours(pos) {
                 if !map.in_bounds(npos) {
                     continue;
                 }
 
-                if units
-                    .values()
-                    .any(|unit| unit.faction != target.faction && unit.pos == npos)
-                {
+                if !can_pass && Self::is_hostile_at(world, npos, target) {
                     continue;
                 }
 
@@ -72,7 +86,7 @@ impl DijkstraMap {
                 let next_idx = map.point_to_idx(npos);
                 let prev_dist = dijkstra_map[next_idx];
 
-                if next_dist <= target.movement
+                if next_dist <= target.movement()
                     && (prev_dist == Self::UNREACHABLE || next_dist < prev_dist)
                 {
                     dijkstra_map[next_idx] = next_dist;
@@ -87,11 +101,13 @@ impl DijkstraMap {
 
         DijkstraMap {
             reachables,
+            passables,
+            stops,
             map: dijkstra_map,
             came_from,
             start: target.pos,
+            topology: map.topology,
             width: map.width,
-            heigth: map.height,
         }
     }
 
@@ -99,16 +115,51 @@ impl DijkstraMap {
         &self.reachables
     }
 
-    /// Caller should ensure the point is reachable
+    pub fn get_passables(&self) -> &HashSet<Point> {
+        &self.passables
+    }
+
+    /// Whether a path may pass through the tile, including tiles it can not stop on
+    pub fn is_traversable(&self, pt: Point) -> bool {
+        self.reachables.contains(&pt) || self.passables.contains(&pt)
+    }
+
+    pub fn stops_movement(&self, pt: Point) -> bool {
+        self.stops.contains(&pt)
+    }
+
+    pub fn topology(&self) -> GridTopology {
+        self.topology
+    }
+
+    fn is_hostile_at(world: &WorldState, pos: Point, target: &Unit) -> bool {
+        world
+            .unit_at(pos)
+            .is_some_and(|unit| unit.id() != target.id() && unit.is_hostile_to(target))
+    }
+
+    fn is_in_zone_of_control(world: &WorldState, pos: Point, target: &Unit) -> bool {
+        world
+            .map
+            .topology
+            .neighbours(pos)
+            .any(|npos| Self::is_hostile_at(world, npos, target))
+    }
+
+    /// From the goal back to the start, empty if the goal can not be reached
     pub fn get_path_to(&self, goal: impl Into<Point>) -> Vec<Point> {
         let goal = goal.into();
         let mut path = Vec::new();
         let mut current = goal;
 
-        // bail if unreachable
-        debug_assert!(self.map[self.idx(goal)] != Self::UNREACHABLE);
-        if self.map[self.idx(goal)] == Self::UNREACHABLE {
-            warn!("Requesting unreachable path");
+        // bail if off the map or unreachable
+        let on_map = usize::try_from(goal.x).is_ok_and(|x| x < self.width) && goal.y >= 0;
+        if !on_map
+            || self
+                .map
+                .get(self.idx(goal))
+                .is_none_or(|&cost| cost == Self::UNREACHABLE)
+        {
             return path;
         }
 
@@ -119,9 +170,6 @@ impl DijkstraMap {
                 current = prev;
             } else {
                 // shouldn't happen unless goal isn't connected
-                #[cfg(debug_assertions)]
-                panic!("Path not found");
-
                 return Vec::new();
             }
         }
@@ -138,18 +186,6 @@ impl DijkstraMap {
     }
 }
 
-pub fn get_manahattan_neighbours(from: Point, range: i32) -> impl Iterator<Item = Point> {
-    (-range..=range).flat_map(move |dx| {
-        (-range..=range).filter_map(move |dy| {
-            if (dx, dy) == (0, 0) || dx.abs() + dy.abs() > range {
-                None
-            } else {
-                Some(from + (dx, dy))
-            }
-        })
-    })
-}
-
 #[derive(PartialEq, Eq)]
 struct Node {
     pos: Point,
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+/// Small deterministic generator (`SplitMix64`) so a battle can be replayed from its seed
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `low..high`
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    pub fn gen_range(&mut self, low: i32, high: i32) -> i32 {
+        debug_assert!(low < high);
+        let span = u64::from(high.abs_diff(low));
+        low + (self.next_u64() % span) as i32
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let idx = (self.next_u64() % items.len() as u64) as usize;
+        items.get(idx)
+    }
+}
@@ -0,0 +1,173 @@
+use crate::grid::GridTopology;
+use crate::rng::Rng;
+use crate::unit::{Unit, UnitId};
+use crate::world::{Faction, Objective};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::fmt;
+
+const FNV_OFFSET: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+/// Canonical copy of everything that decides how a battle goes on, taken with
+/// [`WorldState::snapshot`](crate::world::WorldState::snapshot). Units are sorted by id, so two
+/// worlds that played the same commands from the same seed give equal snapshots on any
+/// platform. Units keep their texture path, which a restored world needs to draw them, so it
+/// counts towards the checksum as well.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    /// Rows of [`Terrain::symbol`](crate::map::Terrain::symbol)
+    pub terrain: Vec<String>,
+    pub topology: GridTopology,
+    pub zone_of_control: bool,
+    pub turn: u32,
+    pub phase: Faction,
+    pub objective: Objective,
+    pub rng: Rng,
+    pub next_unit_id: UnitId,
+    pub units: Vec<Unit>,
+    /// In map order, whether each trigger has fired
+    pub triggers_fired: Vec<bool>,
+    /// Turns the pending reinforcements arrive on, in map order
+    pub reinforcement_turns: Vec<u32>,
+}
+
+impl WorldSnapshot {
+    fn to_value(&self) -> Value {
+        serde_json::to_value(self).expect("Snapshots always serialize")
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Snapshots always serialize")
+    }
+
+    /// # Errors
+    /// If the json is not a snapshot
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// FNV-1a over the compact json, which has a fixed field order and no floats
+    pub fn checksum(&self) -> u64 {
+        let bytes = serde_json::to_vec(self).expect("Snapshots always serialize");
+        bytes.iter().fold(FNV_OFFSET, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+        })
+    }
+
+    /// Every field that differs, units are matched by id rather than position
+    pub fn diff(&self, other: &Self) -> Vec<FieldDiff> {
+        let mut diffs = Vec::new();
+        diff_values(
+            String::new(),
+            &self.to_value(),
+            &other.to_value(),
+            &mut diffs,
+        );
+        diffs
+    }
+}
+
+/// A field of the left snapshot that has another value, or is missing, in the right one
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FieldDiff {
+    /// Like `units[id=2].stats.hp`
+    pub path: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+impl FieldDiff {
+    fn new(path: String, left: Option<&Value>, right: Option<&Value>) -> Self {
+        Self {
+            path,
+            left: left.map(Value::to_string),
+            right: right.map(Value::to_string),
+        }
+    }
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |value: &Option<String>| value.clone().unwrap_or_else(|| "missing".into());
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.path,
+            side(&self.left),
+            side(&self.right)
+        )
+    }
+}
+
+/// Elements keyed by their `id` field, if every element has one
+fn by_id(values: &[Value]) -> Option<Vec<(String, &Value)>> {
+    values
+        .iter()
+        .map(|value| Some((value.get("id")?.to_string(), value)))
+        .collect()
+}
+
+fn diff_values(path: String, left: &Value, right: &Value, diffs: &mut Vec<FieldDiff>) {
+    let field = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+
+    match (left, right) {
+        (Value::Object(left), Value::Object(right)) => {
+            for (key, value) in left {
+                match right.get(key) {
+                    Some(other) => diff_values(field(key), value, other, diffs),
+                    None => diffs.push(FieldDiff::new(field(key), Some(value), None)),
+                }
+            }
+            for (key, value) in right.iter().filter(|(key, _)| !left.contains_key(*key)) {
+                diffs.push(FieldDiff::new(field(key), None, Some(value)));
+            }
+        }
+        (Value::Array(left), Value::Array(right)) => {
+            if let (Some(left), Some(right)) = (by_id(left), by_id(right)) {
+                diff_keyed(&path, &left, &right, diffs);
+            } else if left.len() == right.len() {
+                for (idx, (value, other)) in left.iter().zip(right).enumerate() {
+                    diff_values(format!("{path}[{idx}]"), value, other, diffs);
+                }
+            } else {
+                // Shifted lists line up badly, so they are shown whole
+                diffs.push(FieldDiff::new(
+                    path,
+                    Some(&left.clone().into()),
+                    Some(&right.clone().into()),
+                ));
+            }
+        }
+        _ if left != right => diffs.push(FieldDiff::new(path, Some(left), Some(right))),
+        _ => {}
+    }
+}
+
+fn diff_keyed(
+    path: &str,
+    left: &[(String, &Value)],
+    right: &[(String, &Value)],
+    diffs: &mut Vec<FieldDiff>,
+) {
+    let element = |id: &str| format!("{path}[id={id}]");
+    for (id, value) in left {
+        match right.iter().find(|(other_id, _)| other_id == id) {
+            Some((_, other)) => diff_values(element(id), value, other, diffs),
+            None => diffs.push(FieldDiff::new(element(id), Some(value), None)),
+        }
+    }
+    for (id, value) in right {
+        if !left.iter().any(|(other_id, _)| other_id == id) {
+            diffs.push(FieldDiff::new(element(id), None, Some(value)));
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use crate::class::Stats;
+use serde::{Deserialize, Serialize};
+
+pub const POISON_DAMAGE: i32 = 3;
+pub const BUFF_AMOUNT: i32 = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum StatusKind {
+    /// Loses health at the start of each phase, but never drops below 1
+    Poison,
+    /// Skips its turn, wakes up early when damaged
+    Sleep,
+    /// Skips its turn
+    Stun,
+    /// Acts on its own and treats every other unit as hostile
+    Berserk,
+    AttackUp,
+    DefenceUp,
+    /// Cannot move but may still act
+    Rooted,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    /// Number of phases this effect stays active for
+    pub turns: u32,
+}
+
+impl StatusEffect {
+    pub const fn new(kind: StatusKind, turns: u32) -> Self {
+        Self { kind, turns }
+    }
+}
+
+impl StatusKind {
+    pub fn skips_turn(self) -> bool {
+        matches!(self, Self::Sleep | Self::Stun)
+    }
+
+    pub fn stat_modifier(self) -> Stats {
+        match self {
+            Self::AttackUp => Stats {
+                strength: BUFF_AMOUNT,
+                magic: BUFF_AMOUNT,
+                ..Stats::default()
+            },
+            Self::DefenceUp => Stats {
+                defence: BUFF_AMOUNT,
+                resistance: BUFF_AMOUNT,
+                ..Stats::default()
+            },
+            _ => Stats::default(),
+        }
+    }
+}
@@ -0,0 +1,419 @@
+use crate::class::MAX_LEVEL;
+use crate::class::PROMOTION_LEVEL;
+use crate::class::Stats;
+use crate::class::UnitClass;
+use crate::map::Terrain;
+use crate::math::Point;
+use crate::pathfinding::DijkstraMap;
+use crate::rng::Rng;
+use crate::status::POISON_DAMAGE;
+use crate::status::StatusEffect;
+use crate::status::StatusKind;
+use crate::world::Faction;
+
+use std::ops::Deref;
+use std::ops::DerefMut;
+
+use serde::{Deserialize, Serialize};
+
+pub const EXP_PER_LEVEL: u32 = 100;
+pub const COMBAT_EXP: u32 = 30;
+const TONIC_TURNS: u32 = 3;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Unit {
+    id: UnitId,
+    class: UnitClass,
+    pub level: u32,
+    pub exp: u32,
+    pub stats: Stats,
+    /// Personal growth rates in percent, class modifiers are applied on top
+    pub growths: Stats,
+    pub turn_complete: bool,
+    pub faction: Faction,
+    pub curr_health: i32,
+    pub pos: Point,
+    pub texture_path: String,
+    pub weapon: Option<Weapon>,
+    pub items: Vec<Item>,
+    /// Personal skills, class skills are added on top
+    pub skills: Vec<Skill>,
+    pub status_effects: Vec<StatusEffect>,
+    /// Rolls the unit's level ups
+    rng: Rng,
+}
+
+impl Unit {
+    pub fn from_erased(id: UnitId, erased: &ErasedUnit, seed: u64) -> Self {
+        Self {
+            id,
+            class: erased.class,
+            level: erased.level,
+            exp: 0,
+            stats: erased.stats,
+            growths: erased.growths,
+            turn_complete: erased.turn_complete,
+            faction: erased.faction,
+            curr_health: erased.curr_health,
+            pos: erased.pos,
+            texture_path: erased.texture_path.clone(),
+            weapon: erased.weapon,
+            items: erased.items.clone(),
+            skills: erased.skills.clone(),
+            status_effects: Vec::new(),
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub fn get_movement_cost(&self, terrain: Terrain) -> u32 {
+        self.movement_class().get_movement_cost(terrain)
+    }
+
+    pub fn id(&self) -> UnitId {
+        self.id
+    }
+
+    pub fn class(&self) -> UnitClass {
+        self.class
+    }
+
+    pub fn movement_class(&self) -> MovementClass {
+        self.class.data().movement_class
+    }
+
+    pub fn movement(&self) -> u32 {
+        if self.has_status(StatusKind::Rooted) {
+            return 0;
+        }
+        self.class.data().movement
+    }
+
+    pub fn max_health(&self) -> i32 {
+        self.stats.hp
+    }
+
+    /// The equipped weapon, if the unit's class is able to wield it
+    pub fn usable_weapon(&self) -> Option<Weapon> {
+        self.weapon.filter(|weapon| self.class.can_use(weapon.kind))
+    }
+
+    // TODO Return a Range<i32> to allow for minimum attack range
+    pub fn get_attack_range(&self) -> i32 {
+        self.usable_weapon().map_or(1, |weapon| weapon.max_range)
+    }
+
+    /// Stats with the modifiers of active status effects applied
+    pub fn effective_stats(&self) -> Stats {
+        self.status_effects
+            .iter()
+            .fold(self.stats, |stats, effect| {
+                stats + effect.kind.stat_modifier()
+            })
+    }
+
+    pub fn attack_damage(&self, target: &Unit) -> i32 {
+        let weapon = self.usable_weapon();
+        let might = weapon.map_or(0, |weapon| weapon.might);
+        let stats = self.effective_stats();
+        let target_stats = target.effective_stats();
+
+        let damage = if weapon.is_some_and(|weapon| weapon.kind == WeaponKind::Tome) {
+            stats.magic + might - target_stats.resistance
+        } else {
+            stats.strength + might - target_stats.defence
+        };
+
+        damage.max(0)
+    }
+
+    pub fn is_hostile_to(&self, other: &Unit) -> bool {
+        self.faction != other.faction
+            || self.has_status(StatusKind::Berserk)
+            || other.has_status(StatusKind::Berserk)
+    }
+
+    pub fn has_skill(&self, skill: Skill) -> bool {
+        self.skills.contains(&skill) || self.class.data().skills.contains(&skill)
+    }
+
+    pub fn has_status(&self, kind: StatusKind) -> bool {
+        self.status_effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    /// Adds the effect, refreshing the duration if it is already active
+    pub fn add_status(&mut self, effect: StatusEffect) {
+        if let Some(active) = self
+            .status_effects
+            .iter_mut()
+            .find(|active| active.kind == effect.kind)
+        {
+            active.turns = active.turns.max(effect.turns);
+        } else {
+            self.status_effects.push(effect);
+        }
+    }
+
+    pub fn take_damage(&mut self, damage: i32) {
+        self.curr_health -= damage;
+        if damage > 0 {
+            self.status_effects
+                .retain(|effect| effect.kind != StatusKind::Sleep);
+        }
+    }
+
+    /// Applies and counts down status effects at the start of the unit's phase
+    pub fn tick_status_effects(&mut self) {
+        for effect in &mut self.status_effects {
+            match effect.kind {
+                StatusKind::Poison => {
+                    self.curr_health = (self.curr_health - POISON_DAMAGE).max(1);
+                }
+                kind if kind.skips_turn() => self.turn_complete = true,
+                _ => {}
+            }
+            effect.turns = effect.turns.saturating_sub(1);
+        }
+        self.status_effects.retain(|effect| effect.turns > 0);
+    }
+
+    /// Returns the stat increases if this made the unit level up
+    pub fn gain_exp(&mut self, exp: u32) -> Option<Stats> {
+        if self.level >= MAX_LEVEL && self.class.promotes_to().is_none() {
+            return None;
+        }
+
+        self.exp += exp;
+        if self.exp < EXP_PER_LEVEL {
+            return None;
+        }
+        self.exp -= EXP_PER_LEVEL;
+
+        let growths = self.growths + self.class.data().growth_modifiers;
+        let gains = Stats::default().zip_with(growths, |_, growth| {
+            i32::from(self.rng.gen_range(0, 100) < growth)
+        });
+        self.apply_gains(gains);
+        self.level += 1;
+
+        if self.level >= MAX_LEVEL {
+            if self.class.promotes_to().is_some() {
+                self.promote();
+            } else {
+                self.exp = 0;
+            }
+        }
+
+        Some(gains)
+    }
+
+    pub fn can_use_item(&self, item: Item) -> bool {
+        if !self.items.contains(&item) {
+            return false;
+        }
+
+        match item {
+            Item::MasterSeal => self.level >= PROMOTION_LEVEL && self.class.promotes_to().is_some(),
+            Item::AttackTonic | Item::DefenceTonic => true,
+        }
+    }
+
+    /// Consumes the item and applies its effect, returns false if it could not be used
+    pub fn use_item(&mut self, item: Item) -> bool {
+        if !self.can_use_item(item) {
+            return false;
+        }
+
+        let idx = self.items.iter().position(|held| *held == item).unwrap();
+        self.items.remove(idx);
+        match item {
+            Item::MasterSeal => self.promote(),
+            Item::AttackTonic => {
+                self.add_status(StatusEffect::new(StatusKind::AttackUp, TONIC_TURNS));
+            }
+            Item::DefenceTonic => {
+                self.add_status(StatusEffect::new(StatusKind::DefenceUp, TONIC_TURNS));
+            }
+        }
+
+        true
+    }
+
+    fn promote(&mut self) {
+        let Some(promoted) = self.class.promotes_to() else {
+            return;
+        };
+
+        let gains = promoted.data().base_stats - self.class.data().base_stats;
+        self.class = promoted;
+        self.level = 1;
+        self.exp = 0;
+        self.apply_gains(gains);
+    }
+
+    fn apply_gains(&mut self, gains: Stats) {
+        let old_hp = self.stats.hp;
+        self.stats = (self.stats + gains).clamp_to(self.class.data().stat_caps);
+        self.curr_health += self.stats.hp - old_hp;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ErasedUnit {
+    pub class: UnitClass,
+    pub level: u32,
+    pub stats: Stats,
+    pub growths: Stats,
+    pub faction: Faction,
+    pub curr_health: i32,
+    pub pos: Point,
+    pub texture_path: String,
+    pub weapon: Option<Weapon>,
+    pub items: Vec<Item>,
+    pub skills: Vec<Skill>,
+
+    // TODO This should probably be removed
+    pub turn_complete: bool,
+}
+
+impl ErasedUnit {
+    /// A level 1 unit with the base stats of its class
+    pub fn with_class(
+        class: UnitClass,
+        faction: Faction,
+        pos: impl Into<Point>,
+        texture_path: &str,
+    ) -> Self {
+        let stats = class.data().base_stats;
+        Self {
+            class,
+            level: 1,
+            stats,
+            growths: Stats::new(60, 40, 40, 40, 40, 40, 30, 30),
+            faction,
+            curr_health: stats.hp,
+            pos: pos.into(),
+            texture_path: texture_path.to_string(),
+            weapon: None,
+            items: Vec::new(),
+            skills: Vec::new(),
+            turn_complete: false,
+        }
+    }
+}
+
+macro_rules! create_id {
+    ($name: ident) => {
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+        pub struct $name(u32);
+        impl Deref for $name {
+            type Target = u32;
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+        impl DerefMut for $name {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+        impl $name {
+            pub fn new(id: u32) -> Self {
+                Self(id)
+            }
+
+            pub fn next(&mut self) {
+                self.0 += 1;
+            }
+        }
+    };
+}
+
+create_id!(WeaponId);
+create_id!(UnitId);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct Weapon {
+    id: WeaponId,
+    pub kind: WeaponKind,
+    pub might: i32,
+    pub max_range: i32,
+    /// Inflicted on the target when an attack with this weapon lands
+    pub effect: Option<StatusEffect>,
+}
+
+impl Weapon {
+    pub fn new(id: WeaponId, kind: WeaponKind, might: i32, max_range: i32) -> Self {
+        Self {
+            id,
+            kind,
+            might,
+            max_range,
+            effect: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_effect(self, effect: StatusEffect) -> Self {
+        Self {
+            effect: Some(effect),
+            ..self
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum WeaponKind {
+    Sword,
+    Lance,
+    Axe,
+    Bow,
+    Tome,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Item {
+    MasterSeal,
+    AttackTonic,
+    DefenceTonic,
+}
+
+impl Item {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::MasterSeal => "Master Seal",
+            Self::AttackTonic => "Attack Tonic",
+            Self::DefenceTonic => "Defence Tonic",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Skill {
+    /// Moves through hostile units and ignores their zone of control
+    Pass,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum MovementClass {
+    Infantry,
+    Mounted,
+    Flying,
+}
+
+impl MovementClass {
+    #[allow(clippy::match_same_arms)]
+    pub fn get_movement_cost(self, terrain: Terrain) -> u32 {
+        match (self, terrain) {
+            // Mounted in Forest
+            (MovementClass::Mounted, Terrain::Forest) => 2,
+            // Flying
+            (MovementClass::Flying, _) => 1,
+            // Infantary in Forest
+            (MovementClass::Infantry, Terrain::Forest) => 1,
+            // Normal Ground
+            (_, Terrain::Ground) => 1,
+            // Impassable
+            (_, Terrain::Mountain | Terrain::River) => DijkstraMap::UNREACHABLE,
+        }
+    }
+}
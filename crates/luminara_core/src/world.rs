@@ -0,0 +1,395 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::command::Command;
+use crate::event::Reinforcement;
+use crate::event::TriggerAction;
+use crate::event::WorldEvent;
+use crate::event::evaluate_triggers;
+use crate::map::Map;
+use crate::map::Terrain;
+use crate::math::Point;
+use crate::pathfinding::DijkstraMap;
+use crate::rng::Rng;
+use crate::snapshot::WorldSnapshot;
+use crate::status::StatusEffect;
+use crate::unit::ErasedUnit;
+use crate::unit::Item;
+use crate::unit::Unit;
+use crate::unit::UnitId;
+use serde::{Deserialize, Serialize};
+
+// TODO Make a builder for this
+pub struct WorldState {
+    units: HashMap<UnitId, Unit>,
+    /// Unit standing on each tile, indexed like the map
+    occupancy: Vec<Option<UnitId>>,
+    pub map: Map,
+    pub turn: u32,
+    /// Faction whose phase it is
+    pub phase: Faction,
+    pub objective: Objective,
+    pub rng: Rng,
+    next_unit_id: UnitId,
+    events: Vec<WorldEvent>,
+}
+
+impl WorldState {
+    pub fn new(map: Map, seed: u64) -> Self {
+        Self {
+            units: HashMap::with_capacity(20),
+            occupancy: vec![None; map.width * map.height],
+            map,
+            turn: 0,
+            phase: Faction::Player,
+            objective: Objective::Rout,
+            rng: Rng::new(seed),
+            next_unit_id: UnitId::new(0),
+            events: Vec::new(),
+        }
+    }
+
+    /// Spawns the unit on its tile or the nearest free one if that is taken
+    pub fn spawn_units(&mut self, unit: &ErasedUnit) -> Option<UnitId> {
+        let movement_class = unit.class.data().movement_class;
+        let pos = self.nearest_free_tile(unit.pos, |terrain| {
+            movement_class.get_movement_cost(terrain) != DijkstraMap::UNREACHABLE
+        })?;
+
+        let id = self.next_unit_id;
+        let mut spawned = Unit::from_erased(id, unit, self.rng.next_u64());
+        spawned.pos = pos;
+        self.set_occupant(pos, Some(id));
+        self.units.insert(id, spawned);
+        self.next_unit_id.next();
+
+        Some(id)
+    }
+
+    /// Breadth first search outwards from `pos` for an unoccupied tile the unit can stand on,
+    /// `None` if `pos` is off the map
+    pub fn nearest_free_tile(
+        &self,
+        pos: Point,
+        can_stand: impl Fn(Terrain) -> bool,
+    ) -> Option<Point> {
+        if !self.map.in_bounds(pos) {
+            return None;
+        }
+        let mut visited = HashSet::from([pos]);
+        let mut queue = VecDeque::from([pos]);
+
+        while let Some(pt) = queue.pop_front() {
+            if self.is_tile_empty(pt) && can_stand(self.map.get_terrain(pt)) {
+                return Some(pt);
+            }
+
+            for next in self.map.topology.neighbours(pt) {
+                if self.map.in_bounds(next) && visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Removes and returns the reinforcements arriving in the current phase of `faction`
+    pub fn take_reinforcements(&mut self, faction: Faction) -> Vec<Reinforcement> {
+        let (arriving, pending) = std::mem::take(&mut self.map.reinforcements)
+            .into_iter()
+            .partition(|reinforcement| {
+                reinforcement.turn == self.turn && reinforcement.unit.faction == faction
+            });
+        self.map.reinforcements = pending;
+
+        arriving
+    }
+
+    /// Prepares the units for the start of `faction`'s phase
+    pub fn setup_turn(&mut self, faction: Faction) {
+        if faction == Faction::Player {
+            self.turn += 1;
+        }
+        self.phase = faction;
+
+        self.units.iter_mut().for_each(|(_, unit)| {
+            unit.turn_complete = false;
+            if unit.faction == faction {
+                unit.tick_status_effects();
+            }
+        });
+        self.events.push(WorldEvent::TurnStarted {
+            turn: self.turn,
+            faction,
+        });
+    }
+
+    pub fn snapshot(&self) -> WorldSnapshot {
+        let mut units: Vec<Unit> = self.units.values().cloned().collect();
+        units.sort_by_key(|unit| *unit.id());
+
+        WorldSnapshot {
+            terrain: self.map.to_ascii(),
+            topology: self.map.topology,
+            zone_of_control: self.map.zone_of_control,
+            turn: self.turn,
+            phase: self.phase,
+            objective: self.objective,
+            rng: self.rng,
+            next_unit_id: self.next_unit_id,
+            units,
+            triggers_fired: self
+                .map
+                .triggers
+                .iter()
+                .map(|trigger| !trigger.is_active())
+                .collect(),
+            reinforcement_turns: self
+                .map
+                .reinforcements
+                .iter()
+                .map(|reinforcement| reinforcement.turn)
+                .collect(),
+        }
+    }
+
+    /// Puts the world back into the state of the snapshot. The map has to be the one the
+    /// snapshot was taken from, its triggers and reinforcements are only marked as used up.
+    ///
+    /// # Errors
+    /// If the snapshot does not fit the map or its units overlap, the world is left as it was
+    pub fn restore(&mut self, snapshot: &WorldSnapshot) -> Result<(), String> {
+        let rows: Vec<&str> = snapshot.terrain.iter().map(String::as_str).collect();
+        let map = Map::from_ascii(&rows, snapshot.topology).ok_or("Malformed terrain")?;
+        if (map.width, map.height) != (self.map.width, self.map.height)
+            || snapshot.triggers_fired.len() != self.map.triggers.len()
+        {
+            return Err("The snapshot was taken on another map".into());
+        }
+        let (mut ids, mut positions) = (HashSet::new(), HashSet::new());
+        for unit in &snapshot.units {
+            if !map.in_bounds(unit.pos) {
+                return Err(format!("Unit {} is off the map", *unit.id()));
+            }
+            if !ids.insert(unit.id()) {
+                return Err(format!("Unit {} is in the snapshot twice", *unit.id()));
+            }
+            if !positions.insert(unit.pos) {
+                return Err(format!("Unit {} stands on another unit", *unit.id()));
+            }
+        }
+
+        for y in 0..self.map.height {
+            for x in 0..self.map.width {
+                let pt = Point::new(x.try_into().unwrap(), y.try_into().unwrap());
+                self.map.set_terrain(pt, map.get_terrain(pt));
+            }
+        }
+        self.map.topology = snapshot.topology;
+        self.map.zone_of_control = snapshot.zone_of_control;
+        for (trigger, fired) in self.map.triggers.iter_mut().zip(&snapshot.triggers_fired) {
+            trigger.set_fired(*fired);
+        }
+        // Arrivals happen in phase order, Player before Enemy
+        let arrived = |reinforcement: &Reinforcement| {
+            let phase = |faction| u8::from(faction == Faction::Enemy);
+            (reinforcement.turn, phase(reinforcement.unit.faction))
+                <= (snapshot.turn, phase(snapshot.phase))
+        };
+        self.map
+            .reinforcements
+            .retain(|reinforcement| !arrived(reinforcement));
+
+        self.units.clear();
+        self.occupancy.fill(None);
+        for unit in &snapshot.units {
+            self.set_occupant(unit.pos, Some(unit.id()));
+            self.units.insert(unit.id(), unit.clone());
+        }
+        self.turn = snapshot.turn;
+        self.phase = snapshot.phase;
+        self.objective = snapshot.objective;
+        self.rng = snapshot.rng;
+        self.next_unit_id = snapshot.next_unit_id;
+        self.events.clear();
+
+        Ok(())
+    }
+
+    /// Fingerprint of everything that decides how the battle goes on, two worlds that
+    /// played the same commands from the same seed end up with the same checksum
+    pub fn checksum(&self) -> u64 {
+        self.snapshot().checksum()
+    }
+
+    /// Applies the command and returns where reinforcements arrived, if any did
+    pub fn apply_command(&mut self, command: Command) -> Vec<Point> {
+        match command {
+            Command::CommitUnit(unit) => self.commit_unit(unit),
+            Command::SetupTurn(faction) => {
+                self.setup_turn(faction);
+                return self
+                    .take_reinforcements(faction)
+                    .into_iter()
+                    .filter_map(|reinforcement| {
+                        let mut unit = reinforcement.unit;
+                        unit.turn_complete = !reinforcement.ambush;
+                        let id = self.spawn_units(&unit)?;
+                        self.get_unit(id).map(|unit| unit.pos)
+                    })
+                    .collect();
+            }
+            Command::DamageUnit(id, damage) => self.damage_unit(id, damage),
+            Command::ApplyStatus(id, effect) => self.apply_status(id, effect),
+            Command::Talk(initiator, target) => self.talk(initiator, target),
+        }
+
+        Vec::new()
+    }
+
+    pub fn commit_unit(&mut self, unit: Unit) {
+        let old_pos = self.units.get(&unit.id()).map(|old| old.pos);
+        if old_pos != Some(unit.pos) {
+            if let Some(old_pos) = old_pos {
+                self.set_occupant(old_pos, None);
+            }
+            self.set_occupant(unit.pos, Some(unit.id()));
+            self.events.push(WorldEvent::UnitMoved {
+                id: unit.id(),
+                to: unit.pos,
+            });
+        }
+        self.units.insert(unit.id(), unit);
+    }
+
+    /// Damages the unit and removes it from the map if it is defeated
+    pub fn damage_unit(&mut self, id: UnitId, damage: i32) {
+        let Some(unit) = self.units.get_mut(&id) else {
+            return;
+        };
+        unit.take_damage(damage);
+        if unit.curr_health <= 0 {
+            let pos = unit.pos;
+            self.units.remove(&id);
+            self.set_occupant(pos, None);
+            self.events.push(WorldEvent::UnitDefeated(id));
+        }
+    }
+
+    pub fn apply_status(&mut self, id: UnitId, effect: StatusEffect) {
+        if let Some(unit) = self.units.get_mut(&id) {
+            unit.add_status(effect);
+        }
+    }
+
+    pub fn give_item(&mut self, id: UnitId, item: Item) {
+        if let Some(unit) = self.units.get_mut(&id) {
+            unit.items.push(item);
+        }
+    }
+
+    pub fn talk(&mut self, initiator: UnitId, target: UnitId) {
+        self.events.push(WorldEvent::UnitsTalked(initiator, target));
+    }
+
+    pub fn can_talk(&self, initiator: UnitId, target: UnitId) -> bool {
+        self.map
+            .triggers
+            .iter()
+            .any(|trigger| trigger.is_talk_between(initiator, target))
+    }
+
+    /// Checks the map's triggers against everything that happened since the last call
+    pub fn evaluate_triggers(&mut self) -> Vec<TriggerAction> {
+        let events = std::mem::take(&mut self.events);
+        let mut triggers = std::mem::take(&mut self.map.triggers);
+        let actions = evaluate_triggers(&mut triggers, self, &events);
+        self.map.triggers = triggers;
+
+        actions
+    }
+
+    /// Lowest id first so the order units act in does not depend on hashing
+    pub fn get_unmoved_unit(&self, faction: Faction) -> Option<&Unit> {
+        self.units
+            .values()
+            .filter(|unit| unit.faction == faction && !unit.turn_complete)
+            .min_by_key(|unit| *unit.id())
+    }
+
+    pub fn units(&self) -> impl Iterator<Item = &Unit> {
+        self.units.values()
+    }
+
+    pub fn get_unit(&self, id: UnitId) -> Option<&Unit> {
+        self.units.get(&id)
+    }
+
+    pub fn unit_at(&self, pos: impl Into<Point>) -> Option<&Unit> {
+        let pos = pos.into();
+        if !self.map.in_bounds(pos) {
+            return None;
+        }
+        self.occupancy[self.map.point_to_idx(pos)].and_then(|id| self.units.get(&id))
+    }
+
+    /// Units within `range` tiles of `from`, not counting one standing on `from`
+    pub fn units_in_range(&self, from: Point, range: i32) -> impl Iterator<Item = &Unit> {
+        self.map
+            .topology
+            .tiles_in_range(from, range)
+            .filter_map(|pt| self.unit_at(pt))
+    }
+
+    pub fn enemies_of<'a>(&'a self, unit: &'a Unit) -> impl Iterator<Item = &'a Unit> {
+        self.units
+            .values()
+            .filter(|other| other.id() != unit.id() && other.is_hostile_to(unit))
+    }
+
+    pub fn is_tile_empty(&self, pos: impl Into<Point>) -> bool {
+        self.unit_at(pos).is_none()
+    }
+
+    fn set_occupant(&mut self, pos: Point, id: Option<UnitId>) {
+        let idx = self.map.point_to_idx(pos);
+        self.occupancy[idx] = id;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Objective {
+    Rout,
+    Seize(Point),
+    Survive { turns: u32 },
+    Defeat(UnitId),
+}
+
+impl Objective {
+    pub fn description(self) -> String {
+        match self {
+            Self::Rout => "Defeat all enemies".to_string(),
+            Self::Seize(pt) => format!("Seize the tile at ({}, {})", pt.x, pt.y),
+            Self::Survive { turns } => format!("Survive for {turns} turns"),
+            Self::Defeat(id) => format!("Defeat unit {}", *id),
+        }
+    }
+}
+
+// TODO May be move this to unit.rs
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Faction {
+    Player,
+    Enemy,
+}
+
+impl Faction {
+    /// The faction whose phase comes next
+    #[must_use]
+    pub fn opponent(self) -> Self {
+        match self {
+            Self::Player => Self::Enemy,
+            Self::Enemy => Self::Player,
+        }
+    }
+}
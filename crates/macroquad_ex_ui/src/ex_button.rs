@@ -22,9 +22,12 @@ impl XButton {
 
     #[inline]
     pub fn update(&mut self) {
+        // The mouse counts as one more finger on desktop
         self.is_pressed = touches()
             .iter()
-            .any(|touch| self.rect.contains(touch.position));
+            .any(|touch| self.rect.contains(touch.position))
+            || (is_mouse_button_down(MouseButton::Left)
+                && self.rect.contains(mouse_position().into()));
     }
 
     #[inline]
@@ -50,7 +53,11 @@ impl XButton {
             TextParams {
                 font,
                 font_size: 50,
-                color: BLACK,
+                // Fades along with the button
+                color: Color {
+                    a: self.color.a,
+                    ..BLACK
+                },
                 ..Default::default()
             },
         );
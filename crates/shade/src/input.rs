@@ -1,8 +1,11 @@
 use enumset::{EnumSet, EnumSetType};
+use input_lib::{BindingTarget, Bindings, Physical};
+use macroquad::input::{KeyCode, is_key_down};
 
 pub struct InputManager {
     curr_input: Input,
     last_input: Input,
+    bindings: Bindings<Binding>,
 }
 impl InputManager {
     #[inline]
@@ -10,9 +13,21 @@ impl InputManager {
         Self {
             curr_input: Input::new(),
             last_input: Input::new(),
+            bindings: default_bindings(),
         }
     }
 
+    #[inline]
+    #[must_use]
+    pub fn bindings(&self) -> &Bindings<Binding> {
+        &self.bindings
+    }
+
+    #[inline]
+    pub fn set_bindings(&mut self, bindings: Bindings<Binding>) {
+        self.bindings = bindings;
+    }
+
     #[inline]
     #[must_use]
     pub fn get_curr_input(&self) -> Input {
@@ -41,7 +56,27 @@ impl InputManager {
     }
 
     pub(crate) fn update(&mut self) {
-        todo!()
+        self.last_input = self.curr_input;
+        let mut input = Input::new();
+        for (physical, binding) in self.bindings.entries() {
+            let Physical::Key(key) = physical else {
+                continue;
+            };
+            if !is_key_down(key) {
+                continue;
+            }
+            match binding {
+                Binding::Up => input.dpad_y += 1,
+                Binding::Down => input.dpad_y -= 1,
+                Binding::Left => input.dpad_x -= 1,
+                Binding::Right => input.dpad_x += 1,
+                Binding::Button(button) => input.buttons |= button,
+            }
+        }
+        // Opposite directions cancel out, arrows and WASD held together still move one tile
+        input.dpad_x = input.dpad_x.signum();
+        input.dpad_y = input.dpad_y.signum();
+        self.curr_input = input;
     }
 
     pub(crate) fn render(&mut self) {
@@ -77,3 +112,71 @@ pub enum Button {
     Start,
     Select,
 }
+
+/// What a physical input can drive, bound in the same table format as `input_lib`'s controller
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Binding {
+    Up,
+    Down,
+    Left,
+    Right,
+    Button(Button),
+}
+
+impl BindingTarget for Binding {
+    const ALL: &'static [Self] = &[
+        Self::Up,
+        Self::Down,
+        Self::Left,
+        Self::Right,
+        Self::Button(Button::A),
+        Self::Button(Button::B),
+        Self::Button(Button::X),
+        Self::Button(Button::Y),
+        Self::Button(Button::L),
+        Self::Button(Button::R),
+        Self::Button(Button::Start),
+        Self::Button(Button::Select),
+    ];
+
+    #[inline]
+    fn name(self) -> &'static str {
+        match self {
+            Self::Up => "Up",
+            Self::Down => "Down",
+            Self::Left => "Left",
+            Self::Right => "Right",
+            Self::Button(Button::A) => "A",
+            Self::Button(Button::B) => "B",
+            Self::Button(Button::X) => "X",
+            Self::Button(Button::Y) => "Y",
+            Self::Button(Button::L) => "L",
+            Self::Button(Button::R) => "R",
+            Self::Button(Button::Start) => "Start",
+            Self::Button(Button::Select) => "Select",
+        }
+    }
+}
+
+/// The same table as `input_lib`'s default controller bindings
+fn default_bindings() -> Bindings<Binding> {
+    let key = |key, binding| (Physical::Key(key), binding);
+    Bindings::new(&[
+        key(KeyCode::Up, Binding::Up),
+        key(KeyCode::Down, Binding::Down),
+        key(KeyCode::Left, Binding::Left),
+        key(KeyCode::Right, Binding::Right),
+        key(KeyCode::W, Binding::Up),
+        key(KeyCode::S, Binding::Down),
+        key(KeyCode::A, Binding::Left),
+        key(KeyCode::D, Binding::Right),
+        key(KeyCode::Z, Binding::Button(Button::A)),
+        key(KeyCode::X, Binding::Button(Button::B)),
+        key(KeyCode::C, Binding::Button(Button::X)),
+        key(KeyCode::V, Binding::Button(Button::Y)),
+        key(KeyCode::Q, Binding::Button(Button::L)),
+        key(KeyCode::E, Binding::Button(Button::R)),
+        key(KeyCode::Enter, Binding::Button(Button::Start)),
+        key(KeyCode::Backspace, Binding::Button(Button::Select)),
+    ])
+}
@@ -1,5 +1,8 @@
 use std::collections::HashMap;
 
+use crate::dialogue::DialogueScript;
+
+use macroquad::file::load_string;
 use macroquad::texture::Texture2D;
 use macroquad::texture::load_texture;
 
@@ -41,3 +44,44 @@ impl TextureStore {
         self.textures.get(path).cloned().unwrap()
     }
 }
+
+#[derive(Debug)]
+pub struct DialogueStore {
+    scripts: HashMap<String, DialogueScript>,
+    to_load: Vec<String>,
+}
+
+impl DialogueStore {
+    pub fn new() -> Self {
+        Self {
+            scripts: HashMap::new(),
+            to_load: Vec::new(),
+        }
+    }
+
+    pub fn schedule_load(&mut self, path: &str) {
+        if self.scripts.contains_key(path) {
+            return;
+        }
+
+        self.to_load.push(path.to_string());
+    }
+
+    pub async fn load_all(&mut self) {
+        for path in self.to_load.drain(0..) {
+            let json = load_string(&path).await.expect(&path);
+            let script = DialogueScript::from_json(&json).expect(&path);
+            self.scripts.insert(path, script);
+        }
+    }
+
+    /// Adds a script that was loaded some other way
+    pub fn insert(&mut self, path: &str, script: DialogueScript) {
+        self.scripts.insert(path.to_string(), script);
+    }
+
+    #[must_use]
+    pub fn get(&self, path: &str) -> Option<&DialogueScript> {
+        self.scripts.get(path)
+    }
+}
@@ -0,0 +1,11 @@
+//! Plays a battle between bots, see `luminara::bot`
+
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    if let Err(err) = luminara::bot::run() {
+        eprintln!("{err}\n{}", luminara::bot::USAGE);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,5 @@
+//! Plays Luminara in a terminal, see `luminara::tty`
+
+fn main() {
+    luminara::tty::run();
+}
@@ -0,0 +1,448 @@
+//! JSON-RPC 2.0 interface that lets an external process play a faction instead of the
+//! built-in AI, one message per line over TCP or stdio.
+//!
+//! During its faction's phase the bot may call
+//! - `observe`, the turn, the map and every unit on it
+//! - `legal_actions`, every [`Action`] the faction may take right now
+//! - `act` with an [`Action`] as params, illegal actions are answered with an error
+//!
+//! The game sends the notifications `turn_started` when the bot's phase begins,
+//! `timed_out` when the bot took too long and a unit waited in its place, and
+//! `battle_ended` with the winner once the headless runner is done.
+//!
+//! `cargo run --bin luminara_bot -- --player tcp:127.0.0.1:7777 --enemy stdio`
+
+use crate::action::{self, Action};
+use crate::assets::{DialogueStore, TextureStore};
+use crate::class::{Stats, UnitClass};
+use crate::game::Engine;
+use crate::grid::GridTopology;
+use crate::lines::read_lines;
+use crate::map::Map;
+use crate::math::Point;
+use crate::rng::Rng;
+use crate::status::StatusEffect;
+use crate::tty::FRAME_TIME;
+use crate::unit::{Item, Skill, Unit, UnitId, WeaponKind};
+use crate::world::{Faction, WorldState};
+
+use input_lib::ButtonState;
+use macroquad::logging::info;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+const ILLEGAL_ACTION: i32 = 1;
+
+#[derive(Deserialize, Debug)]
+struct Request {
+    jsonrpc: String,
+    /// Missing for notifications, which get no response
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// What the bot sees of the battle, everything is visible to both sides
+#[derive(Serialize, Debug)]
+pub struct Observation {
+    pub turn: u32,
+    pub faction: Faction,
+    pub objective: String,
+    pub topology: GridTopology,
+    pub zone_of_control: bool,
+    /// Rows of [`crate::map::Terrain::symbol`] glyphs
+    pub map: Vec<String>,
+    pub units: Vec<UnitView>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct UnitView {
+    pub id: UnitId,
+    pub class: UnitClass,
+    pub faction: Faction,
+    pub pos: Point,
+    pub hp: i32,
+    pub max_hp: i32,
+    pub level: u32,
+    pub exp: u32,
+    pub stats: Stats,
+    pub weapon: Option<WeaponView>,
+    pub attack_range: i32,
+    pub items: Vec<Item>,
+    pub skills: Vec<Skill>,
+    pub status_effects: Vec<StatusEffect>,
+    pub turn_complete: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct WeaponView {
+    pub kind: WeaponKind,
+    pub might: i32,
+    pub range: i32,
+    pub effect: Option<StatusEffect>,
+}
+
+impl Observation {
+    pub fn new(world: &WorldState, faction: Faction) -> Self {
+        let mut units: Vec<UnitView> = world.units().map(UnitView::new).collect();
+        units.sort_by_key(|unit| *unit.id);
+
+        Self {
+            turn: world.turn,
+            faction,
+            objective: world.objective.description(),
+            topology: world.map.topology,
+            zone_of_control: world.map.zone_of_control,
+            map: world.map.to_ascii(),
+            units,
+        }
+    }
+}
+
+impl UnitView {
+    fn new(unit: &Unit) -> Self {
+        Self {
+            id: unit.id(),
+            class: unit.class(),
+            faction: unit.faction,
+            pos: unit.pos,
+            hp: unit.curr_health,
+            max_hp: unit.max_health(),
+            level: unit.level,
+            exp: unit.exp,
+            stats: unit.effective_stats(),
+            weapon: unit.weapon.map(|weapon| WeaponView {
+                kind: weapon.kind,
+                might: weapon.might,
+                range: weapon.max_range,
+                effect: weapon.effect,
+            }),
+            attack_range: unit.get_attack_range(),
+            items: unit.items.clone(),
+            skills: unit.skills.clone(),
+            status_effects: unit.status_effects.clone(),
+            turn_complete: unit.turn_complete,
+        }
+    }
+}
+
+/// Outcome of one line from the bot
+#[derive(Debug)]
+pub struct Handled {
+    /// Sent back as is, `None` for notifications
+    pub response: Option<Value>,
+    /// A legal action the bot chose
+    pub action: Option<Action>,
+}
+
+fn success(id: &Value, result: &Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn failure(id: &Value, code: i32, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Answers a request of the bot playing `faction`, without side effects on the world
+#[must_use]
+pub fn handle_line(line: &str, world: &WorldState, faction: Faction) -> Handled {
+    let reply = |response| Handled {
+        response: Some(response),
+        action: None,
+    };
+    let Ok(value) = serde_json::from_str::<Value>(line) else {
+        return reply(failure(&Value::Null, PARSE_ERROR, "Parse error"));
+    };
+    let request = match serde_json::from_value::<Request>(value) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        _ => return reply(failure(&Value::Null, INVALID_REQUEST, "Invalid request")),
+    };
+
+    let (result, action) = match request.method.as_str() {
+        "observe" => (Ok(json!(Observation::new(world, faction))), None),
+        "legal_actions" => (Ok(json!(action::legal_actions(world, faction))), None),
+        "act" => match serde_json::from_value::<Action>(request.params) {
+            Ok(action) if action::is_legal(world, faction, action) => {
+                (Ok(Value::Null), Some(action))
+            }
+            Ok(_) => (Err((ILLEGAL_ACTION, "Illegal action")), None),
+            Err(_) => (Err((INVALID_PARAMS, "Params must be an action")), None),
+        },
+        _ => (Err((METHOD_NOT_FOUND, "Method not found")), None),
+    };
+
+    let response = request.id.map(|id| match result {
+        Ok(result) => success(&id, &result),
+        Err((code, message)) => failure(&id, code, message),
+    });
+    Handled { response, action }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BotEvent {
+    Act(Action),
+    Disconnected,
+}
+
+/// Connection to one bot, lines are read on their own thread so polling never blocks
+pub struct BotLink {
+    incoming: Receiver<String>,
+    output: Box<dyn Write + Send>,
+    timeout: Duration,
+    /// When the game started waiting for the bot's next action
+    waiting_since: Option<Instant>,
+}
+
+impl BotLink {
+    pub fn new(
+        input: impl BufRead + Send + 'static,
+        output: impl Write + Send + 'static,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            incoming: read_lines(input),
+            output: Box::new(output),
+            timeout,
+            waiting_since: None,
+        }
+    }
+
+    #[must_use]
+    pub fn stdio(timeout: Duration) -> Self {
+        Self::new(BufReader::new(std::io::stdin()), std::io::stdout(), timeout)
+    }
+
+    /// Waits for the bot to connect to `addr`
+    ///
+    /// # Errors
+    /// If `addr` cannot be listened on or the connection fails
+    pub fn listen(addr: &str, timeout: Duration) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        info!("Waiting for a bot on {}", listener.local_addr()?);
+        let (stream, _) = listener.accept()?;
+        Ok(Self::new(
+            BufReader::new(stream.try_clone()?),
+            stream,
+            timeout,
+        ))
+    }
+
+    fn send(&mut self, message: &Value) {
+        // A bot that went away shows up as a closed input, so write errors are ignored
+        let _ = writeln!(self.output, "{message}");
+        let _ = self.output.flush();
+    }
+
+    pub fn notify(&mut self, method: &str, params: &Value) {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    #[must_use]
+    pub fn is_waiting(&self) -> bool {
+        self.waiting_since.is_some()
+    }
+
+    /// Answers everything the bot sent so far, returns the first action it took. When the
+    /// bot takes too long the lowest id unit waits instead.
+    pub fn poll(&mut self, world: &WorldState, faction: Faction) -> Option<BotEvent> {
+        let waiting_since = *self.waiting_since.get_or_insert_with(Instant::now);
+        loop {
+            match self.incoming.try_recv() {
+                Ok(line) => {
+                    let handled = handle_line(&line, world, faction);
+                    if let Some(response) = handled.response {
+                        self.send(&response);
+                    }
+                    if let Some(action) = handled.action {
+                        self.waiting_since = None;
+                        return Some(BotEvent::Act(action));
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Some(BotEvent::Disconnected),
+            }
+        }
+
+        if waiting_since.elapsed() < self.timeout {
+            return None;
+        }
+        self.waiting_since = None;
+        let unit = action::controllable_units(world, faction).min_by_key(|unit| *unit.id())?;
+        self.notify("timed_out", &json!({ "unit": unit.id() }));
+        Some(BotEvent::Act(Action::Wait { unit: unit.id() }))
+    }
+}
+
+/// Bots attached to the battle, factions without one use the built-in control
+#[derive(Default)]
+pub struct Bots {
+    links: HashMap<Faction, BotLink>,
+}
+
+impl Bots {
+    pub fn attach(&mut self, faction: Faction, link: BotLink) {
+        self.links.insert(faction, link);
+    }
+
+    pub fn detach(&mut self, faction: Faction) -> Option<BotLink> {
+        self.links.remove(&faction)
+    }
+
+    #[must_use]
+    pub fn controls(&self, faction: Faction) -> bool {
+        self.links.contains_key(&faction)
+    }
+
+    pub fn get_mut(&mut self, faction: Faction) -> Option<&mut BotLink> {
+        self.links.get_mut(&faction)
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        self.links.values().any(BotLink::is_waiting)
+    }
+
+    pub fn notify_all(&mut self, method: &str, params: &Value) {
+        for link in self.links.values_mut() {
+            link.notify(method, params);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Args {
+    seed: u64,
+    topology: GridTopology,
+    timeout: Duration,
+    max_turns: u32,
+    player: Option<String>,
+    enemy: Option<String>,
+}
+
+pub const USAGE: &str = "Usage: luminara_bot --player stdio|tcp:ADDR [--enemy stdio|tcp:ADDR] \
+[--seed S] [--hex] [--timeout SECS] [--max-turns T]";
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self {
+            seed: 0,
+            topology: GridTopology::Square,
+            timeout: DEFAULT_TIMEOUT,
+            max_turns: 50,
+            player: None,
+            enemy: None,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+            match arg.as_str() {
+                "--seed" => parsed.seed = value()?.parse().map_err(|_| "Invalid --seed")?,
+                "--hex" => parsed.topology = GridTopology::Hex,
+                "--timeout" => {
+                    let secs: f64 = value()?.parse().map_err(|_| "Invalid --timeout")?;
+                    parsed.timeout =
+                        Duration::try_from_secs_f64(secs).map_err(|_| "Invalid --timeout")?;
+                }
+                "--max-turns" => {
+                    parsed.max_turns = value()?.parse().map_err(|_| "Invalid --max-turns")?;
+                }
+                "--player" => parsed.player = Some(value()?),
+                "--enemy" => parsed.enemy = Some(value()?),
+                other => return Err(format!("Unknown argument {other}")),
+            }
+        }
+
+        if parsed.player.is_none() {
+            return Err("Nobody can play the player phase headless, --player is required".into());
+        }
+        if parsed.player.as_deref() == Some("stdio") && parsed.enemy.as_deref() == Some("stdio") {
+            return Err("Only one bot can use stdio".into());
+        }
+        Ok(parsed)
+    }
+}
+
+fn connect(spec: &str, timeout: Duration) -> Result<BotLink, String> {
+    if spec == "stdio" {
+        return Ok(BotLink::stdio(timeout));
+    }
+    let addr = spec
+        .strip_prefix("tcp:")
+        .ok_or(format!("Unknown bot {spec}, expected stdio or tcp:ADDR"))?;
+    BotLink::listen(addr, timeout).map_err(|err| format!("Could not listen on {addr}: {err}"))
+}
+
+fn battle_over(world: &WorldState, max_turns: u32) -> bool {
+    winner(world).is_some() || world.units().next().is_none() || world.turn > max_turns
+}
+
+/// The only faction left on the map
+fn winner(world: &WorldState) -> Option<Faction> {
+    let alive = |faction| world.units().any(|unit| unit.faction == faction);
+    match (alive(Faction::Player), alive(Faction::Enemy)) {
+        (true, false) => Some(Faction::Player),
+        (false, true) => Some(Faction::Enemy),
+        _ => None,
+    }
+}
+
+fn play(args: &Args) -> Result<(), String> {
+    let mut rng = Rng::new(args.seed);
+    let map = Map::random(30, 20, args.topology, &mut rng);
+    // Without dialogues nothing stops to wait for a human
+    let mut engine = Engine::new(
+        map,
+        rng.next_u64(),
+        TextureStore::new(),
+        DialogueStore::new(),
+    );
+    for (faction, spec) in [
+        (Faction::Player, &args.player),
+        (Faction::Enemy, &args.enemy),
+    ] {
+        if let Some(spec) = spec {
+            engine.attach_bot(faction, connect(spec, args.timeout)?);
+        }
+    }
+
+    loop {
+        engine.step(ButtonState::default(), FRAME_TIME);
+        if battle_over(engine.world(), args.max_turns) {
+            break;
+        }
+        if engine.waits_for_input() {
+            return Err("The battle is waiting for a human, did a bot disconnect?".into());
+        }
+        if engine.waits_for_bot() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    let winner = winner(engine.world());
+    let turn = engine.world().turn;
+    engine
+        .bots_mut()
+        .notify_all("battle_ended", &json!({ "winner": winner, "turn": turn }));
+    info!("Battle ended on turn {turn}, winner {winner:?}");
+    Ok(())
+}
+
+/// Plays one battle with the arguments given on the command line
+///
+/// # Errors
+/// If the arguments are malformed, or a bot could not be reached or dropped out
+pub fn run() -> Result<(), String> {
+    Args::parse(std::env::args().skip(1)).and_then(|args| play(&args))
+}
@@ -1,24 +1,23 @@
+use crate::map::Map;
 use crate::math::Point;
-use crate::prelude::Bounds2D;
 use crate::render::Viewport;
 
 use input_lib::Controller;
 use macroquad::math::Vec2;
-use macroquad::texture::Texture2D;
 
 #[derive(Clone, Debug)]
 pub struct Cursor {
     pos: Point,
     render_pos: Vec2,
-    pub texture: Texture2D,
 }
 
 impl Cursor {
-    pub fn new(pos: Point, texture: Texture2D) -> Self {
+    pub const TEXTURE: &str = "cursor.png";
+
+    pub fn new(pos: Point) -> Self {
         Self {
             pos,
             render_pos: pos.into(),
-            texture,
         }
     }
 
@@ -39,17 +38,30 @@ impl Cursor {
         self.render_pos = self.pos.into();
     }
 
-    fn shift(&mut self, delta: impl Into<Point>, bounds: &Bounds2D) {
-        self.pos += delta.into();
+    /// Moves to a tapped tile, true if the cursor already was there
+    pub fn tap(&mut self, tile: Option<Point>) -> bool {
+        match tile {
+            Some(tile) if tile == self.pos => true,
+            Some(tile) => {
+                self.pos = tile;
+                false
+            }
+            None => false,
+        }
+    }
+
+    fn shift(&mut self, delta: (i32, i32), map: &Map) {
+        self.pos = map.topology.step(self.pos, delta);
+        let bounds = map.get_bounds();
 
         self.pos.x = self.pos.x.clamp(bounds.0.start, bounds.0.end - 1);
         self.pos.y = self.pos.y.clamp(bounds.1.start, bounds.1.end - 1);
     }
 
-    pub fn update(&mut self, controller: &Controller, bounds: &Bounds2D) {
+    pub fn update(&mut self, controller: &Controller, map: &Map) {
         let input = controller.timed_hold();
         let delta = (input.dpad_x, -input.dpad_y);
-        self.shift(delta, bounds);
+        self.shift(delta, map);
         self.update_render_pos();
     }
 
@@ -0,0 +1,73 @@
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct DialogueScript {
+    pub pages: Vec<DialoguePage>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct DialoguePage {
+    pub speaker: String,
+    /// Texture path of the speaker's portrait
+    pub portrait: Option<String>,
+    #[serde(default)]
+    pub expression: Expression,
+    pub lines: Vec<String>,
+    /// Shown once the last line has been read
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+    /// Page to continue with, defaults to the following page
+    pub next: Option<usize>,
+    /// Ends the dialogue after this page instead of continuing
+    #[serde(default)]
+    pub end: bool,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct DialogueChoice {
+    pub text: String,
+    /// Page to jump to, ends the dialogue if absent
+    pub goto: Option<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Expression {
+    #[default]
+    Neutral,
+    Happy,
+    Angry,
+    Sad,
+    Surprised,
+}
+
+impl DialogueScript {
+    /// Fails on malformed json and on pages or choices pointing past the last page
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let script: Self = serde_json::from_str(json)?;
+        let jumps = script.pages.iter().flat_map(|page| {
+            page.next
+                .into_iter()
+                .chain(page.choices.iter().filter_map(|choice| choice.goto))
+        });
+        for jump in jumps {
+            if jump >= script.pages.len() {
+                return Err(serde::de::Error::custom(format!(
+                    "Page {jump} does not exist, the script has {} pages",
+                    script.pages.len()
+                )));
+            }
+        }
+        Ok(script)
+    }
+
+    /// Page following `page` when no choice is involved, the last page ends the dialogue too
+    pub fn next_page(&self, page: usize) -> Option<usize> {
+        let current = &self.pages[page];
+        if current.end {
+            return None;
+        }
+        let next = current.next.unwrap_or(page + 1);
+        (next < self.pages.len()).then_some(next)
+    }
+}
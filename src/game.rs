@@ -1,15 +1,37 @@
+use crate::assets::DialogueStore;
 use crate::assets::TextureStore;
+use crate::bot::{BotLink, Bots};
+use crate::class::PROMOTION_LEVEL;
+use crate::class::UnitClass;
+use crate::command::Command;
+use crate::event::{Condition, Reinforcement, Trigger, TriggerAction};
 use crate::map::Map;
+use crate::map::Terrain;
+use crate::math::{Point, TileRect};
+use crate::net::NetSession;
 use crate::render::RenderContext;
 use crate::render::RenderCtxWithViewport;
 use crate::render::Viewport;
+use crate::replay::Recorder;
+use crate::settings::Settings;
+use crate::snapshot::WorldSnapshot;
+use crate::state::DialogueState;
 use crate::state::StateMachine;
+use crate::status::StatusEffect;
+use crate::status::StatusKind;
+use crate::tty::TextScreen;
 use crate::unit::ErasedUnit;
-use crate::unit::MovementClass;
+use crate::unit::Item;
+use crate::unit::Skill;
+use crate::unit::UnitId;
+use crate::unit::Weapon;
+use crate::unit::WeaponId;
+use crate::unit::WeaponKind;
 use crate::world::Faction;
+use crate::world::Objective;
 use crate::world::WorldState;
 
-use input_lib::Controller;
+use input_lib::{ButtonState, Controller, Frame, Gesture, Gestures, Physical};
 use macroquad::prelude::*;
 
 pub struct GameContext {
@@ -17,27 +39,66 @@ pub struct GameContext {
     pub render_ctx: RenderContext,
     pub viewport: Viewport,
     pub controller: Controller,
+    pub gestures: Gestures,
+    /// Tile tapped this frame
+    pub tap: Option<Point>,
     pub texture_store: TextureStore,
+    pub dialogue_store: DialogueStore,
+    pub bots: Bots,
+    /// Factions played on this device, more than one makes it a hot-seat battle
+    pub humans: Vec<Faction>,
+    /// Connection to the other player of an online battle
+    pub net: Option<NetSession>,
+    /// Seconds the current frame covers, states read this instead of the clock
+    pub frame_time: f32,
+    pub settings: Settings,
+    /// Changed settings are saved here, battles without one keep them in memory
+    pub settings_path: Option<String>,
+    /// What was last saved, to tell when the settings changed
+    saved_settings: Settings,
 }
 
 pub struct GameCtxView<'a> {
     pub world: &'a WorldState,
     pub controller: &'a Controller,
-    pub texture_store: &'a TextureStore,
+    pub tap: Option<Point>,
+    /// Everything the fingers did this frame, in screen pixels
+    pub gestures: &'a [Gesture],
     pub viewport: &'a mut Viewport,
+    pub bots: &'a mut Bots,
+    pub humans: &'a [Faction],
+    pub net: Option<&'a mut NetSession>,
+    pub frame_time: f32,
+    pub settings: &'a mut Settings,
 }
 
 impl GameContext {
-    pub fn new(map: Map, texture_store: TextureStore) -> Self {
+    pub fn new(
+        map: Map,
+        seed: u64,
+        texture_store: TextureStore,
+        dialogue_store: DialogueStore,
+    ) -> Self {
         Self {
             viewport: Viewport::new(
                 map.width.try_into().unwrap(),
                 map.height.try_into().unwrap(),
+                map.topology,
             ),
-            world: WorldState::new(map),
+            world: WorldState::new(map, seed),
             controller: Controller::new(),
+            gestures: Gestures::new(),
+            tap: None,
             render_ctx: RenderContext::new(),
             texture_store,
+            dialogue_store,
+            bots: Bots::default(),
+            humans: vec![Faction::Player],
+            net: None,
+            frame_time: 0.0,
+            settings: Settings::default(),
+            settings_path: None,
+            saved_settings: Settings::default(),
         }
     }
 
@@ -45,68 +106,420 @@ impl GameContext {
         GameCtxView {
             world: &self.world,
             controller: &self.controller,
-            texture_store: &self.texture_store,
+            gestures: self.gestures.gestures(),
+            tap: self.tap,
             viewport: &mut self.viewport,
+            bots: &mut self.bots,
+            humans: &self.humans,
+            net: self.net.as_mut(),
+            frame_time: self.frame_time,
+            settings: &mut self.settings,
+        }
+    }
+
+    /// Pans and zooms the map and finds the tapped tile
+    fn apply_gestures(&mut self) {
+        self.tap = None;
+        // The layout editor reads the gestures itself
+        if self.settings.editing_layout {
+            return;
+        }
+        for gesture in self.gestures.gestures() {
+            match *gesture {
+                Gesture::Tap(pos) => {
+                    self.tap = self
+                        .render_ctx
+                        .screen_to_layout(pos, &self.viewport)
+                        .map(|layout| self.world.map.topology.tile_at(layout))
+                        .filter(|tile| self.world.map.in_bounds(*tile));
+                }
+                Gesture::Drag(delta) => {
+                    // The map follows the finger, so the view moves the other way
+                    let tile_pixels = self.render_ctx.tile_pixels(&self.viewport);
+                    self.viewport.pan(-delta / tile_pixels);
+                }
+                Gesture::Pinch { center, scale } => {
+                    if let Some(focus) = self.render_ctx.screen_to_layout(center, &self.viewport) {
+                        self.viewport.zoom_at(scale, focus);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Hands changed settings to the controller and saves them
+    fn apply_settings(&mut self) {
+        if self.controller.bindings() != &self.settings.bindings {
+            self.controller.set_bindings(self.settings.bindings.clone());
+        }
+        if self.controller.layout() != &self.settings.layout {
+            self.controller.set_layout(self.settings.layout);
+        }
+        self.controller.set_editing(self.settings.editing_layout);
+
+        if self.settings.editing_layout || self.settings == self.saved_settings {
+            return;
+        }
+        self.saved_settings.clone_from(&self.settings);
+        if let Some(path) = &self.settings_path
+            && let Err(err) = self.settings.save(path)
+        {
+            warn!("Could not save the settings to {}: {}", path, err);
         }
     }
 
     pub fn get_render_view(&'_ self) -> RenderCtxWithViewport<'_> {
-        RenderCtxWithViewport::new(&self.viewport)
+        RenderCtxWithViewport::new(&self.viewport, &self.texture_store)
     }
 }
 
+pub const INTRO_DIALOGUE: &str = "dialogue/intro.json";
+const REINFORCEMENT_DIALOGUE: &str = "dialogue/reinforcements.json";
+const TALK_DIALOGUE: &str = "dialogue/talk.json";
+pub const CHAPTER_DIALOGUES: [&str; 3] = [INTRO_DIALOGUE, REINFORCEMENT_DIALOGUE, TALK_DIALOGUE];
+
+const PEER_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(1);
+
 pub struct Engine {
     state_machine: StateMachine,
     game_context: GameContext,
+    recorder: Option<Recorder>,
 }
 
-type UnitBuilder = [(u32, MovementClass, Faction, i32, (i32, i32), &'static str); 5];
+type WeaponBuilder = (WeaponKind, i32, i32, Option<StatusEffect>);
+type UnitBuilder = [(UnitClass, Faction, (i32, i32), &'static str, WeaponBuilder); 5];
 
 #[rustfmt::skip]
 const UNITS: UnitBuilder = [
-    (5, MovementClass::Infantry, Faction::Player, 10, (4, 3), "unit1.png"),
-    (7, MovementClass::Mounted, Faction::Player, 20, (5, 6), "unit1.png"),
-    (7, MovementClass::Flying, Faction::Player, 20, (4, 6), "unit1.png"),
-    (5, MovementClass::Infantry, Faction::Enemy, 15, (4, 5), "mage1.png"),
-    (6, MovementClass::Mounted, Faction::Enemy, 15, (7, 4), "mage1.png"),
+    (UnitClass::Fighter, Faction::Player, (4, 3), "unit1.png", (WeaponKind::Axe, 5, 1, Some(StatusEffect::new(StatusKind::Stun, 1)))),
+    (UnitClass::Cavalier, Faction::Player, (5, 6), "unit1.png", (WeaponKind::Lance, 4, 1, None)),
+    (UnitClass::PegasusKnight, Faction::Player, (4, 6), "unit1.png", (WeaponKind::Lance, 4, 1, None)),
+    (UnitClass::Mage, Faction::Enemy, (4, 5), "mage1.png", (WeaponKind::Tome, 4, 2, Some(StatusEffect::new(StatusKind::Sleep, 1)))),
+    (UnitClass::Archer, Faction::Enemy, (7, 4), "mage1.png", (WeaponKind::Bow, 5, 2, Some(StatusEffect::new(StatusKind::Poison, 3)))),
 ];
 impl Engine {
-    pub fn new(map: Map, texture_store: TextureStore) -> Self {
+    pub fn new(
+        mut map: Map,
+        seed: u64,
+        texture_store: TextureStore,
+        dialogue_store: DialogueStore,
+    ) -> Self {
         let mut units = Vec::new();
-        for (movement, movement_class, faction, health, pos, texture) in &UNITS {
-            units.push(ErasedUnit {
-                movement: *movement,
-                movement_class: *movement_class,
-                turn_complete: false,
-                faction: *faction,
-                curr_health: *health,
-                max_health: *health,
-                pos: (*pos).into(),
-                texture_path: (*texture).to_string(),
-                weapon: None,
-            });
-        }
-
-        let mut game_ctx = GameContext::new(map, texture_store);
+        let mut weapon_id = WeaponId::new(0);
+        for (class, faction, pos, texture, (kind, might, range, effect)) in &UNITS {
+            let mut unit = ErasedUnit::with_class(*class, *faction, *pos, texture);
+            let weapon = Weapon::new(weapon_id, *kind, *might, *range);
+            unit.weapon = Some(effect.map_or(weapon, |effect| weapon.with_effect(effect)));
+            weapon_id.next();
+            units.push(unit);
+        }
+        units[0].level = PROMOTION_LEVEL;
+        units[0].items.push(Item::MasterSeal);
+        units[1].items.push(Item::AttackTonic);
+        units[2].items.push(Item::DefenceTonic);
+        units[2].skills.push(Skill::Pass);
+        map.zone_of_control = true;
+        map.triggers = Self::chapter_triggers(&mut weapon_id);
+        map.reinforcements = Self::chapter_reinforcements(&mut weapon_id);
+
+        let mut game_ctx = GameContext::new(map, seed, texture_store, dialogue_store);
         for unit in units {
-            game_ctx.world.spawn_units(&unit, &game_ctx.texture_store);
+            game_ctx.world.spawn_units(&unit);
         }
-        game_ctx.world.setup_turn();
+        game_ctx.world.setup_turn(Faction::Player);
 
         // WARN Ensure all operations on game_ctx are done before constructing the statemachine
-        let state_machine = StateMachine::new(&game_ctx);
+        let mut state_machine = StateMachine::new(&mut game_ctx);
+        if let Some(script) = game_ctx.dialogue_store.get(INTRO_DIALOGUE) {
+            let dialogue = DialogueState::boxed_new(script.clone());
+            state_machine.push_state(dialogue, &mut game_ctx);
+        }
 
         Self {
             state_machine,
             game_context: game_ctx,
+            recorder: None,
         }
     }
 
+    /// A battle with only these units and none of the chapter's events or assets
+    pub fn with_units(map: Map, seed: u64, units: &[ErasedUnit]) -> Self {
+        let mut game_ctx = GameContext::new(map, seed, TextureStore::new(), DialogueStore::new());
+        for unit in units {
+            game_ctx.world.spawn_units(unit);
+        }
+        game_ctx.world.setup_turn(Faction::Player);
+
+        Self {
+            state_machine: StateMachine::new(&mut game_ctx),
+            game_context: game_ctx,
+            recorder: None,
+        }
+    }
+
+    fn chapter_reinforcements(weapon_id: &mut WeaponId) -> Vec<Reinforcement> {
+        // The first one arrives on an occupied tile and gets moved to the closest free one
+        [((7, 4), 2, false), ((9, 2), 2, true), ((14, 6), 4, false)]
+            .into_iter()
+            .map(|(pos, turn, ambush)| {
+                let mut unit =
+                    ErasedUnit::with_class(UnitClass::Cavalier, Faction::Enemy, pos, "mage1.png");
+                unit.weapon = Some(Weapon::new(*weapon_id, WeaponKind::Lance, 4, 1));
+                weapon_id.next();
+                Reinforcement { turn, unit, ambush }
+            })
+            .collect()
+    }
+
+    fn chapter_triggers(weapon_id: &mut WeaponId) -> Vec<Trigger> {
+        let mut reinforcements = Vec::new();
+        for pos in [(12, 8), (13, 9)] {
+            let mut unit =
+                ErasedUnit::with_class(UnitClass::Fighter, Faction::Enemy, pos, "mage1.png");
+            unit.weapon = Some(Weapon::new(*weapon_id, WeaponKind::Axe, 5, 1));
+            weapon_id.next();
+            reinforcements.push(unit);
+        }
+
+        vec![
+            Trigger::new(
+                Condition::TurnStart {
+                    turn: 3,
+                    faction: Faction::Player,
+                },
+                vec![
+                    TriggerAction::Dialogue(REINFORCEMENT_DIALOGUE.to_string()),
+                    TriggerAction::SpawnUnits(reinforcements),
+                    TriggerAction::ChangeObjective(Objective::Survive { turns: 8 }),
+                ],
+            ),
+            Trigger::new(
+                Condition::Talk(UnitId::new(0), UnitId::new(2)),
+                vec![
+                    TriggerAction::Dialogue(TALK_DIALOGUE.to_string()),
+                    TriggerAction::GiveItem(UnitId::new(2), Item::DefenceTonic),
+                ],
+            ),
+            Trigger::new(
+                Condition::UnitDefeated(UnitId::new(3)),
+                vec![TriggerAction::ChangeObjective(Objective::Defeat(
+                    UnitId::new(4),
+                ))],
+            ),
+            Trigger::new(
+                Condition::HealthBelow {
+                    unit: UnitId::new(0),
+                    threshold: 8,
+                },
+                vec![TriggerAction::GiveItem(UnitId::new(0), Item::DefenceTonic)],
+            ),
+            Trigger::new(
+                Condition::UnitEntersRegion {
+                    faction: Faction::Player,
+                    region: TileRect::with_size(10, 0, 4, 4),
+                },
+                vec![
+                    TriggerAction::ChangeTerrain(Point::new(12, 5), Terrain::Ground),
+                    TriggerAction::ChangeObjective(Objective::Seize(Point::new(12, 5))),
+                ],
+            ),
+        ]
+    }
+
     pub fn update(&mut self) {
+        self.game_context.render_ctx.resize_if_required();
+        self.game_context.controller.update();
+        self.advance();
+    }
+
+    /// Advances one frame with input that does not come from the screen, needs no window
+    pub fn step(&mut self, input: ButtonState, frame_time: f32) {
+        self.step_frame(&Frame {
+            state: input,
+            frame_time,
+            ..Frame::default()
+        });
+    }
+
+    /// Advances one frame of a recording, needs no window
+    pub fn step_frame(&mut self, frame: &Frame) {
+        self.game_context.controller.feed_frame(frame);
+        self.advance();
+    }
+
+    /// Runs the frame the controller was just advanced with
+    fn advance(&mut self) {
+        if let Some(recorder) = &mut self.recorder
+            && let Err(err) = recorder.record(&self.game_context.controller.frame())
+        {
+            warn!("Stopped recording: {}", err);
+            self.recorder = None;
+        }
+        self.game_context
+            .gestures
+            .update(&self.game_context.controller);
+        self.game_context.apply_gestures();
+        self.game_context.frame_time = self.game_context.controller.frame_time();
         self.state_machine.update(&mut self.game_context);
+        self.game_context.apply_settings();
+    }
+
+    /// Advances one frame with a physical input going down, pressing whatever it is bound to
+    pub fn step_physical(&mut self, input: Physical, frame_time: f32) {
+        let mut state = ButtonState::default();
+        if let Some(target) = self.game_context.controller.bindings().target_of(input) {
+            state.set(target);
+        }
+        self.step_frame(&Frame {
+            state,
+            frame_time,
+            pressed: Some(input),
+            ..Frame::default()
+        });
+    }
+
+    /// Advances one frame with a tap on `tile` and no buttons pressed
+    pub fn step_tap(&mut self, tile: Point, frame_time: f32) {
+        self.game_context
+            .controller
+            .feed(ButtonState::default(), frame_time);
+        self.game_context.tap = Some(tile);
+        self.game_context.frame_time = frame_time;
+        self.state_machine.update(&mut self.game_context);
+        self.game_context.apply_settings();
+    }
+
+    /// Loads the settings from `path` and saves them there whenever they change
+    pub fn load_settings(&mut self, path: &str) {
+        self.game_context.settings = Settings::load(path);
+        self.game_context.saved_settings = self.game_context.settings.clone();
+        self.game_context.settings_path = Some(path.to_string());
+        self.game_context.apply_settings();
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.game_context.settings
+    }
+
+    /// Plays with these settings without saving them anywhere, for replays
+    pub fn use_settings(&mut self, settings: Settings) {
+        self.game_context.saved_settings = settings.clone();
+        self.game_context.settings = settings;
+        self.game_context.settings_path = None;
+        self.game_context.apply_settings();
+    }
+
+    /// Writes every following frame of input to the recorder
+    pub fn record(&mut self, recorder: Recorder) {
+        self.recorder = Some(recorder);
+    }
+
+    /// Feeds the frames to the controller in place of the screen and keyboard, pointers are
+    /// scaled from the `screen` they were recorded on
+    pub fn play(&mut self, frames: Vec<Frame>, screen: Vec2) {
+        self.game_context.controller.play(frames, screen);
+    }
+
+    /// Runs idle frames until the game waits for input, false if it never does
+    pub fn settle(&mut self, frame_time: f32, max_frames: u32) -> bool {
+        for _ in 0..max_frames {
+            self.step(ButtonState::default(), frame_time);
+            if self.waits_for_input() {
+                return true;
+            }
+            // No need to spin while the online opponent thinks
+            if self.waits_for_peer() {
+                std::thread::sleep(PEER_POLL_INTERVAL);
+            }
+        }
+        false
+    }
+
+    /// Applied after the next frame's state update, like the states' own commands
+    pub fn queue_command(&mut self, command: Command) {
+        self.state_machine.queue_command(command);
+    }
+
+    /// Hands `faction` to an external process, call it before the first update
+    pub fn attach_bot(&mut self, faction: Faction, link: BotLink) {
+        self.game_context.bots.attach(faction, link);
+        if faction == self.game_context.world.phase {
+            self.state_machine
+                .restart_phase(faction, &mut self.game_context);
+        }
+    }
+
+    /// Lets humans play these factions on this device, call it before the first update
+    pub fn seat_humans(&mut self, factions: &[Faction]) {
+        self.game_context.humans = factions.to_vec();
+        self.state_machine
+            .restart_phase(self.game_context.world.phase, &mut self.game_context);
+    }
+
+    /// Continues a suspended battle on the map it was suspended on, call it before the first
+    /// update
+    pub fn resume(&mut self, snapshot: &WorldSnapshot) -> Result<(), String> {
+        self.game_context.world.restore(snapshot)?;
+        // Drops the intro dialogue, it was seen before suspending
+        self.state_machine = StateMachine::new(&mut self.game_context);
+        self.state_machine
+            .restart_phase(self.game_context.world.phase, &mut self.game_context);
+        Ok(())
+    }
+    /// Resumes from a file written by the map menu's Suspend
+    ///
+    /// # Errors
+    /// If the file can not be read or does not fit this map
+    pub fn resume_file(&mut self, path: &str) -> Result<(), String> {
+        let json = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let snapshot = WorldSnapshot::from_json(&json).map_err(|err| err.to_string())?;
+        self.resume(&snapshot)
+    }
+    /// Plays the session's local faction here and the other one through the connection,
+    /// call it before the first update
+    pub fn start_online(&mut self, session: NetSession) {
+        self.game_context.humans = vec![session.local()];
+        self.game_context.net = Some(session);
+        self.state_machine
+            .restart_phase(self.game_context.world.phase, &mut self.game_context);
+    }
+    pub fn net(&self) -> Option<&NetSession> {
+        self.game_context.net.as_ref()
+    }
+    pub fn bots_mut(&mut self) -> &mut Bots {
+        &mut self.game_context.bots
     }
 
     pub fn render(&self) {
         self.state_machine.render(&self.game_context);
     }
+
+    pub fn render_text(&self) -> TextScreen {
+        self.state_machine.render_text(&self.game_context.world)
+    }
+
+    pub fn world(&self) -> &WorldState {
+        &self.game_context.world
+    }
+
+    pub fn current_state(&self) -> &'static str {
+        self.state_machine.current_state()
+    }
+
+    /// A bot is expected to send its next action
+    pub fn waits_for_bot(&self) -> bool {
+        self.game_context.bots.is_waiting()
+    }
+
+    /// The online opponent is expected to send their next commands
+    pub fn waits_for_peer(&self) -> bool {
+        self.game_context
+            .net
+            .as_ref()
+            .is_some_and(NetSession::is_waiting)
+    }
+    /// The top state wants input and is not waiting on the camera
+    pub fn waits_for_input(&self) -> bool {
+        self.state_machine.takes_input() && !self.game_context.viewport.is_centering()
+    }
 }
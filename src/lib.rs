@@ -1,19 +1,37 @@
 #![warn(clippy::pedantic, clippy::all)]
 
 mod assets;
+pub mod bot;
 mod cursor;
+mod dialogue;
 mod game;
-mod map;
-mod math;
-mod pathfinding;
-mod prelude;
+mod lines;
+pub mod net;
 mod render;
+pub mod replay;
+pub mod scenario;
+pub mod settings;
 mod state;
+pub mod tty;
 mod ui;
-mod unit;
-mod world;
 
-use crate::{assets::TextureStore, game::Engine, map::Map};
+use crate::{
+    assets::{DialogueStore, TextureStore},
+    game::{CHAPTER_DIALOGUES, Engine},
+    grid::GridTopology,
+    map::Map,
+    net::NetSession,
+    replay::{Recorder, ReplayHeader},
+    rng::Rng,
+    settings::SETTINGS_FILE,
+    world::Faction,
+};
+
+use input_lib::Frame;
+use luminara_core::{
+    action, ai, class, combat, command, event, grid, map, math, pathfinding, rng, snapshot, status,
+    unit, world,
+};
 
 // use macroquad::experimental::animation;
 use macroquad::experimental::collections::storage;
@@ -41,7 +59,7 @@ pub async fn main() {
     set_default_filter_mode(FilterMode::Nearest);
     std::panic::set_hook(Box::new(|info| error!("{:?}", info)));
 
-    let texture_store;
+    let (texture_store, dialogue_store);
     {
         let builder = start_coroutine(async move {
             let mut texture_store = TextureStore::new();
@@ -52,7 +70,14 @@ pub async fn main() {
             texture_store.schedule_load("cursor.png");
 
             texture_store.load_all().await;
-            texture_store
+
+            let mut dialogue_store = DialogueStore::new();
+            for path in CHAPTER_DIALOGUES {
+                dialogue_store.schedule_load(path);
+            }
+            dialogue_store.load_all().await;
+
+            (texture_store, dialogue_store)
         });
 
         let text = "Loading";
@@ -64,7 +89,7 @@ pub async fn main() {
         );
         loop {
             if builder.is_done() {
-                texture_store = builder.retrieve().unwrap();
+                (texture_store, dialogue_store) = builder.retrieve().unwrap();
                 break;
             }
             draw_text(text, x, y, font_size.into(), WHITE);
@@ -72,14 +97,57 @@ pub async fn main() {
         }
     }
 
-    let grass = texture_store.get("grass1.png");
-    let forest = texture_store.get("forest1.png");
-    let map = Map::random(30, 20, &grass, &forest);
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let mut seed = (miniquad::date::now() * 1000.0) as u64;
+    let mut topology = if std::env::args().any(|arg| arg == "--hex") {
+        GridTopology::Hex
+    } else {
+        GridTopology::Square
+    };
+    let replay = load_replay();
+    if let Some((header, _)) = &replay {
+        (seed, topology) = (header.seed, header.topology);
+    }
+    let online = if replay.is_some() {
+        Ok(None)
+    } else {
+        connect_online(seed, topology)
+    };
+    let session = match online {
+        Ok(Some((session, host_seed, host_topology))) => {
+            (seed, topology) = (host_seed, host_topology);
+            Some(session)
+        }
+        Ok(None) => None,
+        Err(err) => {
+            error!("Could not start the online battle: {}", err);
+            None
+        }
+    };
+    let mut rng = Rng::new(seed);
+    let map = Map::random(30, 20, topology, &mut rng);
 
     storage::store("Global Storage");
     debug!("{:?}", *storage::get::<&str>());
 
-    let mut game = Engine::new(map, texture_store);
+    let mut game = Engine::new(map, rng.next_u64(), texture_store, dialogue_store);
+    if let Some(session) = session {
+        game.start_online(session);
+    } else if std::env::args().any(|arg| arg == "--versus") {
+        game.seat_humans(&[Faction::Player, Faction::Enemy]);
+    }
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--resume").nth(1)
+        && let Err(err) = game.resume_file(&path)
+    {
+        error!("Could not resume {}: {}", path, err);
+    }
+    game.load_settings(SETTINGS_FILE);
+    if let Some((header, frames)) = replay {
+        game.use_settings(header.settings);
+        game.play(frames, Vec2::from_array(header.screen));
+    } else {
+        start_recording(&mut game, seed, topology);
+    }
 
     loop {
         clear_background(BLACK);
@@ -90,3 +158,54 @@ pub async fn main() {
         next_frame().await;
     }
 }
+
+/// Reads the recording given with `--replay FILE`, its header replaces the seed and topology
+fn load_replay() -> Option<(ReplayHeader, Vec<Frame>)> {
+    let path = std::env::args()
+        .skip_while(|arg| arg != "--replay")
+        .nth(1)?;
+    replay::load(&path)
+        .inspect_err(|err| error!("Could not load the recording {}: {}", path, err))
+        .ok()
+}
+
+/// Records the input to the file given with `--record FILE`, if any
+fn start_recording(game: &mut Engine, seed: u64, topology: GridTopology) {
+    let Some(path) = std::env::args().skip_while(|arg| arg != "--record").nth(1) else {
+        return;
+    };
+    let header = ReplayHeader {
+        seed,
+        topology,
+        settings: game.settings().clone(),
+        screen: [screen_width(), screen_height()],
+    };
+    match Recorder::create(&path, &header) {
+        Ok(recorder) => game.record(recorder),
+        Err(err) => error!("Could not record to {}: {}", path, err),
+    }
+}
+
+/// Hosts with `--host ADDR` or joins with `--join ADDR`, returns the seed and topology the
+/// battle is built from
+fn connect_online(
+    seed: u64,
+    topology: GridTopology,
+) -> std::io::Result<Option<(NetSession, u64, GridTopology)>> {
+    let args: Vec<String> = std::env::args().collect();
+    let value = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|idx| args.get(idx + 1))
+    };
+
+    if let Some(addr) = value("--host") {
+        let listener = std::net::TcpListener::bind(addr)?;
+        info!("Waiting for the other player on {}", listener.local_addr()?);
+        let session = NetSession::host(listener, seed, topology)?;
+        return Ok(Some((session, seed, topology)));
+    }
+    value("--join")
+        .map(|addr| NetSession::join(addr))
+        .transpose()
+}
@@ -0,0 +1,21 @@
+//! Line based connections shared by the bot and online versus
+
+use std::io::BufRead;
+use std::sync::mpsc::{self, Receiver};
+
+/// Reads `input` on its own thread so polling never blocks. The receiver disconnects once the
+/// input ends or fails.
+pub fn read_lines(input: impl BufRead + Send + 'static) -> Receiver<String> {
+    let (sender, incoming) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in input.lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    incoming
+}
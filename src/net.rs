@@ -0,0 +1,507 @@
+//! Online versus over TCP. Both sides run the whole battle from the host's seed and only
+//! exchange the commands of their own phases, one JSON message per line, so the worlds
+//! advance in lockstep.
+//!
+//! At the start of every phase both sides send [`WorldState::checksum`], a mismatch means
+//! the worlds drifted apart and is reported as a desync. Commands stay buffered until the
+//! other side confirmed a later checksum, so after a dropped connection the host listens
+//! again, the guest redials, and both resend what the other missed since the last
+//! checkpoint they agreed on.
+//!
+//! `cargo run --bin luminara_tty -- --host 0.0.0.0:7878` and `-- --join HOST:7878`
+
+use crate::command::Command;
+use crate::grid::GridTopology;
+use crate::lines::read_lines;
+use crate::math::Point;
+use crate::unit::UnitId;
+use crate::world::{Faction, WorldState};
+
+use macroquad::logging::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use std::collections::{HashSet, VecDeque};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::Duration;
+
+const REDIAL_DELAY: Duration = Duration::from_secs(1);
+
+/// The world's checksum at the start of a phase
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub turn: u32,
+    pub phase: Faction,
+    pub checksum: u64,
+}
+
+impl Checkpoint {
+    #[must_use]
+    pub fn new(world: &WorldState) -> Self {
+        Self {
+            turn: world.turn,
+            phase: world.phase,
+            checksum: world.checksum(),
+        }
+    }
+
+    fn same_phase(self, other: Self) -> bool {
+        self.turn == other.turn && self.phase == other.phase
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum NetMsg {
+    /// Sent by the host once the guest connected, the guest builds the same battle from it
+    Start {
+        seed: u64,
+        topology: GridTopology,
+    },
+    /// Everything one update of the sender's phase queued, applied together on both sides so
+    /// triggers see the same worlds
+    Commands {
+        seq: u64,
+        commands: Vec<Command>,
+    },
+    Checkpoint(Checkpoint),
+    /// First message on a new connection, `received` counts the batches that got through
+    Resume {
+        agreed: Option<Checkpoint>,
+        received: u64,
+    },
+}
+
+impl NetMsg {
+    fn to_line(&self) -> String {
+        serde_json::to_string(self).expect("Net messages always serialize")
+    }
+}
+
+/// Lines are read on their own thread so polling never blocks
+struct Connection {
+    incoming: Receiver<String>,
+    stream: TcpStream,
+}
+
+impl Connection {
+    fn new(reader: BufReader<TcpStream>, stream: TcpStream) -> Self {
+        Self {
+            incoming: read_lines(reader),
+            stream,
+        }
+    }
+
+    fn from_stream(stream: TcpStream) -> io::Result<Self> {
+        Ok(Self::new(BufReader::new(stream.try_clone()?), stream))
+    }
+
+    fn send(&mut self, line: &str) {
+        // A broken connection shows up as a closed input, so write errors are ignored
+        let _ = writeln!(self.stream, "{line}");
+        let _ = self.stream.flush();
+    }
+
+    fn close(&self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+enum Role {
+    Host(TcpListener),
+    Guest(String),
+}
+
+impl Role {
+    /// Waits for the other side in the background, the stream arrives on the receiver
+    fn reconnect(&self) -> io::Result<Receiver<TcpStream>> {
+        let (sender, receiver) = mpsc::channel();
+        match self {
+            Self::Host(listener) => {
+                let listener = listener.try_clone()?;
+                std::thread::spawn(move || {
+                    if let Ok((stream, _)) = listener.accept() {
+                        let _ = sender.send(stream);
+                    }
+                });
+            }
+            Self::Guest(addr) => {
+                let addr = addr.clone();
+                std::thread::spawn(move || {
+                    loop {
+                        if let Ok(stream) = TcpStream::connect(&addr) {
+                            let _ = sender.send(stream);
+                            break;
+                        }
+                        std::thread::sleep(REDIAL_DELAY);
+                    }
+                });
+            }
+        }
+        Ok(receiver)
+    }
+}
+
+/// One side of an online battle, the host plays [`Faction::Player`] and the guest the enemy
+pub struct NetSession {
+    local: Faction,
+    role: Role,
+    connection: Option<Connection>,
+    redial: Option<Receiver<TcpStream>>,
+    next_seq: u64,
+    /// Own batches the other side has not confirmed with a checkpoint yet
+    unconfirmed: VecDeque<(u64, String)>,
+    /// Number of the other side's batches that arrived
+    received: u64,
+    /// `received` when a gap in the batches was last asked to be filled
+    resend_requested: Option<u64>,
+    inbox: VecDeque<Vec<Command>>,
+    /// Own checkpoints waiting for the other side's, with the next batch number at the time
+    own_checkpoints: Vec<(Checkpoint, u64)>,
+    peer_checkpoints: Vec<Checkpoint>,
+    agreed: Option<Checkpoint>,
+    desync: Option<Checkpoint>,
+    waiting: bool,
+}
+
+impl NetSession {
+    fn new(local: Faction, role: Role, connection: Connection) -> Self {
+        Self {
+            local,
+            role,
+            connection: Some(connection),
+            redial: None,
+            next_seq: 0,
+            unconfirmed: VecDeque::new(),
+            received: 0,
+            resend_requested: None,
+            inbox: VecDeque::new(),
+            own_checkpoints: Vec::new(),
+            peer_checkpoints: Vec::new(),
+            agreed: None,
+            desync: None,
+            waiting: false,
+        }
+    }
+
+    /// Waits for the guest and sends it the battle's setup
+    ///
+    /// # Errors
+    /// If the guest fails to connect
+    pub fn host(listener: TcpListener, seed: u64, topology: GridTopology) -> io::Result<Self> {
+        let (stream, _) = listener.accept()?;
+        let mut connection = Connection::from_stream(stream)?;
+        connection.send(&NetMsg::Start { seed, topology }.to_line());
+        Ok(Self::new(Faction::Player, Role::Host(listener), connection))
+    }
+
+    /// Connects to a host, returns the seed and topology the battle has to be built from
+    ///
+    /// # Errors
+    /// If the host cannot be reached or does not start a battle
+    pub fn join(addr: &str) -> io::Result<(Self, u64, GridTopology)> {
+        let stream = TcpStream::connect(addr)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let Ok(NetMsg::Start { seed, topology }) = serde_json::from_str(&line) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The host did not start a battle",
+            ));
+        };
+
+        let connection = Connection::new(reader, stream);
+        let session = Self::new(Faction::Enemy, Role::Guest(addr.to_string()), connection);
+        Ok((session, seed, topology))
+    }
+
+    #[must_use]
+    pub fn local(&self) -> Faction {
+        self.local
+    }
+
+    #[must_use]
+    pub fn remote(&self) -> Faction {
+        self.local.opponent()
+    }
+
+    /// The opponent's phase is waiting for their next commands
+    #[must_use]
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// Last phase both sides had the same world at
+    #[must_use]
+    pub fn agreed(&self) -> Option<Checkpoint> {
+        self.agreed
+    }
+
+    /// This side's checkpoint of the first phase the worlds differed at
+    #[must_use]
+    pub fn desync(&self) -> Option<Checkpoint> {
+        self.desync
+    }
+
+    /// Cuts the connection as if the network dropped it, both sides reconnect on their own
+    pub fn drop_connection(&self) {
+        if let Some(connection) = &self.connection {
+            connection.close();
+        }
+    }
+
+    fn send(&mut self, line: &str) {
+        if let Some(connection) = &mut self.connection {
+            connection.send(line);
+        }
+    }
+
+    /// Reads what arrived and picks up a dropped connection, call it once per frame
+    pub fn update(&mut self) {
+        if let Some(redial) = &self.redial {
+            match redial.try_recv() {
+                Ok(stream) => self.resume(stream),
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.redial = None,
+            }
+        }
+
+        let mut lines = Vec::new();
+        if let Some(connection) = &self.connection {
+            loop {
+                match connection.incoming.try_recv() {
+                    Ok(line) => lines.push(line),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        warn!("Lost the connection to the {:?} side", self.remote());
+                        self.connection = None;
+                        self.redial = self.role.reconnect().ok();
+                        break;
+                    }
+                }
+            }
+        }
+        for line in lines {
+            self.receive(&line);
+        }
+    }
+
+    fn resume(&mut self, stream: TcpStream) {
+        self.redial = None;
+        let Ok(connection) = Connection::from_stream(stream) else {
+            self.redial = self.role.reconnect().ok();
+            return;
+        };
+        info!("Reconnected, resuming from {:?}", self.agreed);
+        self.connection = Some(connection);
+        let resume = NetMsg::Resume {
+            agreed: self.agreed,
+            received: self.received,
+        };
+        self.send(&resume.to_line());
+    }
+
+    fn receive(&mut self, line: &str) {
+        let msg = match serde_json::from_str(line) {
+            Ok(msg) => msg,
+            Err(err) => {
+                warn!("Ignoring malformed net message: {}", err);
+                return;
+            }
+        };
+        match msg {
+            NetMsg::Start { .. } => warn!("The battle has already started"),
+            NetMsg::Commands { seq, commands } => {
+                // Resent after a reconnect but already here
+                if seq < self.received {
+                    return;
+                }
+                if seq > self.received {
+                    self.request_resend();
+                    return;
+                }
+                self.received = seq + 1;
+                self.inbox.push_back(commands);
+            }
+            NetMsg::Checkpoint(checkpoint) => {
+                self.peer_checkpoints.push(checkpoint);
+                self.compare_checkpoints();
+            }
+            NetMsg::Resume { agreed, received } => {
+                if let (Some(theirs), Some(ours)) = (agreed, self.agreed)
+                    && theirs.same_phase(ours)
+                    && theirs != ours
+                {
+                    self.report_desync(ours, theirs);
+                }
+                let missed: Vec<String> = self
+                    .unconfirmed
+                    .iter()
+                    .filter(|(seq, _)| *seq >= received)
+                    .map(|(_, line)| line.clone())
+                    .collect();
+                let checkpoints: Vec<String> = self
+                    .own_checkpoints
+                    .iter()
+                    .map(|(checkpoint, _)| NetMsg::Checkpoint(*checkpoint).to_line())
+                    .collect();
+                for line in missed.iter().chain(&checkpoints) {
+                    self.send(line);
+                }
+            }
+        }
+    }
+
+    /// Batches went missing, the other side resends everything from the first one as it does
+    /// after a reconnect. Asked once per gap, the later batches of it are dropped meanwhile.
+    fn request_resend(&mut self) {
+        if self.resend_requested == Some(self.received) {
+            return;
+        }
+        warn!("Net messages went missing after batch {}", self.received);
+        self.resend_requested = Some(self.received);
+        let resume = NetMsg::Resume {
+            agreed: self.agreed,
+            received: self.received,
+        };
+        self.send(&resume.to_line());
+    }
+
+    /// Sends what one update of the local phase queued
+    pub fn send_commands(&mut self, commands: &[Command]) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let line = NetMsg::Commands {
+            seq,
+            commands: commands.to_vec(),
+        }
+        .to_line();
+        self.send(&line);
+        self.unconfirmed.push_back((seq, line));
+    }
+
+    /// The opponent's next batch of commands, in the order they were sent. Commands the
+    /// opponent could not have given in its phase are left out and reported like a desync.
+    pub fn next_commands(&mut self, world: &WorldState) -> Option<Vec<Command>> {
+        let commands = self.inbox.pop_front();
+        self.waiting = commands.is_none();
+        let mut claimed = HashSet::new();
+        let mut legal = Vec::new();
+        for command in commands? {
+            match check_command(world, self.remote(), &command, &mut claimed) {
+                Ok(()) => legal.push(command),
+                Err(reason) => {
+                    error!(
+                        "Rejected {:?} from the {:?} side: {}",
+                        command,
+                        self.remote(),
+                        reason
+                    );
+                    self.desync.get_or_insert(Checkpoint::new(world));
+                }
+            }
+        }
+        Some(legal)
+    }
+
+    /// Sends the world's checksum, call it once a phase has been set up
+    pub fn phase_started(&mut self, world: &WorldState) {
+        let checkpoint = Checkpoint::new(world);
+        self.own_checkpoints.push((checkpoint, self.next_seq));
+        self.send(&NetMsg::Checkpoint(checkpoint).to_line());
+        self.compare_checkpoints();
+    }
+
+    fn compare_checkpoints(&mut self) {
+        while let Some((own_idx, peer_idx)) =
+            self.own_checkpoints
+                .iter()
+                .enumerate()
+                .find_map(|(own_idx, (own, _))| {
+                    self.peer_checkpoints
+                        .iter()
+                        .position(|peer| peer.same_phase(*own))
+                        .map(|peer_idx| (own_idx, peer_idx))
+                })
+        {
+            let (ours, next_seq) = self.own_checkpoints.remove(own_idx);
+            let theirs = self.peer_checkpoints.remove(peer_idx);
+            if ours == theirs {
+                self.agreed = Some(ours);
+                // The other side applied everything sent before this phase
+                self.unconfirmed.retain(|(seq, _)| *seq >= next_seq);
+            } else {
+                self.report_desync(ours, theirs);
+            }
+        }
+    }
+
+    fn report_desync(&mut self, ours: Checkpoint, theirs: Checkpoint) {
+        error!(
+            "Desync on turn {} in the {:?} phase, checksum {:016x} here and {:016x} on the other side",
+            ours.turn, ours.phase, ours.checksum, theirs.checksum
+        );
+        self.desync.get_or_insert(ours);
+    }
+}
+
+/// Why `remote` could not have sent the command during its phase. `claimed` collects the
+/// tiles units of the batch moved to so far.
+fn check_command(
+    world: &WorldState,
+    remote: Faction,
+    command: &Command,
+    claimed: &mut HashSet<Point>,
+) -> Result<(), String> {
+    let known = |id: UnitId| world.get_unit(id).ok_or(format!("no unit {}", *id));
+    match command {
+        Command::CommitUnit(unit) => {
+            let current = known(unit.id())?;
+            if current.faction != remote || unit.faction != remote {
+                return Err("only the own units can be moved".into());
+            }
+            if !world.map.in_bounds(unit.pos) {
+                return Err(format!("{:?} is off the map", unit.pos));
+            }
+            let taken = world
+                .unit_at(unit.pos)
+                .is_some_and(|other| other.id() != unit.id());
+            if taken || (current.pos != unit.pos && !claimed.insert(unit.pos)) {
+                return Err(format!("{:?} is taken", unit.pos));
+            }
+        }
+        Command::DamageUnit(id, _) | Command::ApplyStatus(id, _) => {
+            let target = known(*id)?;
+            if let Command::DamageUnit(_, damage) = command
+                && *damage < 0
+            {
+                return Err("damage can not heal".into());
+            }
+            let attackable = world
+                .units()
+                .any(|unit| unit.faction == remote && unit.is_hostile_to(target));
+            if !attackable {
+                return Err(format!("no unit can attack unit {}", **id));
+            }
+        }
+        Command::Talk(initiator, target) => {
+            if known(*initiator)?.faction != remote {
+                return Err("only the own units can start a talk".into());
+            }
+            known(*target)?;
+        }
+        Command::SetupTurn(faction) => {
+            if *faction == remote {
+                return Err("the phase can only pass to the other side".into());
+            }
+        }
+    }
+    Ok(())
+}
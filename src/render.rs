@@ -1,7 +1,11 @@
 use std::f32;
 
+use crate::assets::TextureStore;
+use crate::cursor::Cursor;
+use crate::grid::GridTopology;
 use crate::map::Map;
-use crate::math::{Point, clamp_rect_to_bounds};
+use crate::math::Point;
+use crate::status::StatusKind;
 use crate::unit::Unit;
 
 use macroquad::prelude::vec2;
@@ -36,13 +40,11 @@ impl RenderContext {
             ..Default::default()
         };
 
-        let mut render_context = Self {
+        // Sized on the first resize check so this works without a window
+        Self {
             view_camera,
-            screen_size: (1.0, 1.0),
-        };
-
-        render_context.resize_if_required();
-        render_context
+            screen_size: (0.0, 0.0),
+        }
     }
 
     pub fn resize_if_required(&mut self) {
@@ -75,19 +77,60 @@ impl RenderContext {
         &self.view_camera
     }
 
-    pub fn render_map(map: &Map, viewport: &Viewport) {
+    /// The view camera zoomed in as far as the viewport is, for everything drawn on the map
+    pub fn map_camera(&self, viewport: &Viewport) -> Camera2D {
+        Camera2D {
+            zoom: self.view_camera.zoom * viewport.zoom,
+            offset: self.view_camera.offset,
+            viewport: self.view_camera.viewport,
+            ..Default::default()
+        }
+    }
+
+    /// Layout position on the map under a screen position, undoing [`Self::screen_pos`] and
+    /// the letterboxed camera. `None` outside of the letterbox.
+    pub fn screen_to_layout(&self, screen: Vec2, viewport: &Viewport) -> Option<Vec2> {
+        let (x, y, w, h) = self.view_camera.viewport?;
+        // The camera viewport counts from the bottom of the screen like OpenGL
+        let top = self.screen_size.1 - (y + h) as f32;
+        let frac = vec2(
+            (screen.x - x as f32) / w as f32,
+            (screen.y - top) / h as f32,
+        );
+        if !(0.0..1.0).contains(&frac.x) || !(0.0..1.0).contains(&frac.y) {
+            return None;
+        }
+
+        let render_rect = viewport.get_render_rect();
+        Some(render_rect.point() + frac * render_rect.size())
+    }
+
+    /// Screen pixels covered by one tile
+    pub fn tile_pixels(&self, viewport: &Viewport) -> f32 {
+        self.view_camera
+            .viewport
+            .map_or(TILE_SIZE_F, |(_, _, w, _)| {
+                w as f32 / viewport.get_render_rect().w
+            })
+    }
+
+    pub fn render_map(map: &Map, texture_store: &TextureStore, viewport: &Viewport) {
         let view_rect = viewport.get_render_rect();
 
-        let start_x = view_rect.left().floor().max(0.0) as i32;
+        // Shifted rows poke half a tile into the view from the left
+        let start_x = (view_rect.left() - map.topology.layout_padding())
+            .floor()
+            .max(0.0) as i32;
         let start_y = view_rect.top().floor().max(0.0) as i32;
 
-        let end_x = (view_rect.right().ceil()).min(viewport.map_width) as i32;
+        let end_x = (view_rect.right().ceil()).min(map.width as f32) as i32;
         let end_y = (view_rect.bottom().ceil()).min(viewport.map_height) as i32;
 
         for y in start_y..end_y {
             for x in start_x..end_x {
                 let pt: Point = (x, y).into();
-                Self::render_sprite(pt, map.get_texture_handle(pt), WHITE, 1.0, viewport);
+                let texture = texture_store.get(map.get_terrain(pt).texture_path());
+                Self::render_sprite(pt, &texture, WHITE, 1.0, viewport);
             }
         }
     }
@@ -100,7 +143,7 @@ impl RenderContext {
         viewport: &Viewport,
     ) {
         // TODO Rewrite this
-        let (mut x, mut y) = Self::screen_pos(pos, viewport.get_render_rect());
+        let (mut x, mut y) = Self::screen_pos(pos, viewport);
         let padding = ((1.0 - scale) / 2.0) * TILE_SIZE_F;
         (x, y) = (x + padding, y + padding);
         let params = DrawTextureParams {
@@ -111,28 +154,46 @@ impl RenderContext {
         draw_texture_ex(texture, x, y, color, params);
     }
 
-    pub fn render_unit(unit: &Unit, viewport: &Viewport) {
+    /// Draws the unit at `pos`, in tiles, which is off its own tile while it moves
+    pub fn render_unit(unit: &Unit, pos: Vec2, texture_store: &TextureStore, viewport: &Viewport) {
         let color = if unit.turn_complete {
             Color::new(0.75, 0.75, 0.75, 1.0)
         } else {
             WHITE
         };
         Self::render_sprite(
-            unit.render_pos.unwrap_or(unit.pos.into()),
-            &unit.texture,
+            pos,
+            &texture_store.get(&unit.texture_path),
             color,
             1.0,
             viewport,
         );
 
-        let (x, y) = Self::screen_pos(
-            unit.render_pos.unwrap_or(unit.pos.into()),
-            viewport.get_render_rect(),
-        );
+        let (x, y) = Self::screen_pos(pos, viewport);
         let (w, h) = (TILE_SIZE_F * 0.9, TILE_SIZE_F * 0.2);
-        let health_frac = (unit.curr_health as f32) / (unit.max_health as f32);
+        let health_frac = (unit.curr_health as f32) / (unit.max_health() as f32);
         draw_rectangle(x, y + TILE_SIZE_F, w, h, GRAY);
         draw_rectangle(x, y + TILE_SIZE_F, w * health_frac, h, RED);
+
+        let icon_size = TILE_SIZE_F * 0.25;
+        for (i, effect) in unit.status_effects.iter().enumerate() {
+            let (label, color) = Self::status_icon(effect.kind);
+            let icon_x = x + i as f32 * icon_size;
+            draw_rectangle(icon_x, y, icon_size, icon_size, color);
+            draw_text(label, icon_x + 2.0, y + icon_size - 2.0, icon_size, BLACK);
+        }
+    }
+
+    fn status_icon(kind: StatusKind) -> (&'static str, Color) {
+        match kind {
+            StatusKind::Poison => ("P", PURPLE),
+            StatusKind::Sleep => ("Z", SKYBLUE),
+            StatusKind::Stun => ("S", YELLOW),
+            StatusKind::Berserk => ("B", RED),
+            StatusKind::AttackUp => ("A", ORANGE),
+            StatusKind::DefenceUp => ("D", GREEN),
+            StatusKind::Rooted => ("R", BROWN),
+        }
     }
 
     pub fn render_tile_rectangle(
@@ -141,7 +202,7 @@ impl RenderContext {
         scale: f32,
         viewport: &Viewport,
     ) {
-        let (mut x, mut y) = Self::screen_pos(pos, viewport.get_render_rect());
+        let (mut x, mut y) = Self::screen_pos(pos, viewport);
         let (w, h) = (TILE_SIZE_F * scale, TILE_SIZE_F * scale);
 
         x += (TILE_SIZE_F - w) / 2.0;
@@ -149,8 +210,40 @@ impl RenderContext {
         draw_rectangle(x, y, w, h, color);
     }
 
-    pub fn screen_pos(tile_pos: impl Into<Vec2>, render_rect: Rect) -> (f32, f32) {
-        let tile_pos = tile_pos.into();
+    /// Draws a line through the centers of the tiles with an arrow head on the last one
+    pub fn render_path(path: &[Point], color: Color, viewport: &Viewport) {
+        if path.len() < 2 {
+            return;
+        }
+
+        let thickness = TILE_SIZE_F * 0.2;
+        let center = |pt: Point| {
+            let (x, y) = Self::screen_pos(pt, viewport);
+            vec2(x, y) + Vec2::splat(TILE_SIZE_F / 2.0)
+        };
+
+        for segment in path.windows(2) {
+            let (from, to) = (center(segment[0]), center(segment[1]));
+            draw_line(from.x, from.y, to.x, to.y, thickness, color);
+            draw_circle(from.x, from.y, thickness / 2.0, color);
+        }
+
+        let tip = center(path[path.len() - 1]);
+        let dir = (tip - center(path[path.len() - 2])).normalize_or_zero();
+        let side = dir.perp() * TILE_SIZE_F * 0.25;
+        let base = tip - dir * TILE_SIZE_F * 0.3;
+        draw_triangle(
+            tip + dir * TILE_SIZE_F * 0.1,
+            base + side,
+            base - side,
+            color,
+        );
+    }
+
+    /// Top left corner of the tile on screen, laid out according to the grid topology
+    pub fn screen_pos(tile_pos: impl Into<Vec2>, viewport: &Viewport) -> (f32, f32) {
+        let tile_pos = viewport.topology.layout_pos(tile_pos.into());
+        let render_rect = viewport.get_render_rect();
         (
             Self::screen_x(tile_pos.x, &render_rect),
             Self::screen_y(tile_pos.y, &render_rect),
@@ -175,31 +268,53 @@ impl RenderContext {
     }
 }
 
+#[must_use]
+/// Maps smaller than the rect keep it at the origin
+fn clamp_rect_to_bounds(mut rect: Rect, width: f32, height: f32) -> Rect {
+    rect.x = rect.x.clamp(0.0, (width - rect.w).max(0.0));
+    rect.y = rect.y.clamp(0.0, (height - rect.h).max(0.0));
+
+    rect
+}
+
 #[derive(Clone, Copy, PartialEq, Debug)]
 enum ViewportMode {
-    CenterOn { corner: Vec2 },
+    CenterOn {
+        corner: Vec2,
+    },
     Follow(Point),
     Free,
+    /// Moved by hand. Following picks up again once the followed point changes.
+    Panned {
+        followed: Option<Point>,
+    },
 }
 #[derive(Debug)]
 pub struct Viewport {
     render_view: Rect,
     map_width: f32,
     map_height: f32,
+    topology: GridTopology,
     mode: ViewportMode,
+    /// Above 1 shows fewer, bigger tiles
+    zoom: f32,
 }
 impl Viewport {
     // TODO Make this a global constant from which all animation speeds are derived from
     pub const SHIFT_SPEED: f32 = 0.35;
     const MARGIN: f32 = 2.0;
+    const MIN_ZOOM: f32 = 0.6;
+    const MAX_ZOOM: f32 = 2.0;
 
     #[allow(clippy::cast_precision_loss)]
-    pub fn new(map_width: i32, map_height: i32) -> Self {
+    pub fn new(map_width: i32, map_height: i32, topology: GridTopology) -> Self {
         Self {
             render_view: Rect::new(0.0, 0.0, VIEWPORT_TILES_WIDTH_F, VIEWPORT_TILES_HEIGHT_F),
-            map_width: map_width as f32,
+            map_width: map_width as f32 + topology.layout_padding(),
             map_height: map_height as f32,
+            topology,
             mode: ViewportMode::Free,
+            zoom: 1.0,
         }
     }
 
@@ -210,8 +325,8 @@ impl Viewport {
     pub fn set_center_on(&mut self, pt: impl Into<Vec2>) {
         let pt = pt.into();
         let mut centered_rect = self.render_view;
-        centered_rect.x = (pt.x - (VIEWPORT_TILES_WIDTH_F / 2.0)).round();
-        centered_rect.y = (pt.y - (VIEWPORT_TILES_HEIGHT_F / 2.0)).round();
+        centered_rect.x = (pt.x - (centered_rect.w / 2.0)).round();
+        centered_rect.y = (pt.y - (centered_rect.h / 2.0)).round();
         centered_rect = clamp_rect_to_bounds(centered_rect, self.map_width, self.map_height);
         self.mode = ViewportMode::CenterOn {
             corner: centered_rect.point(),
@@ -219,7 +334,44 @@ impl Viewport {
     }
 
     pub fn set_follow(&mut self, pt: impl Into<Point>) {
-        self.mode = ViewportMode::Follow(pt.into());
+        let pt = pt.into();
+        if self.mode == (ViewportMode::Panned { followed: Some(pt) }) {
+            return;
+        }
+        self.mode = ViewportMode::Follow(pt);
+    }
+
+    /// Moves the view by hand, in tiles
+    pub fn pan(&mut self, delta: Vec2) {
+        self.stop_following();
+        self.render_view = self.render_view.offset(delta);
+        self.render_view = clamp_rect_to_bounds(self.render_view, self.map_width, self.map_height);
+    }
+
+    /// Zooms by `factor` while `focus`, a layout position, stays where it is on screen
+    pub fn zoom_at(&mut self, factor: f32, focus: Vec2) {
+        self.stop_following();
+        let zoom = (self.zoom * factor).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        let scale = self.zoom / zoom;
+        self.zoom = zoom;
+
+        let corner = focus + (self.render_view.point() - focus) * scale;
+        self.render_view = Rect::new(
+            corner.x,
+            corner.y,
+            VIEWPORT_TILES_WIDTH_F / zoom,
+            VIEWPORT_TILES_HEIGHT_F / zoom,
+        );
+        self.render_view = clamp_rect_to_bounds(self.render_view, self.map_width, self.map_height);
+    }
+
+    fn stop_following(&mut self) {
+        let followed = match self.mode {
+            ViewportMode::Follow(pt) => Some(pt),
+            ViewportMode::Panned { followed } => followed,
+            ViewportMode::CenterOn { .. } | ViewportMode::Free => None,
+        };
+        self.mode = ViewportMode::Panned { followed };
     }
 
     pub fn update(&mut self) {
@@ -231,7 +383,7 @@ impl Viewport {
                 }
             }
             ViewportMode::Follow(point) => self.ensure_in_view(point),
-            ViewportMode::Free => {}
+            ViewportMode::Free | ViewportMode::Panned { .. } => {}
         }
     }
 
@@ -247,13 +399,13 @@ impl Viewport {
         let pt: Vec2 = pt.into();
         let center = self.render_view.center();
 
-        let dx = if (pt.x - center.x).abs() > (VIEWPORT_TILES_WIDTH_F / 2.0 - Self::MARGIN) {
+        let dx = if (pt.x - center.x).abs() > (self.render_view.w / 2.0 - Self::MARGIN) {
             pt.x - center.x
         } else {
             center.x.round() - center.x
         };
 
-        let dy = if (pt.y - center.y).abs() > (VIEWPORT_TILES_HEIGHT_F / 2.0 - Self::MARGIN) {
+        let dy = if (pt.y - center.y).abs() > (self.render_view.h / 2.0 - Self::MARGIN) {
             pt.y - center.y
         } else {
             center.y.round() - center.y
@@ -287,10 +439,14 @@ impl Viewport {
 #[derive(Clone, Copy, Debug)]
 pub struct RenderCtxWithViewport<'a> {
     pub viewport: &'a Viewport,
+    pub texture_store: &'a TextureStore,
 }
 impl<'a> RenderCtxWithViewport<'a> {
-    pub fn new(viewport: &'a Viewport) -> Self {
-        Self { viewport }
+    pub fn new(viewport: &'a Viewport, texture_store: &'a TextureStore) -> Self {
+        Self {
+            viewport,
+            texture_store,
+        }
     }
 
     pub fn is_tile_visible(self, pt: impl Into<Vec2>) -> bool {
@@ -301,6 +457,10 @@ impl<'a> RenderCtxWithViewport<'a> {
         RenderContext::render_tile_rectangle(pos, color, scale, self.viewport);
     }
 
+    pub fn render_path(self, path: &[Point], color: Color) {
+        RenderContext::render_path(path, color, self.viewport);
+    }
+
     pub fn render_sprite(
         self,
         pos: impl Into<Vec2>,
@@ -310,4 +470,9 @@ impl<'a> RenderCtxWithViewport<'a> {
     ) {
         RenderContext::render_sprite(pos, texture, color, scale, self.viewport);
     }
+
+    pub fn render_cursor(self, cursor: &Cursor, scale: f32) {
+        let texture = self.texture_store.get(Cursor::TEXTURE);
+        self.render_sprite(cursor.get_render_pos(), &texture, WHITE, scale);
+    }
 }
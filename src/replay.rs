@@ -0,0 +1,72 @@
+//! Recordings of the controller's input, one [`Frame`] per line after a header line. Played back
+//! into a battle started the same way, a recording goes through the same states frame by frame,
+//! menus included.
+//!
+//! Taps from [`Engine::step_tap`](crate::game::Engine::step_tap) skip the controller and are
+//! not recorded.
+
+use crate::grid::GridTopology;
+use crate::settings::Settings;
+
+use input_lib::Frame;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+/// What the window frontend started the battle with, the rest comes from the same flags
+#[derive(Clone, Debug, Serialize, serde::Deserialize)]
+pub struct ReplayHeader {
+    pub seed: u64,
+    pub topology: GridTopology,
+    pub settings: Settings,
+    /// Window size in pixels, pointers are scaled to the window played back in
+    pub screen: [f32; 2],
+}
+
+/// Writes frames as they come, flushing each one so a crash keeps everything up to it
+#[derive(Debug)]
+pub struct Recorder {
+    writer: BufWriter<File>,
+}
+
+impl Recorder {
+    /// # Errors
+    /// If the file can not be created
+    pub fn create(path: &str, header: &impl Serialize) -> io::Result<Self> {
+        let mut recorder = Self {
+            writer: BufWriter::new(File::create(path)?),
+        };
+        recorder.write_line(header)?;
+        Ok(recorder)
+    }
+
+    /// # Errors
+    /// If the file can not be written
+    pub fn record(&mut self, frame: &Frame) -> io::Result<()> {
+        self.write_line(frame)
+    }
+
+    fn write_line(&mut self, value: &impl Serialize) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, value).map_err(io::Error::other)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+}
+
+/// Reads back the header and frames written by a [`Recorder`]
+///
+/// # Errors
+/// If the file can not be read or a line is malformed
+pub fn load<H: DeserializeOwned>(path: &str) -> io::Result<(H, Vec<Frame>)> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    let header = lines
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "Empty recording"))??;
+    let header = serde_json::from_str(&header).map_err(io::Error::other)?;
+    let frames = lines
+        .map(|line| serde_json::from_str(&line?).map_err(io::Error::other))
+        .collect::<io::Result<_>>()?;
+    Ok((header, frames))
+}
@@ -0,0 +1,301 @@
+//! Test harness that sets up a battle from an ASCII map and drives it through the regular
+//! states, so movement rules and state flow can be checked without a window.
+//!
+//! ```no_run
+//! use luminara::scenario::Scenario;
+//! use luminara_core::class::UnitClass;
+//! use luminara_core::world::Faction;
+//!
+//! let mut battle = Scenario::new(&["....", ".T..", "...."])
+//!     .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+//!     .start();
+//! battle.keys("z");
+//! battle.assert_state("Player Move");
+//! ```
+// Panicking is how the harness reports a failed check, and checks are called for that alone
+#![allow(clippy::missing_panics_doc, clippy::must_use_candidate)]
+
+use crate::class::UnitClass;
+use crate::command::Command;
+use crate::event::{Condition, Reinforcement, Trigger, TriggerAction};
+use crate::game::Engine;
+use crate::grid::GridTopology;
+use crate::map::Map;
+use crate::math::Point;
+use crate::pathfinding::DijkstraMap;
+use crate::replay::{self, Recorder};
+use crate::settings::Settings;
+use crate::tty::{FRAME_TIME, MAX_IDLE_FRAMES, TextScreen, key_input};
+use crate::unit::{ErasedUnit, Unit, UnitId, Weapon, WeaponId, WeaponKind};
+use crate::world::{Faction, WorldState};
+
+use input_lib::{ButtonState, Buttons, Physical};
+
+use std::collections::HashSet;
+
+const DEFAULT_MIGHT: i32 = 5;
+
+#[derive(Debug)]
+pub struct Scenario {
+    rows: Vec<String>,
+    topology: GridTopology,
+    zone_of_control: bool,
+    seed: u64,
+    humans: Vec<Faction>,
+    units: Vec<ErasedUnit>,
+    triggers: Vec<Trigger>,
+    reinforcements: Vec<Reinforcement>,
+}
+
+/// A running scenario, unit ids follow the order the units were added in
+pub struct Battle {
+    engine: Engine,
+}
+
+impl Scenario {
+    /// Rows use the glyphs of [`crate::map::Terrain::symbol`]
+    pub fn new(rows: &[&str]) -> Self {
+        Self {
+            rows: rows.iter().map(ToString::to_string).collect(),
+            topology: GridTopology::Square,
+            zone_of_control: false,
+            seed: 0,
+            humans: vec![Faction::Player],
+            units: Vec::new(),
+            triggers: Vec::new(),
+            reinforcements: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn hex(mut self) -> Self {
+        self.topology = GridTopology::Hex;
+        self
+    }
+
+    #[must_use]
+    pub fn zone_of_control(mut self) -> Self {
+        self.zone_of_control = true;
+        self
+    }
+
+    #[must_use]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Both factions are played through the input, handing the device over between phases
+    #[must_use]
+    pub fn versus(mut self) -> Self {
+        self.humans = vec![Faction::Player, Faction::Enemy];
+        self
+    }
+
+    /// Adds a level 1 unit armed with the first weapon kind of its class
+    #[must_use]
+    pub fn unit(mut self, class: UnitClass, faction: Faction, pos: (i32, i32)) -> Self {
+        let kind = class.data().weapons[0];
+        let range = if matches!(kind, WeaponKind::Bow | WeaponKind::Tome) {
+            2
+        } else {
+            1
+        };
+        let id = WeaponId::new(self.units.len().try_into().unwrap());
+
+        let mut unit = ErasedUnit::with_class(class, faction, pos, "");
+        unit.weapon = Some(Weapon::new(id, kind, DEFAULT_MIGHT, range));
+        self.units.push(unit);
+        self
+    }
+
+    #[must_use]
+    pub fn trigger(mut self, condition: Condition, actions: Vec<TriggerAction>) -> Self {
+        self.triggers.push(Trigger::new(condition, actions));
+        self
+    }
+
+    #[must_use]
+    pub fn reinforcement(mut self, reinforcement: Reinforcement) -> Self {
+        self.reinforcements.push(reinforcement);
+        self
+    }
+
+    /// Changes the unit added last
+    #[must_use]
+    pub fn with(mut self, f: impl FnOnce(&mut ErasedUnit)) -> Self {
+        f(self
+            .units
+            .last_mut()
+            .expect("with() needs a unit to change"));
+        self
+    }
+
+    /// Panics if the map is malformed
+    pub fn start(self) -> Battle {
+        let mut engine = Engine::with_units(self.map(), self.seed, &self.units);
+        engine.seat_humans(&self.humans);
+        let mut battle = Battle { engine };
+        battle.settle();
+        battle
+    }
+
+    /// Just the world at the start of the player's first phase, without any states driving it.
+    /// Panics if the map is malformed.
+    pub fn world(&self) -> WorldState {
+        let mut world = WorldState::new(self.map(), self.seed);
+        for unit in &self.units {
+            world.spawn_units(unit);
+        }
+        world.setup_turn(Faction::Player);
+        world
+    }
+
+    fn map(&self) -> Map {
+        let mut map = Map::from_ascii(&self.rows, self.topology).expect("Malformed scenario map");
+        map.zone_of_control = self.zone_of_control;
+        map.triggers.clone_from(&self.triggers);
+        map.reinforcements.clone_from(&self.reinforcements);
+        map
+    }
+}
+
+impl Battle {
+    fn settle(&mut self) {
+        assert!(
+            self.engine.settle(FRAME_TIME, MAX_IDLE_FRAMES),
+            "The battle stopped waiting for input"
+        );
+    }
+
+    /// Presses and releases the input, then lets animations and AI phases play out
+    pub fn press(&mut self, input: ButtonState) -> &mut Self {
+        self.engine.step(input, FRAME_TIME);
+        self.settle();
+        self
+    }
+
+    pub fn button(&mut self, button: Buttons) -> &mut Self {
+        let mut input = ButtonState::default();
+        input.buttons.insert(button);
+        self.press(input)
+    }
+
+    /// One press per character with the terminal's bindings, `wasd` move and `z`/`x` are A/B
+    pub fn keys(&mut self, keys: &str) -> &mut Self {
+        for key in keys.chars() {
+            let input = key_input(key).unwrap_or_else(|| panic!("No binding for {key:?}"));
+            self.press(input);
+        }
+        self
+    }
+
+    /// Writes every following frame of input to `path`, see [`crate::replay`]
+    pub fn record(&mut self, path: &str) -> &mut Self {
+        let recorder = Recorder::create(path, &()).expect("The recording can be created");
+        self.engine.record(recorder);
+        self
+    }
+
+    /// Plays a recording back frame by frame, it only waits for input where the recording did
+    pub fn replay(&mut self, path: &str) -> &mut Self {
+        let ((), frames) = replay::load(path).expect("The recording can be read");
+        for frame in &frames {
+            self.engine.step_frame(frame);
+        }
+        self
+    }
+
+    /// A finger tapping the tile
+    pub fn tap(&mut self, tile: (i32, i32)) -> &mut Self {
+        self.engine.step_tap(tile.into(), FRAME_TIME);
+        self.settle();
+        self
+    }
+
+    /// A physical key going down and up again, through the controller's bindings
+    pub fn physical(&mut self, input: Physical) -> &mut Self {
+        self.engine.step_physical(input, FRAME_TIME);
+        self.settle();
+        self
+    }
+
+    /// Goes through the state machine's command buffer, so states see the change as usual
+    pub fn command(&mut self, command: Command) -> &mut Self {
+        self.engine.queue_command(command);
+        self.press(ButtonState::default())
+    }
+
+    pub fn world(&self) -> &WorldState {
+        self.engine.world()
+    }
+
+    pub fn settings(&self) -> &Settings {
+        self.engine.settings()
+    }
+
+    /// Panics if the unit has been defeated
+    pub fn unit(&self, id: u32) -> &Unit {
+        self.world()
+            .get_unit(UnitId::new(id))
+            .unwrap_or_else(|| panic!("Unit {id} is not on the map"))
+    }
+
+    pub fn state(&self) -> &'static str {
+        self.engine.current_state()
+    }
+
+    /// Tiles the unit could stop on if it moved now
+    pub fn reachables(&self, id: u32) -> HashSet<Point> {
+        DijkstraMap::new(self.world(), self.unit(id))
+            .get_reachables()
+            .clone()
+    }
+
+    /// What the terminal frontend would show right now
+    pub fn screen(&self) -> TextScreen {
+        self.engine.render_text()
+    }
+
+    pub fn assert_state(&self, name: &str) -> &Self {
+        assert_eq!(self.state(), name, "Unexpected state on top of the stack");
+        self
+    }
+
+    pub fn assert_pos(&self, id: u32, pos: (i32, i32)) -> &Self {
+        assert_eq!(
+            self.unit(id).pos,
+            Point::from(pos),
+            "Unit {id} is elsewhere"
+        );
+        self
+    }
+
+    pub fn assert_hp(&self, id: u32, hp: i32) -> &Self {
+        assert_eq!(self.unit(id).curr_health, hp, "Unit {id} has other health");
+        self
+    }
+
+    pub fn assert_defeated(&self, id: u32) -> &Self {
+        assert!(
+            self.world().get_unit(UnitId::new(id)).is_none(),
+            "Unit {id} is still on the map"
+        );
+        self
+    }
+
+    pub fn assert_reachables(&self, id: u32, expected: &[(i32, i32)]) -> &Self {
+        let expected: HashSet<Point> = expected.iter().map(|pt| (*pt).into()).collect();
+        let actual = self.reachables(id);
+
+        let mut missing: Vec<_> = expected.difference(&actual).collect();
+        let mut extra: Vec<_> = actual.difference(&expected).collect();
+        missing.sort();
+        extra.sort();
+        assert!(
+            missing.is_empty() && extra.is_empty(),
+            "Unit {id} reachables differ, missing {missing:?}, unexpected {extra:?}"
+        );
+        self
+    }
+}
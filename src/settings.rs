@@ -0,0 +1,52 @@
+use input_lib::{Bindings, ButtonKind, Layout};
+use macroquad::logging::warn;
+use serde::{Deserialize, Serialize};
+
+use std::io::ErrorKind;
+
+/// Where the window frontend keeps the settings, next to `suspend.json`
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Everything the player sets up once and keeps across battles. Missing fields fall back to
+/// their defaults, so older files keep loading.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub bindings: Bindings<ButtonKind>,
+    pub layout: Layout,
+    /// Set while the layout editor is open, changes are saved once it closes
+    #[serde(skip)]
+    pub editing_layout: bool,
+}
+
+impl Settings {
+    /// Defaults if the file does not exist yet or can not be read, a layout edited by hand is
+    /// brought back in range
+    #[must_use]
+    pub fn load(path: &str) -> Self {
+        let mut settings = Self::read(path);
+        settings.layout = settings.layout.sanitized();
+        settings
+    }
+
+    fn read(path: &str) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|err| {
+                warn!("Ignoring malformed settings in {}: {}", path, err);
+                Self::default()
+            }),
+            Err(err) if err.kind() == ErrorKind::NotFound => Self::default(),
+            Err(err) => {
+                warn!("Could not read the settings in {}: {}", path, err);
+                Self::default()
+            }
+        }
+    }
+
+    /// # Errors
+    /// If the file can not be written
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+}
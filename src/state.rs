@@ -1,6 +1,48 @@
 mod animation;
+mod controls;
+mod dialogue;
+mod hot_seat;
+mod layout_editor;
+mod map_menu;
+mod online;
 mod player;
+mod remote;
 mod simulated;
 mod state_machine;
+mod unit_info;
 
+pub use dialogue::DialogueState;
 pub use state_machine::StateMachine;
+
+use crate::game::GameCtxView;
+use crate::world::Faction;
+
+use hot_seat::PassDevice;
+use online::NetOpponent;
+use player::PlayerSelect;
+use remote::RemoteManager;
+use simulated::SimulatedManager;
+use state_machine::GameState;
+
+/// First state of `faction`'s phase. The other side of an online battle or a bot attached to
+/// the faction plays it, otherwise a human seated for it does and the AI is left with the rest.
+fn phase_state(faction: Faction, game_ctx: &GameCtxView) -> Box<dyn GameState> {
+    if game_ctx
+        .net
+        .as_ref()
+        .is_some_and(|net| net.remote() == faction)
+    {
+        return NetOpponent::boxed_new(faction);
+    }
+    if game_ctx.bots.controls(faction) {
+        return RemoteManager::boxed_new(faction);
+    }
+    if !game_ctx.humans.contains(&faction) {
+        return Box::new(SimulatedManager::new(faction));
+    }
+    // Several humans share the device, the next one has to take it first
+    if game_ctx.humans.len() > 1 {
+        return PassDevice::boxed_new(faction);
+    }
+    PlayerSelect::boxed_new(game_ctx.world, faction)
+}
@@ -4,17 +4,19 @@ use crate::math::Point;
 use crate::unit::Unit;
 
 use macroquad::math::Vec2;
-use macroquad::time::get_frame_time;
 
 use std::collections::VecDeque;
 
 const TICK_TIME: f32 = 0.15;
+const PAN_HOLD_TIME: f32 = 0.6;
 
 #[derive(Debug)]
 pub struct MoveAnimation {
     timer: f32,
     unit: Unit,
     path: Vec<Point>,
+    /// Between the unit's tile and the next one on the path
+    render_pos: Option<Vec2>,
 }
 
 impl MoveAnimation {
@@ -23,6 +25,7 @@ impl MoveAnimation {
             timer: 0.0,
             unit,
             path,
+            render_pos: None,
         })
     }
 }
@@ -32,6 +35,10 @@ impl GameState for MoveAnimation {
         Some(&self.unit)
     }
 
+    fn active_render_pos(&self) -> Option<Vec2> {
+        self.render_pos
+    }
+
     fn update(
         &mut self,
         msg_queue: &mut VecDeque<GameMsg>,
@@ -41,10 +48,9 @@ impl GameState for MoveAnimation {
         if game_ctx.viewport.is_centering() {
             return Transition::None;
         }
-        self.timer += get_frame_time();
+        self.timer += game_ctx.frame_time;
 
         if self.path.is_empty() {
-            self.unit.render_pos = None;
             msg_queue.push_back(GameMsg::MoveAnimationDone(self.unit.clone()));
             return Transition::Pop;
         }
@@ -53,7 +59,7 @@ impl GameState for MoveAnimation {
         let next_pos: Vec2 = (*self.path.last().unwrap()).into();
         // Normalised
         let progress = (self.timer / TICK_TIME).min(1.0);
-        self.unit.render_pos = Some(curr_pos.lerp(next_pos, progress));
+        self.render_pos = Some(curr_pos.lerp(next_pos, progress));
 
         if self.timer >= TICK_TIME {
             self.unit.pos = self.path.pop().unwrap();
@@ -70,3 +76,59 @@ impl GameState for MoveAnimation {
         "Move Animation"
     }
 }
+
+/// Centers the viewport on each point in turn, holding briefly on every one
+#[derive(Debug)]
+pub struct CameraPan {
+    timer: f32,
+    points: Vec<Point>,
+    current: usize,
+}
+
+impl CameraPan {
+    pub fn boxed_new(points: Vec<Point>) -> Box<Self> {
+        Box::new(Self {
+            timer: 0.0,
+            points,
+            current: 0,
+        })
+    }
+}
+
+impl GameState for CameraPan {
+    fn on_enter(&self, game_ctx: GameCtxView) {
+        if let Some(pt) = self.points.first() {
+            game_ctx.viewport.set_center_on(*pt);
+        }
+    }
+
+    fn update(
+        &mut self,
+        _msg_queue: &mut VecDeque<GameMsg>,
+        _commands: &mut Commands,
+        game_ctx: GameCtxView,
+    ) -> Transition {
+        if game_ctx.viewport.is_centering() {
+            return Transition::None;
+        }
+
+        self.timer += game_ctx.frame_time;
+        if self.timer < PAN_HOLD_TIME {
+            return Transition::None;
+        }
+
+        self.current += 1;
+        self.timer = 0.0;
+        match self.points.get(self.current) {
+            Some(pt) => {
+                game_ctx.viewport.set_center_on(*pt);
+                Transition::None
+            }
+            None => Transition::Pop,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Camera Pan"
+    }
+}
@@ -0,0 +1,196 @@
+use super::layout_editor::LayoutEditor;
+use super::state_machine::{Commands, GameMsg, GameState, Transition};
+use crate::game::GameCtxView;
+use crate::render::{RenderContext, RenderCtxWithViewport};
+use crate::tty::TextScreen;
+
+use std::collections::VecDeque;
+
+use input_lib::{BindingTarget, Bindings, ButtonKind, ButtonState, Buttons, Physical};
+use macroquad::color::{Color, GREEN, WHITE, YELLOW};
+use macroquad::input::KeyCode;
+use macroquad::shapes::{draw_rectangle, draw_rectangle_lines};
+use macroquad::text::draw_text;
+
+/// Cancels waiting for a key, so it can not be bound itself
+const CANCEL: Physical = Physical::Key(KeyCode::Escape);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Mode {
+    Browse,
+    /// "Press a key for A"
+    Waiting,
+    /// The key already drives `other`, pressing it again swaps the two
+    Conflict {
+        input: Physical,
+        other: ButtonKind,
+    },
+}
+
+/// Rebinds the keyboard, one button at a time. Changes go straight into the settings, the
+/// engine hands them to the controller and saves them.
+#[derive(Debug)]
+pub struct Controls {
+    /// Copy of the settings' bindings for rendering
+    bindings: Bindings<ButtonKind>,
+    selected: usize,
+    mode: Mode,
+    /// The key that was just bound is still down and must not act as its new button yet
+    wait_release: bool,
+}
+
+impl Controls {
+    pub fn boxed_new(bindings: &Bindings<ButtonKind>) -> Box<Self> {
+        Box::new(Self {
+            bindings: bindings.clone(),
+            selected: 0,
+            mode: Mode::Browse,
+            wait_release: true,
+        })
+    }
+
+    fn target(&self) -> ButtonKind {
+        ButtonKind::ALL[self.selected]
+    }
+
+    fn row(&self, target: ButtonKind) -> String {
+        let inputs: Vec<String> = self
+            .bindings
+            .inputs_of(target)
+            .map(Physical::name)
+            .collect();
+        let inputs = if inputs.is_empty() {
+            "-".to_string()
+        } else {
+            inputs.join(", ")
+        };
+        format!("{:<7}{inputs}", target.name())
+    }
+
+    fn prompt(&self) -> String {
+        let name = self.target().name();
+        match self.mode {
+            Mode::Browse => "A rebinds, X moves the on screen buttons, B goes back".to_string(),
+            Mode::Waiting => format!("Press a key for {name}, Escape cancels"),
+            Mode::Conflict { input, other } => format!(
+                "{} is {}, press it again to swap with {name}",
+                input.name(),
+                other.name()
+            ),
+        }
+    }
+
+    fn capture(&mut self, input: Physical) {
+        if input == CANCEL {
+            self.mode = Mode::Browse;
+            return;
+        }
+        let target = self.target();
+        match self.mode {
+            Mode::Conflict {
+                input: conflict, ..
+            } if conflict == input => {
+                self.bindings.swap(target, input);
+                self.mode = Mode::Browse;
+            }
+            _ => match self.bindings.rebind(target, input) {
+                Ok(()) => self.mode = Mode::Browse,
+                Err(other) => self.mode = Mode::Conflict { input, other },
+            },
+        }
+        self.wait_release = self.mode == Mode::Browse;
+    }
+}
+
+impl GameState for Controls {
+    fn update(
+        &mut self,
+        _msg_queue: &mut VecDeque<GameMsg>,
+        _commands: &mut Commands,
+        game_ctx: GameCtxView,
+    ) -> Transition {
+        // Messages are left for the state underneath
+        let controller = game_ctx.controller;
+        if self.mode != Mode::Browse {
+            if let Some(input) = controller.pressed_input() {
+                self.capture(input);
+                game_ctx.settings.bindings.clone_from(&self.bindings);
+            } else if controller.clicked(Buttons::B) {
+                // The on screen B still backs out
+                self.mode = Mode::Browse;
+            }
+            return Transition::None;
+        }
+
+        if self.wait_release {
+            self.wait_release = controller.button_state() != ButtonState::default();
+            return Transition::None;
+        }
+        if controller.clicked(Buttons::B) {
+            return Transition::Pop;
+        }
+        if controller.clicked(Buttons::A) {
+            self.mode = Mode::Waiting;
+            return Transition::None;
+        }
+        if controller.clicked(Buttons::X) {
+            return Transition::Push(LayoutEditor::boxed_new(game_ctx.settings.layout));
+        }
+
+        let dy = controller.timed_hold().dpad_y;
+        self.selected = self
+            .selected
+            .saturating_add_signed((-dy).try_into().unwrap())
+            .min(ButtonKind::ALL.len() - 1);
+
+        Transition::None
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn render_ui_layer(&self, _render_ctx: RenderCtxWithViewport) -> Option<()> {
+        let view_rect = RenderContext::screen_view_rect();
+        let (x, y) = (
+            view_rect.x + view_rect.w * 0.1,
+            view_rect.y + view_rect.h * 0.1,
+        );
+        let (w, h) = (view_rect.w * 0.8, view_rect.h * 0.8);
+        draw_rectangle(x, y, w, h, Color::new(0.0, 0.0, 0.2, 0.9));
+        draw_rectangle_lines(x, y, w, h, 4.0, WHITE);
+
+        let font_size = view_rect.h / 20.0;
+        let line_height = font_size * 1.1;
+        let padding = font_size;
+        draw_text(
+            &self.prompt(),
+            x + padding,
+            y + padding * 1.5,
+            font_size,
+            YELLOW,
+        );
+        for (i, target) in ButtonKind::ALL.iter().enumerate() {
+            let color = if i == self.selected { GREEN } else { WHITE };
+            let line_y = y + padding * 1.5 + line_height * (i as f32 + 1.5);
+            draw_text(&self.row(*target), x + padding, line_y, font_size, color);
+        }
+
+        Some(())
+    }
+
+    fn render_text(&self, screen: &mut TextScreen) -> Option<()> {
+        for (i, target) in ButtonKind::ALL.iter().enumerate() {
+            let marker = if i == self.selected { '>' } else { ' ' };
+            screen.line(format!("{marker} {}", self.row(*target)));
+        }
+        screen.line(self.prompt());
+
+        Some(())
+    }
+
+    fn takes_input(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "Controls"
+    }
+}
@@ -0,0 +1,238 @@
+use super::state_machine::{Commands, GameMsg, GameState, Transition};
+use crate::dialogue::{DialogueScript, Expression};
+use crate::game::GameCtxView;
+use crate::render::{RenderContext, RenderCtxWithViewport};
+use crate::tty::TextScreen;
+
+use std::collections::VecDeque;
+
+use input_lib::Buttons;
+use macroquad::color::{BLACK, Color, GREEN, WHITE};
+use macroquad::logging::warn;
+use macroquad::math::Vec2;
+use macroquad::shapes::{draw_rectangle, draw_rectangle_lines};
+use macroquad::text::draw_text;
+use macroquad::texture::{DrawTextureParams, draw_texture_ex};
+
+const CHARS_PER_SECOND: f32 = 40.0;
+
+#[derive(Debug)]
+pub struct DialogueState {
+    script: DialogueScript,
+    page: usize,
+    line: usize,
+    /// Number of characters of the current line shown by the typewriter
+    revealed: f32,
+    choice: usize,
+}
+
+impl DialogueState {
+    pub fn boxed_new(script: DialogueScript) -> Box<Self> {
+        Box::new(Self {
+            script,
+            page: 0,
+            line: 0,
+            revealed: 0.0,
+            choice: 0,
+        })
+    }
+
+    fn current_line_len(&self) -> usize {
+        self.script.pages[self.page]
+            .lines
+            .get(self.line)
+            .map_or(0, |line| line.chars().count())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn is_line_revealed(&self) -> bool {
+        self.revealed >= self.current_line_len() as f32
+    }
+
+    fn is_last_line(&self) -> bool {
+        self.line + 1 >= self.script.pages[self.page].lines.len()
+    }
+
+    fn showing_choices(&self) -> bool {
+        self.is_last_line()
+            && self.is_line_revealed()
+            && !self.script.pages[self.page].choices.is_empty()
+    }
+
+    /// Returns false once the script has run out of pages
+    fn go_to_page(&mut self, page: Option<usize>) -> bool {
+        let Some(page) = page.filter(|page| *page < self.script.pages.len()) else {
+            return false;
+        };
+
+        self.page = page;
+        self.line = 0;
+        self.revealed = 0.0;
+        self.choice = 0;
+        true
+    }
+
+    fn expression_tint(expression: Expression) -> Color {
+        match expression {
+            Expression::Neutral => WHITE,
+            Expression::Happy => Color::new(1.0, 1.0, 0.8, 1.0),
+            Expression::Angry => Color::new(1.0, 0.7, 0.7, 1.0),
+            Expression::Sad => Color::new(0.7, 0.8, 1.0, 1.0),
+            Expression::Surprised => Color::new(1.0, 0.9, 0.7, 1.0),
+        }
+    }
+}
+
+impl GameState for DialogueState {
+    #[allow(clippy::cast_precision_loss)]
+    fn update(
+        &mut self,
+        msg_queue: &mut VecDeque<GameMsg>,
+        _commands: &mut Commands,
+        game_ctx: GameCtxView,
+    ) -> Transition {
+        if let Some(msg) = msg_queue.pop_front() {
+            warn!("{} state should not receive msg: {:?}", self.name(), msg);
+        }
+
+        if self.script.pages.is_empty() {
+            return Transition::Pop;
+        }
+
+        self.revealed = (self.revealed + game_ctx.frame_time * CHARS_PER_SECOND)
+            .min(self.current_line_len() as f32);
+
+        if self.showing_choices() {
+            let choice_count = self.script.pages[self.page].choices.len();
+            let dy = game_ctx.controller.timed_hold().dpad_y;
+            if dy < 0 {
+                self.choice = (self.choice + 1) % choice_count;
+            } else if dy > 0 {
+                self.choice = (self.choice + choice_count - 1) % choice_count;
+            }
+
+            if game_ctx.controller.clicked(Buttons::A) {
+                let goto = self.script.pages[self.page].choices[self.choice].goto;
+                if !self.go_to_page(goto) {
+                    return Transition::Pop;
+                }
+            }
+            return Transition::None;
+        }
+
+        if !game_ctx.controller.clicked(Buttons::A) {
+            return Transition::None;
+        }
+
+        if !self.is_line_revealed() {
+            self.revealed = self.current_line_len() as f32;
+        } else if !self.is_last_line() {
+            self.line += 1;
+            self.revealed = 0.0;
+        } else if !self.go_to_page(self.script.next_page(self.page)) {
+            return Transition::Pop;
+        }
+
+        Transition::None
+    }
+
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn render_ui_layer(&self, render_ctx: RenderCtxWithViewport) -> Option<()> {
+        let Some(page) = self.script.pages.get(self.page) else {
+            return Some(());
+        };
+
+        let view_rect = RenderContext::screen_view_rect();
+        let font_size = view_rect.h / 20.0;
+        let (x, w) = (view_rect.x + view_rect.w * 0.05, view_rect.w * 0.9);
+        let (y, h) = (view_rect.y + view_rect.h * 0.7, view_rect.h * 0.25);
+
+        if let Some(path) = &page.portrait {
+            let portrait = render_ctx.texture_store.get(path);
+            let size = view_rect.h * 0.3;
+            let params = DrawTextureParams {
+                dest_size: Some(Vec2::splat(size)),
+                ..Default::default()
+            };
+            let tint = Self::expression_tint(page.expression);
+            draw_texture_ex(&portrait, x, y - size, tint, params);
+        }
+
+        draw_rectangle(x, y, w, h, Color::new(0.0, 0.0, 0.2, 0.85));
+        draw_rectangle_lines(x, y, w, h, 4.0, WHITE);
+        draw_text(
+            &page.speaker,
+            x + font_size,
+            y + font_size,
+            font_size,
+            GREEN,
+        );
+
+        let line_height = font_size * 1.1;
+        let mut text_y = y + font_size + line_height;
+        for line in &page.lines[..self.line] {
+            draw_text(line, x + font_size, text_y, font_size, WHITE);
+            text_y += line_height;
+        }
+        if let Some(line) = page.lines.get(self.line) {
+            let current: String = line.chars().take(self.revealed as usize).collect();
+            draw_text(&current, x + font_size, text_y, font_size, WHITE);
+        }
+
+        if self.showing_choices() {
+            let choice_w = view_rect.w * 0.35;
+            let choice_x = x + w - choice_w;
+            let choice_y = y - line_height * page.choices.len() as f32;
+            for (i, choice) in page.choices.iter().enumerate() {
+                let item_y = choice_y + i as f32 * line_height;
+                let color = if i == self.choice { GREEN } else { WHITE };
+                draw_rectangle(choice_x, item_y, choice_w, line_height, color);
+                draw_text(
+                    &choice.text,
+                    choice_x + font_size * 0.5,
+                    item_y + font_size,
+                    font_size,
+                    BLACK,
+                );
+            }
+        }
+
+        Some(())
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn render_text(&self, screen: &mut TextScreen) -> Option<()> {
+        let Some(page) = self.script.pages.get(self.page) else {
+            return Some(());
+        };
+
+        screen.line(format!("{}:", page.speaker));
+        for line in &page.lines[..self.line] {
+            screen.line(format!("  {line}"));
+        }
+        if let Some(line) = page.lines.get(self.line) {
+            let current: String = line.chars().take(self.revealed as usize).collect();
+            screen.line(format!("  {current}"));
+        }
+        if self.showing_choices() {
+            for (i, choice) in page.choices.iter().enumerate() {
+                let marker = if i == self.choice { '>' } else { ' ' };
+                screen.line(format!("{marker} {}", choice.text));
+            }
+        }
+
+        Some(())
+    }
+
+    fn takes_input(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "Dialogue"
+    }
+}
@@ -0,0 +1,89 @@
+use super::player::PlayerSelect;
+use super::state_machine::{Commands, GameMsg, GameState, Transition};
+use crate::game::GameCtxView;
+use crate::render::{RenderContext, RenderCtxWithViewport};
+use crate::tty::TextScreen;
+use crate::world::Faction;
+
+use std::collections::VecDeque;
+
+use input_lib::Buttons;
+use macroquad::color::{Color, GREEN, WHITE};
+use macroquad::shapes::draw_rectangle;
+use macroquad::text::{draw_text, get_text_center};
+
+/// Covers the battle between the phases of a hot-seat battle until the next player has
+/// the device
+#[derive(Debug)]
+pub struct PassDevice {
+    faction: Faction,
+}
+
+impl PassDevice {
+    pub fn boxed_new(faction: Faction) -> Box<Self> {
+        Box::new(Self { faction })
+    }
+
+    fn title(&self) -> String {
+        format!("{:?} Phase", self.faction)
+    }
+}
+
+impl GameState for PassDevice {
+    fn update(
+        &mut self,
+        msg_queue: &mut VecDeque<GameMsg>,
+        _commands: &mut Commands,
+        game_ctx: GameCtxView,
+    ) -> Transition {
+        // Nothing to keep up to date, the next state reads the world fresh
+        msg_queue.clear();
+
+        if game_ctx.controller.clicked(Buttons::A) {
+            return Transition::Switch(PlayerSelect::boxed_new(game_ctx.world, self.faction));
+        }
+
+        Transition::None
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn render_ui_layer(&self, _render_ctx: RenderCtxWithViewport) -> Option<()> {
+        let view_rect = RenderContext::screen_view_rect();
+        draw_rectangle(
+            view_rect.x,
+            view_rect.y,
+            view_rect.w,
+            view_rect.h,
+            Color::new(0.0, 0.0, 0.1, 0.95),
+        );
+
+        let font_size = view_rect.h / 10.0;
+        let title = self.title();
+        let center = get_text_center(&title, None, font_size as u16, 1.0, 0.0);
+        let y = view_rect.y + view_rect.h * 0.45;
+        draw_text(&title, view_rect.center().x - center.x, y, font_size, GREEN);
+
+        let hint = "Pass the device, then press A";
+        let hint_size = font_size / 2.0;
+        let center = get_text_center(hint, None, hint_size as u16, 1.0, 0.0);
+        let y = y + font_size;
+        draw_text(hint, view_rect.center().x - center.x, y, hint_size, WHITE);
+
+        Some(())
+    }
+
+    fn render_text(&self, screen: &mut TextScreen) -> Option<()> {
+        screen.line(self.title());
+        screen.line("Pass the device, then press A");
+
+        Some(())
+    }
+
+    fn takes_input(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "Pass Device"
+    }
+}
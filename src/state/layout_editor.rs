@@ -0,0 +1,269 @@
+use super::state_machine::{Commands, GameMsg, GameState, Transition};
+use crate::game::GameCtxView;
+use crate::render::RenderCtxWithViewport;
+use crate::tty::TextScreen;
+
+use std::collections::VecDeque;
+
+use input_lib::{Buttons, Cluster, Gesture, Layout, MIN_OPACITY};
+use macroquad::camera::set_default_camera;
+use macroquad::color::{Color, GREEN, WHITE, YELLOW};
+use macroquad::math::{Rect, Vec2};
+use macroquad::shapes::{draw_rectangle, draw_rectangle_lines};
+use macroquad::text::draw_text;
+use macroquad::window::{screen_height, screen_width};
+
+/// How far one d-pad step moves a cluster, as a fraction of the screen
+const NUDGE: f32 = 0.01;
+const SCALE_STEP: f32 = 0.1;
+const OPACITY_STEP: f32 = 0.2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum PanelItem {
+    Mirror,
+    Opacity,
+    AutoHide,
+    Done,
+    Cancel,
+}
+
+impl PanelItem {
+    const ALL: [Self; 5] = [
+        Self::Mirror,
+        Self::Opacity,
+        Self::AutoHide,
+        Self::Done,
+        Self::Cancel,
+    ];
+
+    fn label(self, layout: &Layout) -> String {
+        let on_off = |on| if on { "on" } else { "off" };
+        match self {
+            Self::Mirror => format!("Left handed: {}", on_off(layout.left_handed)),
+            Self::Opacity => format!("Opacity: {:.0}%", layout.opacity * 100.0),
+            Self::AutoHide => format!("Hide with keys: {}", on_off(layout.auto_hide)),
+            Self::Done => "Done".to_string(),
+            Self::Cancel => "Cancel".to_string(),
+        }
+    }
+
+    /// Where the item sits on a screen of this size, in pixels
+    #[allow(clippy::cast_precision_loss)]
+    fn rect(self, screen: Vec2) -> Rect {
+        let index = Self::ALL.iter().position(|item| *item == self).unwrap_or(0);
+        let (w, h) = (screen.x * 0.3, screen.y * 0.07);
+        Rect::new(
+            (screen.x - w) / 2.0,
+            screen.y * 0.15 + h * 1.2 * index as f32,
+            w,
+            h,
+        )
+    }
+}
+
+/// Moves and resizes the on screen buttons. Fingers drag the selected cluster, tapping a cluster
+/// selects it and pinching resizes the buttons. Every change shows right away through the
+/// settings, which are only saved once the editor closes.
+#[derive(Debug)]
+pub struct LayoutEditor {
+    /// Put back on cancel
+    original: Layout,
+    layout: Layout,
+    selected: Cluster,
+}
+
+impl LayoutEditor {
+    pub fn boxed_new(layout: Layout) -> Box<Self> {
+        Box::new(Self {
+            original: layout,
+            layout,
+            selected: Cluster::DPad,
+        })
+    }
+
+    fn cycle(&mut self, step: isize) {
+        let index = Cluster::ALL
+            .iter()
+            .position(|cluster| *cluster == self.selected)
+            .unwrap_or(0);
+        let len = Cluster::ALL.len();
+        self.selected = Cluster::ALL[(index + len).wrapping_add_signed(step) % len];
+    }
+
+    fn cycle_opacity(&mut self) {
+        let opacity = if self.layout.opacity <= MIN_OPACITY + f32::EPSILON {
+            1.0
+        } else {
+            self.layout.opacity - OPACITY_STEP
+        };
+        self.layout.set_opacity(opacity);
+    }
+
+    /// Returns whether the editor closes
+    fn choose(&mut self, item: PanelItem) -> bool {
+        match item {
+            PanelItem::Mirror => self.layout.left_handed = !self.layout.left_handed,
+            PanelItem::Opacity => self.cycle_opacity(),
+            PanelItem::AutoHide => self.layout.auto_hide = !self.layout.auto_hide,
+            PanelItem::Done => return true,
+            PanelItem::Cancel => {
+                self.layout = self.original;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns whether the editor closes
+    fn apply_gesture(&mut self, gesture: Gesture, screen: Vec2) -> bool {
+        match gesture {
+            Gesture::Tap(pos) => {
+                if let Some(item) = PanelItem::ALL
+                    .into_iter()
+                    .find(|item| item.rect(screen).contains(pos))
+                {
+                    return self.choose(item);
+                }
+                if let Some(cluster) = self.layout.cluster_at(pos, screen) {
+                    self.selected = cluster;
+                }
+            }
+            Gesture::Drag(delta) => {
+                if screen.x > 0.0 && screen.y > 0.0 {
+                    self.layout.move_cluster(self.selected, delta / screen);
+                }
+            }
+            Gesture::Pinch { scale, .. } => self.layout.set_scale(self.layout.scale * scale),
+        }
+        false
+    }
+
+    fn prompt(&self) -> String {
+        format!(
+            "{} selected, L/R picks another, X/Y resize",
+            self.selected.name()
+        )
+    }
+}
+
+impl GameState for LayoutEditor {
+    fn on_enter(&self, game_ctx: GameCtxView) {
+        game_ctx.settings.editing_layout = true;
+    }
+
+    fn update(
+        &mut self,
+        _msg_queue: &mut VecDeque<GameMsg>,
+        _commands: &mut Commands,
+        game_ctx: GameCtxView,
+    ) -> Transition {
+        let controller = game_ctx.controller;
+        let screen = controller.screen_size();
+
+        let mut done = false;
+        for gesture in game_ctx.gestures {
+            done |= self.apply_gesture(*gesture, screen);
+        }
+        if controller.clicked(Buttons::A) {
+            done |= self.choose(PanelItem::Done);
+        } else if controller.clicked(Buttons::B) {
+            done |= self.choose(PanelItem::Cancel);
+        }
+        if done {
+            game_ctx.settings.layout = self.layout;
+            game_ctx.settings.editing_layout = false;
+            return Transition::Pop;
+        }
+
+        if controller.clicked(Buttons::L) {
+            self.cycle(-1);
+        } else if controller.clicked(Buttons::R) {
+            self.cycle(1);
+        }
+        if controller.clicked(Buttons::X) {
+            self.layout.set_scale(self.layout.scale + SCALE_STEP);
+        } else if controller.clicked(Buttons::Y) {
+            self.layout.set_scale(self.layout.scale - SCALE_STEP);
+        }
+        if controller.clicked(Buttons::Select) {
+            self.choose(PanelItem::Mirror);
+        }
+        if controller.clicked(Buttons::Start) {
+            self.choose(PanelItem::Opacity);
+        }
+
+        let step = controller.timed_hold();
+        #[allow(clippy::cast_precision_loss)]
+        let nudge = Vec2::new(step.dpad_x as f32, -step.dpad_y as f32) * NUDGE;
+        if nudge != Vec2::ZERO {
+            self.layout.move_cluster(self.selected, nudge);
+        }
+
+        game_ctx.settings.layout = self.layout;
+        Transition::None
+    }
+
+    fn render_ui_layer(&self, _render_ctx: RenderCtxWithViewport) -> Option<()> {
+        // The buttons are laid out in screen pixels, so the editor draws in them too
+        set_default_camera();
+        let screen = Vec2::new(screen_width(), screen_height());
+        draw_rectangle(0.0, 0.0, screen.x, screen.y, Color::new(0.0, 0.0, 0.0, 0.5));
+
+        let selected = self.layout.cluster_rect(self.selected, screen);
+        draw_rectangle_lines(
+            selected.x - 8.0,
+            selected.y - 8.0,
+            selected.w + 16.0,
+            selected.h + 16.0,
+            4.0,
+            GREEN,
+        );
+
+        let font_size = screen.y / 25.0;
+        draw_text(
+            &self.prompt(),
+            screen.x * 0.05,
+            screen.y * 0.08,
+            font_size,
+            YELLOW,
+        );
+        for item in PanelItem::ALL {
+            let rect = item.rect(screen);
+            draw_rectangle(
+                rect.x,
+                rect.y,
+                rect.w,
+                rect.h,
+                Color::new(0.0, 0.0, 0.2, 0.9),
+            );
+            draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, WHITE);
+            draw_text(
+                &item.label(&self.layout),
+                rect.x + font_size / 2.0,
+                rect.y + rect.h / 2.0 + font_size / 3.0,
+                font_size,
+                WHITE,
+            );
+        }
+
+        Some(())
+    }
+
+    fn render_text(&self, screen: &mut TextScreen) -> Option<()> {
+        screen.line(self.prompt());
+        for item in PanelItem::ALL {
+            screen.line(item.label(&self.layout));
+        }
+        screen.line(format!("Size: {:.0}%", self.layout.scale * 100.0));
+
+        Some(())
+    }
+
+    fn takes_input(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "Layout Editor"
+    }
+}
@@ -0,0 +1,273 @@
+use super::controls::Controls;
+use super::state_machine::{Commands, GameMsg, GameState, Transition};
+use crate::action;
+use crate::command::Command;
+use crate::game::GameCtxView;
+use crate::render::{RenderContext, RenderCtxWithViewport};
+use crate::tty::TextScreen;
+use crate::ui::{Menu, MenuItem};
+use crate::unit::Unit;
+use crate::world::{Faction, WorldState};
+
+use std::collections::VecDeque;
+
+use input_lib::Buttons;
+use macroquad::color::{Color, RED, WHITE};
+use macroquad::shapes::{draw_rectangle, draw_rectangle_lines};
+use macroquad::text::{draw_text, get_text_center};
+
+/// Where Suspend saves the battle, `--resume` picks it up again
+pub const SUSPEND_FILE: &str = "suspend.json";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum MapMenuItem {
+    EndTurn,
+    Objectives,
+    UnitList,
+    Suspend,
+    Options,
+    Retire,
+}
+
+impl MenuItem for MapMenuItem {
+    fn menu_label(&self) -> &str {
+        match self {
+            Self::EndTurn => "End Turn",
+            Self::Objectives => "Objectives",
+            Self::UnitList => "Unit List",
+            Self::Suspend => "Suspend",
+            Self::Options => "Options",
+            Self::Retire => "Retire",
+        }
+    }
+}
+
+/// Opened with Start during the player's phase
+#[derive(Debug)]
+pub struct MapMenu {
+    faction: Faction,
+    menu: Menu<MapMenuItem>,
+    /// Lines shown next to the menu until A or B closes them
+    panel: Option<Vec<String>>,
+    /// Retiring needs a second press of A
+    confirming_retire: bool,
+}
+
+impl MapMenu {
+    pub fn boxed_new(faction: Faction) -> Box<Self> {
+        Box::new(Self {
+            faction,
+            menu: Menu::new(&[
+                MapMenuItem::EndTurn,
+                MapMenuItem::Objectives,
+                MapMenuItem::UnitList,
+                MapMenuItem::Suspend,
+                MapMenuItem::Options,
+                MapMenuItem::Retire,
+            ]),
+            panel: None,
+            confirming_retire: false,
+        })
+    }
+
+    fn objectives(world: &WorldState) -> Vec<String> {
+        vec![
+            world.objective.description(),
+            format!("Turn {}", world.turn),
+        ]
+    }
+
+    fn unit_list(&self, world: &WorldState) -> Vec<String> {
+        let mut units: Vec<&Unit> = world
+            .units()
+            .filter(|unit| unit.faction == self.faction)
+            .collect();
+        units.sort_by_key(|unit| *unit.id());
+        units
+            .iter()
+            .map(|unit| {
+                let done = if unit.turn_complete { "done" } else { "ready" };
+                format!(
+                    "{} Lv{} HP {}/{} ({}, {}) {done}",
+                    unit.class().name(),
+                    unit.level,
+                    unit.curr_health,
+                    unit.max_health(),
+                    unit.pos.x,
+                    unit.pos.y,
+                )
+            })
+            .collect()
+    }
+
+    fn suspend(world: &WorldState) -> Vec<String> {
+        match std::fs::write(SUSPEND_FILE, world.snapshot().to_json()) {
+            Ok(()) => vec![format!("Saved to {SUSPEND_FILE}")],
+            Err(err) => vec![format!("Could not save: {err}")],
+        }
+    }
+
+    fn end_turn(&self, commands: &mut Commands, world: &WorldState) {
+        for unit in action::controllable_units(world, self.faction) {
+            let mut unit = unit.clone();
+            unit.turn_complete = true;
+            commands.add(Command::CommitUnit(unit));
+        }
+    }
+}
+
+impl GameState for MapMenu {
+    fn update(
+        &mut self,
+        _msg_queue: &mut VecDeque<GameMsg>,
+        commands: &mut Commands,
+        game_ctx: GameCtxView,
+    ) -> Transition {
+        // Messages are left for the state underneath
+        let controller = game_ctx.controller;
+        if self.panel.is_some() {
+            if self.confirming_retire && controller.clicked(Buttons::A) {
+                return Transition::Switch(Retired::boxed_new());
+            }
+            if controller.clicked(Buttons::A) || controller.clicked(Buttons::B) {
+                self.panel = None;
+                self.confirming_retire = false;
+            }
+            return Transition::None;
+        }
+
+        if controller.clicked(Buttons::B) || controller.clicked(Buttons::Start) {
+            return Transition::Pop;
+        }
+        self.menu.update(controller);
+        if !controller.clicked(Buttons::A) {
+            return Transition::None;
+        }
+
+        let world = game_ctx.world;
+        self.panel = Some(match self.menu.selected() {
+            MapMenuItem::EndTurn => {
+                // The phase ends once the select state sees no unit left to move
+                self.end_turn(commands, world);
+                return Transition::Pop;
+            }
+            MapMenuItem::Objectives => Self::objectives(world),
+            MapMenuItem::UnitList => self.unit_list(world),
+            MapMenuItem::Suspend => Self::suspend(world),
+            MapMenuItem::Options => {
+                return Transition::Push(Controls::boxed_new(&game_ctx.settings.bindings));
+            }
+            MapMenuItem::Retire => {
+                self.confirming_retire = true;
+                vec![
+                    "Give up the battle?".to_string(),
+                    "A retires, B goes back".to_string(),
+                ]
+            }
+        });
+
+        Transition::None
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn render_ui_layer(&self, _render_ctx: RenderCtxWithViewport) -> Option<()> {
+        self.menu.render();
+
+        let Some(panel) = &self.panel else {
+            return Some(());
+        };
+        let view_rect = RenderContext::screen_view_rect();
+        let font_size = view_rect.h / 20.0;
+        let line_height = font_size * 1.2;
+        let (x, y) = (
+            view_rect.x + view_rect.w * 0.05,
+            view_rect.y + view_rect.h * 0.2,
+        );
+        let (w, h) = (view_rect.w * 0.6, line_height * (panel.len() as f32 + 1.0));
+        draw_rectangle(x, y, w, h, Color::new(0.0, 0.0, 0.2, 0.9));
+        draw_rectangle_lines(x, y, w, h, 4.0, WHITE);
+        for (i, line) in panel.iter().enumerate() {
+            let line_y = y + line_height * (i as f32 + 1.0);
+            draw_text(line, x + font_size / 2.0, line_y, font_size, WHITE);
+        }
+
+        Some(())
+    }
+
+    fn render_text(&self, screen: &mut TextScreen) -> Option<()> {
+        match &self.panel {
+            Some(panel) => panel.iter().for_each(|line| screen.line(line.clone())),
+            None => self.menu.render_text(screen),
+        }
+
+        Some(())
+    }
+
+    fn takes_input(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "Map Menu"
+    }
+}
+
+/// The player gave up, nothing is played any more
+#[derive(Debug)]
+pub struct Retired;
+
+impl Retired {
+    pub fn boxed_new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+impl GameState for Retired {
+    fn update(
+        &mut self,
+        msg_queue: &mut VecDeque<GameMsg>,
+        _commands: &mut Commands,
+        _game_ctx: GameCtxView,
+    ) -> Transition {
+        msg_queue.clear();
+        Transition::None
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn render_ui_layer(&self, _render_ctx: RenderCtxWithViewport) -> Option<()> {
+        let view_rect = RenderContext::screen_view_rect();
+        draw_rectangle(
+            view_rect.x,
+            view_rect.y,
+            view_rect.w,
+            view_rect.h,
+            Color::new(0.1, 0.0, 0.0, 0.9),
+        );
+
+        let text = "Retired";
+        let font_size = view_rect.h / 8.0;
+        let center = get_text_center(text, None, font_size as u16, 1.0, 0.0);
+        let (x, y) = (
+            view_rect.center().x - center.x,
+            view_rect.center().y - center.y,
+        );
+        draw_text(text, x, y, font_size, RED);
+
+        Some(())
+    }
+
+    fn render_text(&self, screen: &mut TextScreen) -> Option<()> {
+        screen.line("Retired from the battle");
+
+        Some(())
+    }
+
+    /// Keeps the screen up until the player closes the game
+    fn takes_input(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "Retired"
+    }
+}
@@ -0,0 +1,68 @@
+use super::phase_state;
+use super::simulated::SimulatedManager;
+use super::state_machine::{Commands, GameMsg, GameState, Transition};
+use crate::command::Command;
+use crate::game::GameCtxView;
+use crate::tty::TextScreen;
+use crate::world::Faction;
+
+use macroquad::logging::warn;
+
+use std::collections::VecDeque;
+
+/// Replays the commands the other side of an online battle sent for its phase, see
+/// [`crate::net`]
+#[derive(Debug)]
+pub struct NetOpponent {
+    faction: Faction,
+}
+
+impl NetOpponent {
+    pub fn boxed_new(faction: Faction) -> Box<Self> {
+        Box::new(Self { faction })
+    }
+}
+
+impl GameState for NetOpponent {
+    fn update(
+        &mut self,
+        msg_queue: &mut VecDeque<GameMsg>,
+        commands: &mut Commands,
+        mut game_ctx: GameCtxView,
+    ) -> Transition {
+        // The world is read fresh once the phase is over
+        msg_queue.clear();
+
+        let Some(net) = game_ctx.net.as_deref_mut() else {
+            warn!("The online battle lost its session, the AI takes over");
+            return Transition::Switch(Box::new(SimulatedManager::new(self.faction)));
+        };
+        let Some(batch) = net.next_commands(game_ctx.world) else {
+            return Transition::None;
+        };
+
+        let mut next_phase = None;
+        for command in batch {
+            match &command {
+                Command::CommitUnit(unit) => game_ctx.viewport.set_center_on(unit.pos),
+                Command::SetupTurn(faction) => next_phase = Some(*faction),
+                _ => {}
+            }
+            commands.add(command);
+        }
+        match next_phase {
+            Some(faction) => Transition::Switch(phase_state(faction, &game_ctx)),
+            None => Transition::None,
+        }
+    }
+
+    fn render_text(&self, screen: &mut TextScreen) -> Option<()> {
+        screen.line(format!("Waiting for the {:?} player", self.faction));
+
+        Some(())
+    }
+
+    fn name(&self) -> &'static str {
+        "Net Opponent"
+    }
+}
@@ -1,20 +1,27 @@
 use super::animation::MoveAnimation;
-use super::simulated::SimulatedManager;
-use super::state_machine::{Command, Commands, GameMsg, GameState, Transition};
-use crate::assets::TextureStore;
+use super::map_menu::MapMenu;
+use super::phase_state;
+use super::simulated::MoveSimulated;
+use super::state_machine::{Commands, GameMsg, GameState, Transition};
+use super::unit_info::UnitInfo;
+use crate::combat;
+use crate::command::Command;
 use crate::cursor::Cursor;
 use crate::game::GameCtxView;
+use crate::map::Map;
 use crate::math::Point;
-use crate::pathfinding::{DijkstraMap, get_manahattan_neighbours};
+use crate::pathfinding::DijkstraMap;
 use crate::render::RenderCtxWithViewport;
+use crate::status::StatusKind;
+use crate::tty::{TextColor, TextScreen};
 use crate::ui::{Menu, MenuItem};
-use crate::unit::{Unit, UnitId};
+use crate::unit::{Item, Unit, UnitId};
 use crate::world::{Faction, WorldState};
 
 use std::collections::{HashMap, HashSet, VecDeque};
 
 use input_lib::Buttons;
-use macroquad::color::{BLUE, Color, RED, WHITE};
+use macroquad::color::{BLUE, Color, RED, YELLOW};
 use macroquad::logging::info;
 use macroquad::logging::warn;
 
@@ -22,8 +29,10 @@ const MARKER_SCALE: f32 = 0.95;
 
 #[derive(Debug)]
 pub struct PlayerSelect {
-    player_units: HashMap<Point, UnitId>,
-    enemy_units: HashMap<Point, UnitId>,
+    /// The human faction whose phase this is
+    faction: Faction,
+    own_units: HashMap<Point, UnitId>,
+    hostile_units: HashMap<Point, UnitId>,
     cursor: Cursor,
 }
 
@@ -32,12 +41,15 @@ struct PlayerMove {
     unit: Unit,
     dijkstra_map: DijkstraMap,
     targetables: Vec<Point>,
+    /// Path drawn by the cursor, from the unit to the destination
+    path: Vec<Point>,
     cursor: Cursor,
 }
 
 #[derive(Debug)]
 struct PlayerAction {
     targetables: HashSet<Point>,
+    talk_partner: Option<UnitId>,
     unit: Unit,
     menu: Menu<PossibleActions>,
     cursor: Cursor,
@@ -52,38 +64,53 @@ struct PlayerAttack {
 }
 
 impl PlayerSelect {
-    pub fn boxed_new(world: &WorldState, texture_store: &TextureStore) -> Box<Self> {
+    pub fn boxed_new(world: &WorldState, faction: Faction) -> Box<Self> {
         let pt = world
-            .units
-            .iter()
-            .find(|(_, unit)| unit.faction == Faction::Player)
-            .map_or(Point::zero(), |(_, unit)| unit.pos);
+            .units()
+            .filter(|unit| unit.faction == faction)
+            .min_by_key(|unit| *unit.id())
+            .map_or(Point::zero(), |unit| unit.pos);
 
         let mut state = Self {
-            player_units: HashMap::new(),
-            enemy_units: HashMap::with_capacity(10),
-            cursor: Cursor::new(pt, texture_store.get("cursor.png")),
+            faction,
+            own_units: HashMap::new(),
+            hostile_units: HashMap::with_capacity(10),
+            cursor: Cursor::new(pt),
         };
         state.update_data(world);
 
         Box::new(state)
     }
+    /// Position of the next unmoved unit after the one under the cursor, or the previous one
+    /// for a negative `step`, in id order and wrapping around
+    fn cycle_unit(&self, step: i32, world: &WorldState) -> Option<Point> {
+        let mut ids: Vec<UnitId> = self.own_units.values().copied().collect();
+        ids.sort_unstable_by_key(|id| **id);
+        let current = world.unit_at(self.cursor.get_pos()).map(|unit| *unit.id());
+        let next = if step > 0 {
+            let after = current.and_then(|current| ids.iter().find(|id| ***id > current));
+            after.or_else(|| ids.first())
+        } else {
+            let before = current.and_then(|current| ids.iter().rev().find(|id| ***id < current));
+            before.or_else(|| ids.last())
+        };
+        Some(world.get_unit(*next?)?.pos)
+    }
     fn update_data(&mut self, world: &WorldState) {
-        self.player_units.clear();
-        self.player_units = world
-            .units
-            .iter()
-            .filter(|(_, unit)| unit.faction == Faction::Player)
-            .filter(|(_, unit)| !unit.turn_complete)
-            .map(|(id, unit)| (unit.pos, *id))
+        self.own_units.clear();
+        self.own_units = world
+            .units()
+            .filter(|unit| unit.faction == self.faction)
+            .filter(|unit| !unit.turn_complete)
+            .filter(|unit| !unit.has_status(StatusKind::Berserk))
+            .map(|unit| (unit.pos, unit.id()))
             .collect::<HashMap<Point, UnitId>>();
 
-        self.enemy_units.clear();
-        self.enemy_units = world
-            .units
-            .iter()
-            .filter(|(_, unit)| unit.faction == Faction::Enemy)
-            .map(|(id, unit)| (unit.pos, *id))
+        self.hostile_units.clear();
+        self.hostile_units = world
+            .units()
+            .filter(|unit| unit.faction != self.faction)
+            .map(|unit| (unit.pos, unit.id()))
             .collect();
     }
 }
@@ -115,25 +142,58 @@ impl GameState for PlayerSelect {
             return Transition::None;
         }
 
-        self.cursor
-            .update(game_ctx.controller, &game_ctx.world.map.get_bounds());
+        self.cursor.update(game_ctx.controller, &game_ctx.world.map);
+        let tapped = self.cursor.tap(game_ctx.tap);
         game_ctx.viewport.set_follow(self.cursor.get_pos());
 
-        if game_ctx.world.get_unmoved_unit(Faction::Player).is_none() {
-            commands.add(Command::SetupTurn);
-            return Transition::Switch(Box::new(SimulatedManager::new(Faction::Enemy)));
+        if game_ctx.world.get_unmoved_unit(self.faction).is_none() {
+            let next = self.faction.opponent();
+            commands.add(Command::SetupTurn(next));
+            return Transition::Switch(phase_state(next, &game_ctx));
+        }
+
+        // Berserk units are out of the player's control
+        if let Some(unit) = game_ctx.world.units().find(|unit| {
+            unit.faction == self.faction
+                && !unit.turn_complete
+                && unit.has_status(StatusKind::Berserk)
+        }) {
+            let dijkstra_map = DijkstraMap::new(game_ctx.world, unit);
+            game_ctx.viewport.set_center_on(unit.pos);
+            return Transition::Push(MoveSimulated::boxed_new(unit.clone(), dijkstra_map));
+        }
+
+        let step = i32::from(game_ctx.controller.clicked(Buttons::R))
+            - i32::from(game_ctx.controller.clicked(Buttons::L));
+        if step != 0
+            && let Some(pos) = self.cycle_unit(step, game_ctx.world)
+        {
+            self.cursor.set_pos(pos);
+            game_ctx.viewport.set_center_on(pos);
+            return Transition::None;
+        }
+
+        if game_ctx.controller.clicked(Buttons::Start) {
+            return Transition::Push(MapMenu::boxed_new(self.faction));
+        }
+
+        if game_ctx.controller.clicked(Buttons::X)
+            && let Some(unit) = game_ctx.world.unit_at(self.cursor.get_pos())
+        {
+            return Transition::Push(UnitInfo::boxed_new(unit.clone()));
         }
 
         // TODO Show enemy range
-        if game_ctx.controller.clicked(Buttons::A)
-            && let Some(unit_id) = self.player_units.get(&self.cursor.get_pos())
+        if (game_ctx.controller.clicked(Buttons::A) || tapped)
+            && let Some(unit_id) = self.own_units.get(&self.cursor.get_pos())
         {
-            let unit = game_ctx.world.units.get(unit_id).unwrap();
-            let dijkstra_map = DijkstraMap::new(&game_ctx.world.map, unit, &game_ctx.world.units);
+            let unit = game_ctx.world.get_unit(*unit_id).unwrap();
+            let dijkstra_map = DijkstraMap::new(game_ctx.world, unit);
             return Transition::Push(PlayerMove::boxed_new(
                 unit.clone(),
                 dijkstra_map,
                 self.cursor.clone(),
+                game_ctx.world,
             ));
         }
 
@@ -141,47 +201,100 @@ impl GameState for PlayerSelect {
     }
 
     fn render_ui_layer(&self, render_ctx: RenderCtxWithViewport<'_>) -> Option<()> {
-        render_ctx.render_sprite(
-            self.cursor.get_render_pos(),
-            &self.cursor.texture,
-            WHITE,
-            1.2,
-        );
+        render_ctx.render_cursor(&self.cursor, 1.2);
 
         Some(())
     }
 
+    fn render_text(&self, screen: &mut TextScreen) -> Option<()> {
+        screen.set_cursor(self.cursor.get_pos());
+
+        Some(())
+    }
+
+    fn takes_input(&self) -> bool {
+        true
+    }
+
     fn name(&self) -> &'static str {
         "Player Select"
     }
 }
 
 impl PlayerMove {
-    pub fn boxed_new(unit: Unit, dijkstra_map: DijkstraMap, cursor: Cursor) -> Box<Self> {
+    pub fn boxed_new(
+        unit: Unit,
+        dijkstra_map: DijkstraMap,
+        cursor: Cursor,
+        world: &WorldState,
+    ) -> Box<Self> {
+        let topology = dijkstra_map.topology();
         let reachables = dijkstra_map.get_reachables();
         let edge_tiles: Vec<_> = reachables
             .iter()
             .copied()
             .filter(|pt| {
-                DijkstraMap::DIRS
-                    .iter()
-                    .any(|d_point| !reachables.contains(&(*pt + *d_point)))
+                topology
+                    .neighbours(*pt)
+                    .any(|npos| !reachables.contains(&npos))
             })
             .collect();
 
         let mut targetables = HashSet::new();
         for tile in edge_tiles {
-            targetables.extend(get_manahattan_neighbours(tile, unit.get_attack_range()));
+            targetables.extend(topology.tiles_in_range(tile, unit.get_attack_range()));
         }
-        targetables.retain(|pt| !reachables.contains(pt));
+        // Units with Pass can move through enemies, those stay targets
+        let passables = dijkstra_map.get_passables();
+        targetables.retain(|pt| {
+            !reachables.contains(pt)
+                && (!passables.contains(pt)
+                    || world
+                        .unit_at(*pt)
+                        .is_some_and(|other| unit.is_hostile_to(other)))
+        });
 
         Box::new(Self {
-            unit,
             dijkstra_map,
             targetables: targetables.into_iter().collect(),
+            path: vec![unit.pos],
+            unit,
             cursor,
         })
     }
+
+    fn path_cost(&self, map: &Map) -> u32 {
+        self.path
+            .iter()
+            .skip(1)
+            .map(|pt| self.unit.get_movement_cost(map.get_terrain(*pt)))
+            .sum()
+    }
+
+    /// Follows the cursor with the path, falling back to the cheapest path when the
+    /// drawn one would go over the unit's movement
+    fn update_path(&mut self, map: &Map) {
+        let goal = self.cursor.get_pos();
+        if let Some(idx) = self.path.iter().position(|pt| *pt == goal) {
+            self.path.truncate(idx + 1);
+            return;
+        }
+        if !self.dijkstra_map.is_traversable(goal) {
+            return;
+        }
+
+        let last = *self.path.last().unwrap();
+        let cost = self.path_cost(map) + self.unit.get_movement_cost(map.get_terrain(goal));
+        if map.topology.neighbours(last).any(|pt| pt == goal)
+            && !self.dijkstra_map.stops_movement(last)
+            && cost <= self.unit.movement()
+        {
+            self.path.push(goal);
+        } else {
+            self.path = self.dijkstra_map.get_path_to(goal);
+            self.path.reverse();
+        }
+    }
 }
 impl GameState for PlayerMove {
     fn active_unit(&self) -> Option<&Unit> {
@@ -196,7 +309,11 @@ impl GameState for PlayerMove {
         if let Some(msg) = msg_queue.pop_front() {
             match msg {
                 GameMsg::MoveAnimationDone(unit) => {
-                    return Transition::Push(PlayerAction::boxed_new(unit, self.cursor.clone()));
+                    return Transition::Push(PlayerAction::boxed_new(
+                        unit,
+                        self.cursor.clone(),
+                        game_ctx.world,
+                    ));
                 }
                 _ => {
                     warn!("{} state should not receive msg: {:?}", self.name(), msg);
@@ -204,30 +321,32 @@ impl GameState for PlayerMove {
             }
         }
 
-        self.cursor
-            .update(game_ctx.controller, &game_ctx.world.map.get_bounds());
+        self.cursor.update(game_ctx.controller, &game_ctx.world.map);
+        let tapped = self.cursor.tap(game_ctx.tap);
         game_ctx.viewport.set_follow(self.cursor.get_pos());
+        self.update_path(&game_ctx.world.map);
 
         if game_ctx.controller.clicked(Buttons::B) {
             return Transition::Pop;
         }
 
-        if game_ctx.controller.clicked(Buttons::A) {
+        if game_ctx.controller.clicked(Buttons::A) || tapped {
             if self.unit.pos == self.cursor.get_pos() {
                 return Transition::Push(PlayerAction::boxed_new(
                     self.unit.clone(),
                     self.cursor.clone(),
+                    game_ctx.world,
                 ));
             }
             if self
                 .dijkstra_map
                 .get_reachables()
                 .contains(&self.cursor.get_pos())
-                && game_ctx.world.is_tile_empty(self.cursor.get_pos())
             {
+                // The animation walks the path backwards
                 return Transition::Push(MoveAnimation::boxed_new(
                     self.unit.clone(),
-                    self.dijkstra_map.get_path_to(self.cursor.get_pos()),
+                    self.path.iter().rev().copied().collect(),
                 ));
             }
         }
@@ -251,36 +370,66 @@ impl GameState for PlayerMove {
                 render_ctx.render_tile_rectangle(*pt, Color { a: 0.4, ..RED }, MARKER_SCALE);
             });
 
+        render_ctx.render_path(&self.path, Color { a: 0.8, ..YELLOW });
+
         Some(())
     }
 
     fn render_ui_layer(&self, render_ctx: RenderCtxWithViewport) -> Option<()> {
-        render_ctx.render_sprite(
-            self.cursor.get_render_pos(),
-            &self.cursor.texture,
-            WHITE,
-            1.2,
-        );
+        render_ctx.render_cursor(&self.cursor, 1.2);
 
         Some(())
     }
 
+    fn render_text(&self, screen: &mut TextScreen) -> Option<()> {
+        for pt in self.dijkstra_map.get_reachables() {
+            screen.mark(*pt, TextColor::Blue);
+        }
+        for pt in &self.targetables {
+            screen.mark(*pt, TextColor::Red);
+        }
+        for pt in &self.path {
+            screen.mark(*pt, TextColor::Yellow);
+        }
+        screen.set_cursor(self.cursor.get_pos());
+
+        Some(())
+    }
+
+    fn takes_input(&self) -> bool {
+        true
+    }
+
     fn name(&self) -> &'static str {
         "Player Move"
     }
 }
 
 impl PlayerAction {
-    pub fn boxed_new(unit: Unit, cursor: Cursor) -> Box<Self> {
+    pub fn boxed_new(unit: Unit, cursor: Cursor, world: &WorldState) -> Box<Self> {
+        let talk_partner = world
+            .units_in_range(unit.pos, 1)
+            .find(|other| world.can_talk(unit.id(), other.id()))
+            .map(Unit::id);
+
+        let mut actions = vec![PossibleActions::Attack, PossibleActions::Skill];
+        if talk_partner.is_some() {
+            actions.push(PossibleActions::Talk);
+        }
+        for item in &unit.items {
+            let action = PossibleActions::UseItem(*item);
+            if unit.can_use_item(*item) && !actions.contains(&action) {
+                actions.push(action);
+            }
+        }
+        actions.push(PossibleActions::Wait);
+
         Box::new(Self {
             targetables: HashSet::new(),
+            talk_partner,
             unit,
             cursor,
-            menu: Menu::new(&[
-                PossibleActions::Attack,
-                PossibleActions::Skill,
-                PossibleActions::Wait,
-            ]),
+            menu: Menu::new(&actions),
         })
     }
 }
@@ -314,20 +463,21 @@ impl GameState for PlayerAction {
             }
             PossibleActions::Attack => 'attack: {
                 self.targetables.clear();
-                self.targetables.extend(get_manahattan_neighbours(
-                    self.unit.pos,
-                    self.unit.get_attack_range(),
-                ));
+                self.targetables.extend(
+                    game_ctx
+                        .world
+                        .map
+                        .topology
+                        .tiles_in_range(self.unit.pos, self.unit.get_attack_range()),
+                );
                 if !game_ctx.controller.clicked(Buttons::A) {
                     break 'attack;
                 }
                 let opposing_units: Vec<(UnitId, Point)> = game_ctx
                     .world
-                    .units
-                    .iter()
-                    .filter(|(_, unit)| unit.faction == Faction::Enemy)
-                    .filter(|(_, unit)| self.targetables.contains(&unit.pos))
-                    .map(|(id, unit)| (*id, unit.pos))
+                    .units_in_range(self.unit.pos, self.unit.get_attack_range())
+                    .filter(|unit| unit.is_hostile_to(&self.unit))
+                    .map(|unit| (unit.id(), unit.pos))
                     .collect();
 
                 // TODO Attack option shouldn't be shown if this is empty
@@ -344,6 +494,32 @@ impl GameState for PlayerAction {
                 // TODO Control from render() if this should render rather than clearing it
                 self.targetables.clear();
             }
+            PossibleActions::Talk => 'talk: {
+                self.targetables.clear();
+                let Some(partner) = self.talk_partner else {
+                    break 'talk;
+                };
+                if !game_ctx.controller.clicked(Buttons::A) {
+                    break 'talk;
+                }
+                self.unit.turn_complete = true;
+                commands.add(Command::Talk(self.unit.id(), partner));
+                commands.add(Command::CommitUnit(self.unit.clone()));
+                msg_queue.push_back(GameMsg::SetCursor(self.cursor.clone()));
+                return Transition::PopAllButFirst;
+            }
+            PossibleActions::UseItem(item) => 'item: {
+                self.targetables.clear();
+                if !game_ctx.controller.clicked(Buttons::A) {
+                    break 'item;
+                }
+                self.unit.use_item(*item);
+                info!("Used {}", item.name());
+                self.unit.turn_complete = true;
+                commands.add(Command::CommitUnit(self.unit.clone()));
+                msg_queue.push_back(GameMsg::SetCursor(self.cursor.clone()));
+                return Transition::PopAllButFirst;
+            }
         }
 
         Transition::None
@@ -366,6 +542,20 @@ impl GameState for PlayerAction {
         Some(())
     }
 
+    fn render_text(&self, screen: &mut TextScreen) -> Option<()> {
+        for pt in &self.targetables {
+            screen.mark(*pt, TextColor::Red);
+        }
+        screen.set_cursor(self.cursor.get_pos());
+        self.menu.render_text(screen);
+
+        Some(())
+    }
+
+    fn takes_input(&self) -> bool {
+        true
+    }
+
     fn name(&self) -> &'static str {
         "Player Action"
     }
@@ -395,8 +585,21 @@ impl GameState for PlayerAttack {
             return Transition::Pop;
         }
         if game_ctx.controller.clicked(Buttons::A) {
+            let target_id = self.targets[self.selected].0;
+            let target = game_ctx.world.get_unit(target_id).unwrap();
             self.unit.turn_complete = true;
-            commands_buffer.add(Command::DamageUnit(self.targets[self.selected].0, 3));
+
+            let class = self.unit.class();
+            let outcome = combat::attack(&mut self.unit, target);
+            for command in outcome.commands {
+                commands_buffer.add(command);
+            }
+            if let Some(gains) = outcome.level_up {
+                info!("Level up to {}: {:?}", self.unit.level, gains);
+            }
+            if class != self.unit.class() {
+                info!("Promoted to {}", self.unit.class().name());
+            }
             commands_buffer.add(Command::CommitUnit(self.unit.clone()));
 
             self.cursor.set_pos(self.unit.pos);
@@ -416,16 +619,26 @@ impl GameState for PlayerAttack {
     }
 
     fn render_ui_layer(&self, render_ctx: RenderCtxWithViewport) -> Option<()> {
-        render_ctx.render_sprite(
-            self.cursor.get_render_pos(),
-            &self.cursor.texture,
-            WHITE,
-            1.0,
-        );
+        render_ctx.render_cursor(&self.cursor, 1.0);
+
+        Some(())
+    }
+
+    fn render_text(&self, screen: &mut TextScreen) -> Option<()> {
+        screen.set_cursor(self.cursor.get_pos());
+        screen.line(format!(
+            "Target {} of {}",
+            self.selected + 1,
+            self.targets.len()
+        ));
 
         Some(())
     }
 
+    fn takes_input(&self) -> bool {
+        true
+    }
+
     fn name(&self) -> &'static str {
         "Player Attack"
     }
@@ -435,6 +648,8 @@ impl GameState for PlayerAttack {
 enum PossibleActions {
     Attack,
     Skill,
+    Talk,
+    UseItem(Item),
     Wait,
 }
 
@@ -443,6 +658,8 @@ impl MenuItem for PossibleActions {
         match self {
             Self::Attack => "Attack",
             Self::Skill => "Skill",
+            Self::Talk => "Talk",
+            Self::UseItem(item) => item.name(),
             Self::Wait => "Wait",
         }
     }
@@ -0,0 +1,153 @@
+use super::animation::MoveAnimation;
+use super::phase_state;
+use super::simulated::{MoveSimulated, SimulatedManager};
+use super::state_machine::{Commands, GameMsg, GameState, Transition};
+use crate::action::{self, Action};
+use crate::bot::BotEvent;
+use crate::combat;
+use crate::command::Command;
+use crate::game::GameCtxView;
+use crate::pathfinding::DijkstraMap;
+use crate::render::Viewport;
+use crate::status::StatusKind;
+use crate::unit::Unit;
+use crate::world::{Faction, WorldState};
+
+use macroquad::logging::warn;
+use serde_json::json;
+
+use std::collections::VecDeque;
+
+/// Plays a faction with the actions of an external bot, see [`crate::bot`]
+#[derive(Debug)]
+pub struct RemoteManager {
+    faction: Faction,
+    /// Finished once the unit has walked to its destination
+    pending: Option<Action>,
+}
+
+impl RemoteManager {
+    pub fn boxed_new(faction: Faction) -> Box<Self> {
+        Box::new(Self {
+            faction,
+            pending: None,
+        })
+    }
+
+    /// Attacks if the action says so and ends the unit's turn
+    fn finish(&mut self, mut unit: Unit, commands: &mut Commands, world: &WorldState) {
+        if let Some(Action::Attack { target, .. }) = self.pending.take()
+            && let Some(target) = world.get_unit(target)
+        {
+            for command in combat::attack(&mut unit, target).commands {
+                commands.add(command);
+            }
+        }
+        unit.turn_complete = true;
+        commands.add(Command::CommitUnit(unit));
+    }
+
+    fn play(
+        &mut self,
+        action: Action,
+        commands: &mut Commands,
+        world: &WorldState,
+        viewport: &mut Viewport,
+    ) -> Transition {
+        let (unit, to) = match action {
+            Action::EndTurn => {
+                for unit in action::controllable_units(world, self.faction) {
+                    let mut unit = unit.clone();
+                    unit.turn_complete = true;
+                    commands.add(Command::CommitUnit(unit));
+                }
+                return Transition::None;
+            }
+            Action::Wait { unit } => (unit, None),
+            Action::Move { unit, to } | Action::Attack { unit, to, .. } => (unit, Some(to)),
+        };
+        // Checked to be legal when the bot sent it
+        let unit = world.get_unit(unit).unwrap();
+        self.pending = Some(action);
+
+        match to {
+            Some(to) if to != unit.pos => {
+                let path = DijkstraMap::new(world, unit).get_path_to(to);
+                viewport.set_center_on(unit.pos);
+                Transition::Push(MoveAnimation::boxed_new(unit.clone(), path))
+            }
+            _ => {
+                self.finish(unit.clone(), commands, world);
+                Transition::None
+            }
+        }
+    }
+}
+
+impl GameState for RemoteManager {
+    fn on_enter(&self, game_ctx: GameCtxView) {
+        if let Some(link) = game_ctx.bots.get_mut(self.faction) {
+            link.notify(
+                "turn_started",
+                &json!({ "turn": game_ctx.world.turn, "faction": self.faction }),
+            );
+        }
+    }
+
+    fn update(
+        &mut self,
+        msg_queue: &mut VecDeque<GameMsg>,
+        commands: &mut Commands,
+        game_ctx: GameCtxView,
+    ) -> Transition {
+        // Drained so a finished move is never left behind a world update
+        while let Some(msg) = msg_queue.pop_front() {
+            match msg {
+                GameMsg::MoveAnimationDone(unit) => {
+                    self.finish(unit, commands, game_ctx.world);
+                    return Transition::None;
+                }
+                GameMsg::WorldUpdated => {}
+                GameMsg::SetCursor(_) => {
+                    warn!("{} state should not receive msg: {:?}", self.name(), msg);
+                }
+            }
+        }
+
+        // Berserk units are out of the bot's control
+        if let Some(unit) = game_ctx.world.units().find(|unit| {
+            unit.faction == self.faction
+                && !unit.turn_complete
+                && unit.has_status(StatusKind::Berserk)
+        }) {
+            let dijkstra_map = DijkstraMap::new(game_ctx.world, unit);
+            game_ctx.viewport.set_center_on(unit.pos);
+            return Transition::Push(MoveSimulated::boxed_new(unit.clone(), dijkstra_map));
+        }
+
+        if game_ctx.world.get_unmoved_unit(self.faction).is_none() {
+            let next = self.faction.opponent();
+            commands.add(Command::SetupTurn(next));
+            return Transition::Switch(phase_state(next, &game_ctx));
+        }
+
+        let Some(link) = game_ctx.bots.get_mut(self.faction) else {
+            return Transition::Switch(Box::new(SimulatedManager::new(self.faction)));
+        };
+        match link.poll(game_ctx.world, self.faction) {
+            None => Transition::None,
+            Some(BotEvent::Act(action)) => {
+                self.play(action, commands, game_ctx.world, game_ctx.viewport)
+            }
+            Some(BotEvent::Disconnected) => {
+                warn!("The {:?} bot disconnected, the AI takes over", self.faction);
+                game_ctx.bots.detach(self.faction);
+                Transition::Switch(Box::new(SimulatedManager::new(self.faction)))
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Remote Manager"
+    }
+}
@@ -1,13 +1,15 @@
-use super::state_machine::{Command, Commands, GameMsg, GameState, Transition};
+use super::phase_state;
+use super::state_machine::{Commands, GameMsg, GameState, Transition};
+use crate::ai;
+use crate::combat;
+use crate::command::Command;
 use crate::game::GameCtxView;
-use crate::pathfinding::{DijkstraMap, get_manahattan_neighbours};
+use crate::pathfinding::DijkstraMap;
 use crate::state::animation::MoveAnimation;
-use crate::state::player::PlayerSelect;
 use crate::unit::Unit;
 use crate::world::Faction;
 
 use macroquad::logging::warn;
-use macroquad::rand::ChooseRandom;
 
 use std::collections::VecDeque;
 
@@ -40,20 +42,21 @@ impl GameState for SimulatedManager {
         commands: &mut Commands,
         game_ctx: GameCtxView,
     ) -> Transition {
-        if let Some(msg) = msg_queue.pop_front() {
+        // The world is re-read every update so there is nothing to refresh
+        if let Some(msg) = msg_queue.pop_front()
+            && !matches!(msg, GameMsg::WorldUpdated)
+        {
             warn!("{} state should not receive msg: {:?}", self.name(), msg);
         }
         if let Some(unit) = game_ctx.world.get_unmoved_unit(self.faction) {
-            let dijkstra_map = DijkstraMap::new(&game_ctx.world.map, unit, &game_ctx.world.units);
+            let dijkstra_map = DijkstraMap::new(game_ctx.world, unit);
             game_ctx.viewport.set_center_on(unit.pos);
             return Transition::Push(MoveSimulated::boxed_new(unit.clone(), dijkstra_map));
         }
 
-        commands.add(Command::SetupTurn);
-        Transition::Switch(PlayerSelect::boxed_new(
-            game_ctx.world,
-            game_ctx.texture_store,
-        ))
+        let next = self.faction.opponent();
+        commands.add(Command::SetupTurn(next));
+        Transition::Switch(phase_state(next, &game_ctx))
     }
 
     fn name(&self) -> &'static str {
@@ -89,21 +92,7 @@ impl GameState for MoveSimulated {
             }
         }
 
-        // TODO Calculate best unit to target / best point to move to
-        let player_positions = game_ctx
-            .world
-            .units
-            .values()
-            .filter(|unit| unit.faction == Faction::Player)
-            .map(|unit| unit.pos);
-        let neighbours = player_positions.flat_map(|pt| get_manahattan_neighbours(pt, 1));
-        let mut empty_tiles = neighbours.filter(|pt| game_ctx.world.is_tile_empty(*pt));
-        let maybe_dest = empty_tiles.find(|pt| self.dijkstra_map.get_reachables().contains(pt));
-
-        let dest = maybe_dest.unwrap_or_else(|| {
-            let reachable: Vec<_> = self.dijkstra_map.get_reachables().iter().copied().collect();
-            *reachable.choose().unwrap()
-        });
+        let dest = ai::choose_destination(game_ctx.world, &self.unit, &self.dijkstra_map);
         let path = self.dijkstra_map.get_path_to(dest);
 
         Transition::Push(MoveAnimation::boxed_new(self.unit.clone(), path))
@@ -131,17 +120,10 @@ impl GameState for ActionSimulated {
         commands: &mut Commands,
         game_ctx: GameCtxView,
     ) -> Transition {
-        let target = get_manahattan_neighbours(self.unit.pos, 2).find_map(|pt| {
-            game_ctx
-                .world
-                .units
-                .iter()
-                .find(|(_, unit)| unit.faction == Faction::Player && unit.pos == pt)
-        });
-
-        if let Some((id, _unit)) = target {
-            // TODO Compute damage
-            commands.add(Command::DamageUnit(*id, 5));
+        if let Some(target) = ai::choose_target(game_ctx.world, &self.unit) {
+            for command in combat::attack(&mut self.unit, target).commands {
+                commands.add(command);
+            }
         }
         self.unit.turn_complete = true;
         commands.add(Command::CommitUnit(self.unit.clone()));
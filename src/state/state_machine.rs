@@ -1,16 +1,25 @@
 use macroquad::camera::set_default_camera;
+use macroquad::logging::{info, warn};
+use macroquad::math::Vec2;
 use macroquad::prelude::set_camera;
 
 use std::collections::VecDeque;
 use std::fmt::Debug;
 
-use super::player::PlayerSelect;
+use super::animation::CameraPan;
+use super::dialogue::DialogueState;
+use super::phase_state;
+use crate::command::Command;
 use crate::cursor::Cursor;
+use crate::event::TriggerAction;
 use crate::game::{GameContext, GameCtxView};
+use crate::math::Point;
 use crate::render::RenderContext;
 use crate::render::RenderCtxWithViewport;
+use crate::tty::TextScreen;
 // use crate::state::animation::ShiftMapView;
-use crate::unit::{Unit, UnitId};
+use crate::unit::{ErasedUnit, Unit};
+use crate::world::{Faction, WorldState};
 
 #[derive(Debug)]
 pub struct StateMachine {
@@ -19,47 +28,76 @@ pub struct StateMachine {
     commands_buffer: Commands,
 }
 impl StateMachine {
-    pub fn new(game_ctx: &GameContext) -> Self {
+    pub fn new(game_ctx: &mut GameContext) -> Self {
         Self {
-            stack: vec![PlayerSelect::boxed_new(
-                &game_ctx.world,
-                &game_ctx.texture_store,
-            )],
+            stack: vec![phase_state(Faction::Player, &game_ctx.get_view())],
             msg_queue: VecDeque::new(),
             commands_buffer: Commands::new(),
         }
     }
 
-    pub fn update(&mut self, game_ctx: &mut GameContext) {
-        game_ctx.render_ctx.resize_if_required();
+    /// Pushes a state on top of the stack outside of a regular transition
+    pub fn push_state(&mut self, game_state: Box<dyn GameState>, game_ctx: &mut GameContext) {
+        self.apply_transition(Transition::Push(game_state), game_ctx.get_view());
+    }
+
+    /// Replaces the state the current phase started with, states pushed on top stay
+    pub fn restart_phase(&mut self, faction: Faction, game_ctx: &mut GameContext) {
+        let game_ctx = game_ctx.get_view();
+        let state = phase_state(faction, &game_ctx);
+        state.on_enter(game_ctx);
+        self.stack[0] = state;
+    }
 
+    pub fn update(&mut self, game_ctx: &mut GameContext) {
+        if let Some(net) = &mut game_ctx.net {
+            net.update();
+        }
         loop {
-            game_ctx.controller.update();
             let transition = self.stack.last_mut().unwrap().update(
                 &mut self.msg_queue,
                 &mut self.commands_buffer,
                 game_ctx.get_view(),
             );
-            self.commands_buffer
-                .drain()
-                .for_each(|command| match command {
-                    Command::CommitUnit(unit) => {
-                        game_ctx.world.units.insert(unit.id(), unit);
-                        // TODO Make this a callback in the state trait instead of a msg
-                        self.msg_queue.push_back(GameMsg::WorldUpdated);
-                    }
-                    Command::SetupTurn => game_ctx.world.setup_turn(),
-                    Command::DamageUnit(id, dmg) => {
-                        let unit = game_ctx.world.units.get_mut(&id).unwrap();
-                        unit.curr_health -= dmg;
-                        self.msg_queue.push_back(GameMsg::WorldUpdated);
-                    }
-                });
-
-            match transition {
-                Transition::None => break,
-                _ => self.apply_transition(transition, game_ctx.get_view()),
+            let commands: Vec<Command> = self.commands_buffer.drain().collect();
+            // The other side of an online battle applies the local phase's commands as well
+            if let Some(net) = &mut game_ctx.net
+                && game_ctx.world.phase == net.local()
+                && !commands.is_empty()
+            {
+                net.send_commands(&commands);
+            }
+            let mut phase_started = false;
+            let mut arrivals = Vec::new();
+            for command in commands {
+                phase_started |= matches!(command, Command::SetupTurn(_));
+                // TODO Make this a callback in the state trait instead of a msg
+                if !matches!(command, Command::Talk(..)) {
+                    self.msg_queue.push_back(GameMsg::WorldUpdated);
+                }
+                arrivals.extend(game_ctx.world.apply_command(command));
+            }
+            if phase_started && let Some(net) = &mut game_ctx.net {
+                net.phase_started(&game_ctx.world);
+            }
+            let actions = game_ctx.world.evaluate_triggers();
+
+            let done = matches!(transition, Transition::None);
+            self.apply_transition(transition, game_ctx.get_view());
+            for action in actions {
+                self.apply_trigger_action(action, game_ctx);
+            }
+            if !arrivals.is_empty() {
+                self.push_state(CameraPan::boxed_new(arrivals), game_ctx);
+            }
+
+            if done {
+                break;
             }
+            // The next state must not see this frame's presses or taps as new ones
+            let state = game_ctx.controller.button_state();
+            game_ctx.controller.feed(state, 0.0);
+            game_ctx.tap = None;
         }
         game_ctx.viewport.update();
     }
@@ -67,8 +105,12 @@ impl StateMachine {
     pub fn render(&self, game_ctx: &GameContext) {
         let render_ctx = game_ctx.get_render_view();
 
-        set_camera(game_ctx.render_ctx.camera_ref());
-        RenderContext::render_map(&game_ctx.world.map, &game_ctx.viewport);
+        set_camera(&game_ctx.render_ctx.map_camera(&game_ctx.viewport));
+        RenderContext::render_map(
+            &game_ctx.world.map,
+            &game_ctx.texture_store,
+            &game_ctx.viewport,
+        );
 
         self.stack
             .iter()
@@ -78,25 +120,37 @@ impl StateMachine {
         let mut operating_unit = None;
         for state in self.stack.iter().rev() {
             if let Some(unit) = state.active_unit() {
-                operating_unit = Some(unit);
+                operating_unit = Some((unit, state.active_render_pos()));
                 break;
             }
         }
 
         game_ctx
             .world
-            .units
-            .iter()
-            .filter(|(_, unit)| game_ctx.viewport.is_point_visible(unit.pos))
-            .filter(|(id, _)| operating_unit.is_none_or(|unit| unit.id() != **id))
-            .for_each(|(_, unit)| RenderContext::render_unit(unit, &game_ctx.viewport));
+            .units()
+            .filter(|unit| game_ctx.viewport.is_point_visible(unit.pos))
+            .filter(|unit| operating_unit.is_none_or(|(operating, _)| operating.id() != unit.id()))
+            .for_each(|unit| {
+                RenderContext::render_unit(
+                    unit,
+                    unit.pos.into(),
+                    &game_ctx.texture_store,
+                    &game_ctx.viewport,
+                );
+            });
 
-        if let Some(unit) = operating_unit
+        if let Some((unit, render_pos)) = operating_unit
             && game_ctx.viewport.is_point_visible(unit.pos)
         {
-            RenderContext::render_unit(unit, &game_ctx.viewport);
+            RenderContext::render_unit(
+                unit,
+                render_pos.unwrap_or(unit.pos.into()),
+                &game_ctx.texture_store,
+                &game_ctx.viewport,
+            );
         }
 
+        set_camera(game_ctx.render_ctx.camera_ref());
         self.stack
             .iter()
             .rev()
@@ -106,6 +160,85 @@ impl StateMachine {
         game_ctx.controller.draw(None);
     }
 
+    /// Same layering as [`StateMachine::render`], drawn into characters
+    pub fn render_text(&self, world: &WorldState) -> TextScreen {
+        let mut screen = TextScreen::new(world);
+        let operating_unit = self
+            .stack
+            .iter()
+            .rev()
+            .find_map(|state| state.active_unit());
+
+        world
+            .units()
+            .filter(|unit| operating_unit.is_none_or(|operating| operating.id() != unit.id()))
+            .for_each(|unit| screen.draw_unit(unit));
+        if let Some(unit) = operating_unit {
+            screen.draw_unit(unit);
+        }
+
+        self.stack
+            .iter()
+            .rev()
+            .find_map(|state| state.render_text(&mut screen));
+        screen
+    }
+
+    pub fn queue_command(&mut self, command: Command) {
+        self.commands_buffer.add(command);
+    }
+
+    pub fn current_state(&self) -> &'static str {
+        self.stack.last().unwrap().name()
+    }
+
+    pub fn takes_input(&self) -> bool {
+        self.stack.last().unwrap().takes_input()
+    }
+
+    /// Returns where the unit was placed
+    fn spawn(unit: &ErasedUnit, game_ctx: &mut GameContext) -> Option<Point> {
+        let Some(id) = game_ctx.world.spawn_units(unit) else {
+            warn!("No free tile to spawn {:?} near {:?}", unit.class, unit.pos);
+            return None;
+        };
+        game_ctx.world.get_unit(id).map(|unit| unit.pos)
+    }
+
+    fn apply_trigger_action(&mut self, action: TriggerAction, game_ctx: &mut GameContext) {
+        match action {
+            TriggerAction::Dialogue(path) => {
+                let Some(script) = game_ctx.dialogue_store.get(&path) else {
+                    warn!("Dialogue {} is not loaded", path);
+                    return;
+                };
+                let dialogue = DialogueState::boxed_new(script.clone());
+                self.push_state(dialogue, game_ctx);
+            }
+            TriggerAction::SpawnUnits(units) => {
+                let arrivals: Vec<_> = units
+                    .iter()
+                    .filter_map(|unit| Self::spawn(unit, game_ctx))
+                    .collect();
+                self.msg_queue.push_back(GameMsg::WorldUpdated);
+                if !arrivals.is_empty() {
+                    self.push_state(CameraPan::boxed_new(arrivals), game_ctx);
+                }
+            }
+            TriggerAction::ChangeTerrain(pos, terrain) => {
+                game_ctx.world.map.set_terrain(pos, terrain);
+            }
+            TriggerAction::ChangeObjective(objective) => {
+                info!("New objective: {}", objective.description());
+                game_ctx.world.objective = objective;
+            }
+            TriggerAction::GiveItem(id, item) => {
+                game_ctx.world.give_item(id, item);
+                self.msg_queue.push_back(GameMsg::WorldUpdated);
+            }
+        }
+    }
+
     fn apply_transition(&mut self, transition: Transition, game_ctx: GameCtxView) {
         match transition {
             Transition::None => {}
@@ -129,19 +262,13 @@ impl StateMachine {
 }
 
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum GameMsg {
     MoveAnimationDone(Unit),
     SetCursor(Cursor),
     WorldUpdated,
 }
 
-#[derive(Debug)]
-pub enum Command {
-    CommitUnit(Unit),
-    DamageUnit(UnitId, i32),
-    SetupTurn,
-}
-
 pub trait GameState: Debug {
     fn on_enter(&self, _game_ctx: GameCtxView) {}
 
@@ -160,9 +287,24 @@ pub trait GameState: Debug {
         None
     }
 
+    /// Terminal counterpart of the render layers, see [`crate::tty`]
+    fn render_text(&self, _screen: &mut TextScreen) -> Option<()> {
+        None
+    }
+
     fn active_unit(&self) -> Option<&Unit> {
         None
     }
+
+    /// Where the active unit is drawn while it is between tiles
+    fn active_render_pos(&self) -> Option<Vec2> {
+        None
+    }
+
+    /// Whether the state is waiting for the player rather than playing something out
+    fn takes_input(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Debug)]
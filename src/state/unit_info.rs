@@ -0,0 +1,197 @@
+use super::state_machine::{Commands, GameMsg, GameState, Transition};
+use crate::game::GameCtxView;
+use crate::render::{RenderContext, RenderCtxWithViewport};
+use crate::tty::TextScreen;
+use crate::unit::Unit;
+
+use std::collections::VecDeque;
+
+use input_lib::Buttons;
+use macroquad::color::{Color, GREEN, WHITE};
+use macroquad::math::Vec2;
+use macroquad::shapes::{draw_rectangle, draw_rectangle_lines};
+use macroquad::text::draw_text;
+use macroquad::texture::{DrawTextureParams, draw_texture_ex};
+
+/// Everything about one unit, the d-pad pages through the other units of its faction
+#[derive(Debug)]
+pub struct UnitInfo {
+    unit: Unit,
+}
+
+impl UnitInfo {
+    pub fn boxed_new(unit: Unit) -> Box<Self> {
+        Box::new(Self { unit })
+    }
+
+    fn title(&self) -> String {
+        format!(
+            "{:?} {}  Lv {}  Exp {}",
+            self.unit.faction,
+            self.unit.class().name(),
+            self.unit.level,
+            self.unit.exp
+        )
+    }
+
+    fn details(&self) -> Vec<String> {
+        let unit = &self.unit;
+        let stats = unit.effective_stats();
+        let weapon = unit.weapon.map_or_else(
+            || "None".to_string(),
+            |weapon| {
+                let effect = weapon
+                    .effect
+                    .map(|effect| format!(", inflicts {:?}", effect.kind))
+                    .unwrap_or_default();
+                format!(
+                    "{:?}, might {}, range {}{effect}",
+                    weapon.kind, weapon.might, weapon.max_range
+                )
+            },
+        );
+        let items: Vec<String> = unit.items.iter().map(|item| item.name().into()).collect();
+        let skills: Vec<String> = unit
+            .class()
+            .data()
+            .skills
+            .iter()
+            .chain(&unit.skills)
+            .map(|skill| format!("{skill:?}"))
+            .collect();
+        let statuses: Vec<String> = unit
+            .status_effects
+            .iter()
+            .map(|effect| format!("{:?} ({})", effect.kind, effect.turns))
+            .collect();
+        let list = |entries: Vec<String>| {
+            if entries.is_empty() {
+                "None".to_string()
+            } else {
+                entries.join(", ")
+            }
+        };
+
+        vec![
+            format!(
+                "HP {}/{}  Move {}",
+                unit.curr_health,
+                unit.max_health(),
+                unit.movement()
+            ),
+            format!(
+                "Str {}  Mag {}  Skl {}  Spd {}",
+                stats.strength, stats.magic, stats.skill, stats.speed
+            ),
+            format!(
+                "Lck {}  Def {}  Res {}",
+                stats.luck, stats.defence, stats.resistance
+            ),
+            format!("Weapon: {weapon}"),
+            format!("Items: {}", list(items)),
+            format!("Skills: {}", list(skills)),
+            format!("Status: {}", list(statuses)),
+        ]
+    }
+
+    /// Moves `step` places through the units of the same faction, ordered by id
+    fn page(&mut self, step: i32, game_ctx: &mut GameCtxView) {
+        let mut units: Vec<&Unit> = game_ctx
+            .world
+            .units()
+            .filter(|unit| unit.faction == self.unit.faction)
+            .collect();
+        units.sort_by_key(|unit| *unit.id());
+        let Some(current) = units.iter().position(|unit| unit.id() == self.unit.id()) else {
+            return;
+        };
+
+        let len = i32::try_from(units.len()).unwrap();
+        let next = (i32::try_from(current).unwrap() + step).rem_euclid(len);
+        self.unit = units[usize::try_from(next).unwrap()].clone();
+        game_ctx.viewport.set_center_on(self.unit.pos);
+    }
+}
+
+impl GameState for UnitInfo {
+    fn update(
+        &mut self,
+        _msg_queue: &mut VecDeque<GameMsg>,
+        _commands: &mut Commands,
+        mut game_ctx: GameCtxView,
+    ) -> Transition {
+        // Messages are left for the state underneath, nothing changes while this is open
+        if game_ctx.controller.clicked(Buttons::B) || game_ctx.controller.clicked(Buttons::X) {
+            return Transition::Pop;
+        }
+
+        let dpad = game_ctx.controller.timed_hold();
+        let step = if dpad.dpad_x != 0 {
+            dpad.dpad_x
+        } else {
+            -dpad.dpad_y
+        };
+        if step != 0 {
+            self.page(step.signum(), &mut game_ctx);
+        }
+
+        Transition::None
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn render_ui_layer(&self, render_ctx: RenderCtxWithViewport) -> Option<()> {
+        let view_rect = RenderContext::screen_view_rect();
+        let (x, y) = (
+            view_rect.x + view_rect.w * 0.1,
+            view_rect.y + view_rect.h * 0.1,
+        );
+        let (w, h) = (view_rect.w * 0.8, view_rect.h * 0.8);
+        draw_rectangle(x, y, w, h, Color::new(0.0, 0.0, 0.2, 0.9));
+        draw_rectangle_lines(x, y, w, h, 4.0, WHITE);
+
+        let font_size = view_rect.h / 18.0;
+        let padding = font_size;
+        let portrait_size = h * 0.4;
+        let portrait = render_ctx.texture_store.get(&self.unit.texture_path);
+        let params = DrawTextureParams {
+            dest_size: Some(Vec2::splat(portrait_size)),
+            ..Default::default()
+        };
+        draw_texture_ex(&portrait, x + padding, y + padding, WHITE, params);
+
+        let text_x = x + padding * 2.0 + portrait_size;
+        draw_text(&self.title(), text_x, y + padding * 2.0, font_size, GREEN);
+        let line_height = font_size * 1.2;
+        let first_line = y + padding * 2.0 + line_height * 1.5;
+        for (i, line) in self.details().iter().enumerate() {
+            let line_y = first_line + i as f32 * line_height;
+            // Lines below the portrait use the whole width
+            let line_x = if line_y < y + padding + portrait_size {
+                text_x
+            } else {
+                x + padding
+            };
+            draw_text(line, line_x, line_y, font_size, WHITE);
+        }
+
+        Some(())
+    }
+
+    fn render_text(&self, screen: &mut TextScreen) -> Option<()> {
+        screen.set_cursor(self.unit.pos);
+        screen.line(self.title());
+        for line in self.details() {
+            screen.line(line);
+        }
+
+        Some(())
+    }
+
+    fn takes_input(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "Unit Info"
+    }
+}
@@ -0,0 +1,441 @@
+//! Terminal frontend that plays the same states as the window, for SSH debugging and
+//! CI smoke tests where macroquad cannot open one.
+//!
+//! Input is read a line at a time so a whole session can be piped in:
+//! `printf 'z\nddz\n' | cargo run --bin luminara_tty -- --seed 1 --no-color`
+
+use crate::assets::{DialogueStore, TextureStore};
+use crate::class::UnitClass;
+use crate::dialogue::DialogueScript;
+use crate::game::{CHAPTER_DIALOGUES, Engine};
+use crate::grid::GridTopology;
+use crate::map::Map;
+use crate::math::Point;
+use crate::net::NetSession;
+use crate::rng::Rng;
+use crate::snapshot::WorldSnapshot;
+use crate::unit::Unit;
+use crate::world::{Faction, WorldState};
+
+use input_lib::{ButtonState, Buttons};
+
+use std::fmt::Write as _;
+use std::io::{BufRead, Write};
+use std::net::TcpListener;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub(crate) const FRAME_TIME: f32 = 1.0 / 60.0;
+/// Enough for a long enemy phase, a battle nobody can act in stops here
+pub(crate) const MAX_IDLE_FRAMES: u32 = 20_000;
+
+const HELP: &str = "\
+keys: w a s d move   z A   x B   c X   v Y   e Start   r Select   [ ] L R
+      several keys per line run in order, q quits, ? shows this again
+debug: :checksum   :snapshot FILE saves the world   :diff FILE compares it with a saved one
+map:  . ground   T forest   ^ mountain   ~ river
+      f/F fighter/warrior   c/C cavalier/paladin   a/A archer/sniper
+      p/P pegasus/falcon knight   m/M mage/sage";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextColor {
+    Red,
+    Yellow,
+    Blue,
+}
+
+impl TextColor {
+    fn ansi_code(self) -> u8 {
+        match self {
+            Self::Red => 31,
+            Self::Yellow => 33,
+            Self::Blue => 34,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TextTile {
+    glyph: char,
+    color: Option<TextColor>,
+    mark: Option<TextColor>,
+}
+
+/// Character grid of the map plus lines of text below it, what states draw in a terminal
+#[derive(Debug)]
+pub struct TextScreen {
+    width: usize,
+    topology: GridTopology,
+    tiles: Vec<TextTile>,
+    cursor: Option<Point>,
+    lines: Vec<String>,
+}
+
+impl TextScreen {
+    #[must_use]
+    pub fn new(world: &WorldState) -> Self {
+        let map = &world.map;
+        let tiles = map
+            .to_ascii()
+            .iter()
+            .flat_map(|row| row.chars().collect::<Vec<_>>())
+            .map(|glyph| TextTile {
+                glyph,
+                color: None,
+                mark: None,
+            })
+            .collect();
+
+        Self {
+            width: map.width,
+            topology: map.topology,
+            tiles,
+            cursor: None,
+            lines: Vec::new(),
+        }
+    }
+
+    fn tile_mut(&mut self, pt: Point) -> Option<&mut TextTile> {
+        let x = usize::try_from(pt.x).ok().filter(|x| *x < self.width)?;
+        let y = usize::try_from(pt.y).ok()?;
+        self.tiles.get_mut(y * self.width + x)
+    }
+
+    /// Tiles marked in `color`, as a range overlay marks them
+    #[must_use]
+    pub fn marked(&self, color: TextColor) -> Vec<Point> {
+        (0..self.tiles.len())
+            .filter(|idx| self.tiles[*idx].mark == Some(color))
+            .filter_map(|idx| {
+                Some(Point::new(
+                    (idx % self.width).try_into().ok()?,
+                    (idx / self.width).try_into().ok()?,
+                ))
+            })
+            .collect()
+    }
+
+    pub fn draw_unit(&mut self, unit: &Unit) {
+        let color = match unit.faction {
+            Faction::Player => TextColor::Blue,
+            Faction::Enemy => TextColor::Red,
+        };
+        if let Some(tile) = self.tile_mut(unit.pos) {
+            tile.glyph = unit_glyph(unit.class());
+            tile.color = Some(color);
+        }
+    }
+
+    /// Highlights the tile, the last mark on a tile wins
+    pub fn mark(&mut self, pt: Point, color: TextColor) {
+        if let Some(tile) = self.tile_mut(pt) {
+            tile.mark = Some(color);
+        }
+    }
+
+    pub fn set_cursor(&mut self, pt: Point) {
+        self.cursor = Some(pt);
+    }
+
+    #[must_use]
+    pub fn cursor(&self) -> Option<Point> {
+        self.cursor
+    }
+
+    pub fn line(&mut self, text: impl Into<String>) {
+        self.lines.push(text.into());
+    }
+
+    /// Without colors marks show as `*` and the cursor as `<` after the tile
+    #[must_use]
+    pub fn to_text(&self, colored: bool) -> String {
+        let cursor = self
+            .cursor
+            .and_then(|pt| Some((usize::try_from(pt.x).ok()?, usize::try_from(pt.y).ok()?)));
+        let mut text = String::new();
+        for (y, row) in self.tiles.chunks(self.width).enumerate() {
+            if self.topology == GridTopology::Hex && y % 2 == 1 {
+                text.push(' ');
+            }
+            for (x, tile) in row.iter().enumerate() {
+                let is_cursor = cursor == Some((x, y));
+                if colored {
+                    Self::push_colored(&mut text, *tile, is_cursor);
+                } else {
+                    text.push(tile.glyph);
+                    text.push(match (is_cursor, tile.mark) {
+                        (true, _) => '<',
+                        (false, Some(_)) => '*',
+                        (false, None) => ' ',
+                    });
+                }
+            }
+            text.push('\n');
+        }
+        for line in &self.lines {
+            text.push_str(line);
+            text.push('\n');
+        }
+        text
+    }
+
+    fn push_colored(text: &mut String, tile: TextTile, is_cursor: bool) {
+        let mut codes = Vec::new();
+        if is_cursor {
+            codes.push(7);
+        }
+        if let Some(color) = tile.color {
+            codes.push(color.ansi_code());
+            codes.push(1);
+        }
+        if let Some(mark) = tile.mark {
+            codes.push(mark.ansi_code() + 10);
+        }
+
+        if codes.is_empty() {
+            let _ = write!(text, "{} ", tile.glyph);
+            return;
+        }
+        let codes: Vec<_> = codes.iter().map(u8::to_string).collect();
+        let _ = write!(text, "\x1b[{}m{}\x1b[0m ", codes.join(";"), tile.glyph);
+    }
+}
+
+/// Base classes are lower case, their promotions upper case
+fn unit_glyph(class: UnitClass) -> char {
+    match class {
+        UnitClass::Fighter => 'f',
+        UnitClass::Warrior => 'F',
+        UnitClass::Cavalier => 'c',
+        UnitClass::Paladin => 'C',
+        UnitClass::Archer => 'a',
+        UnitClass::Sniper => 'A',
+        UnitClass::PegasusKnight => 'p',
+        UnitClass::FalconKnight => 'P',
+        UnitClass::Mage => 'm',
+        UnitClass::Sage => 'M',
+    }
+}
+
+/// Key bindings of the terminal, also used by the scenario tests
+pub(crate) fn key_input(key: char) -> Option<ButtonState> {
+    let mut state = ButtonState::default();
+    match key.to_ascii_lowercase() {
+        'w' => state.dpad_y = 1,
+        's' => state.dpad_y = -1,
+        'a' => state.dpad_x = -1,
+        'd' => state.dpad_x = 1,
+        'z' => {
+            state.buttons.insert(Buttons::A);
+        }
+        'x' => {
+            state.buttons.insert(Buttons::B);
+        }
+        'c' => {
+            state.buttons.insert(Buttons::X);
+        }
+        'v' => {
+            state.buttons.insert(Buttons::Y);
+        }
+        'e' => {
+            state.buttons.insert(Buttons::Start);
+        }
+        'r' => {
+            state.buttons.insert(Buttons::Select);
+        }
+        '[' => {
+            state.buttons.insert(Buttons::L);
+        }
+        ']' => {
+            state.buttons.insert(Buttons::R);
+        }
+        _ => return None,
+    }
+    Some(state)
+}
+
+/// Reads the chapter dialogues straight from the assets folder, macroquad's loader needs a window
+fn load_dialogues() -> DialogueStore {
+    let mut dialogue_store = DialogueStore::new();
+    for path in CHAPTER_DIALOGUES {
+        let script = std::fs::read_to_string(format!("assets/{path}"))
+            .ok()
+            .and_then(|json| DialogueScript::from_json(&json).ok());
+        match script {
+            Some(script) => dialogue_store.insert(path, script),
+            None => eprintln!("Could not load {path}"),
+        }
+    }
+    dialogue_store
+}
+
+/// Like [`Engine::settle`], but an online opponent may take as long as they like
+fn settle(engine: &mut Engine) -> bool {
+    loop {
+        if engine.settle(FRAME_TIME, MAX_IDLE_FRAMES) {
+            return true;
+        }
+        if !engine.waits_for_peer() {
+            return false;
+        }
+    }
+}
+
+/// Debug commands for tracking down desyncs, see [`WorldSnapshot`]
+fn debug_command(world: &WorldState, command: &str) -> String {
+    let mut words = command.split_whitespace();
+    let (name, path) = (words.next().unwrap_or_default(), words.next());
+    let result = match (name, path) {
+        ("checksum", _) => Ok(format!("{:016x}\n", world.checksum())),
+        ("snapshot", Some(path)) => std::fs::write(path, world.snapshot().to_json())
+            .map(|()| format!("Saved the world to {path}\n"))
+            .map_err(|err| err.to_string()),
+        ("diff", Some(path)) => std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|json| WorldSnapshot::from_json(&json).map_err(|err| err.to_string()))
+            .map(|saved| {
+                let diffs = saved.diff(&world.snapshot());
+                if diffs.is_empty() {
+                    return format!("The world matches {path}\n");
+                }
+                diffs.iter().fold(String::new(), |mut out, diff| {
+                    let _ = writeln!(out, "{diff}");
+                    out
+                })
+            }),
+        _ => Err(format!("Unknown debug command :{command}")),
+    };
+    result.unwrap_or_else(|err| format!("{err}\n"))
+}
+
+fn host_battle(addr: &str, seed: u64, topology: GridTopology) -> std::io::Result<NetSession> {
+    let listener = TcpListener::bind(addr)?;
+    eprintln!("Waiting for the other player on {}", listener.local_addr()?);
+    NetSession::host(listener, seed, topology)
+}
+
+fn status(engine: &Engine, screen: &TextScreen) -> String {
+    let world = engine.world();
+    let mut status = format!(
+        "Turn {} | {} | {}",
+        world.turn,
+        world.objective.description(),
+        engine.current_state(),
+    );
+    if let Some(desync) = engine.net().and_then(NetSession::desync) {
+        let _ = write!(status, " | Desync since turn {}", desync.turn);
+    }
+    if let Some(unit) = screen.cursor().and_then(|pt| world.unit_at(pt)) {
+        let _ = write!(
+            status,
+            " | {:?} {} Lv{} HP {}/{}",
+            unit.faction,
+            unit.class().name(),
+            unit.level,
+            unit.curr_health,
+            unit.max_health(),
+        );
+    }
+    status
+}
+
+pub fn run() {
+    let mut args = std::env::args().skip(1);
+    let mut seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let mut topology = GridTopology::Square;
+    let mut versus = false;
+    let (mut host, mut join) = (None, None);
+    let mut resume = None;
+    let mut colored = std::env::var_os("NO_COLOR").is_none();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => {
+                seed = args
+                    .next()
+                    .and_then(|seed| seed.parse().ok())
+                    .unwrap_or(seed);
+            }
+            "--hex" => topology = GridTopology::Hex,
+            "--versus" => versus = true,
+            "--host" => host = args.next(),
+            "--join" => join = args.next(),
+            "--resume" => resume = args.next(),
+            "--no-color" => colored = false,
+            other => eprintln!("Ignoring unknown argument {other}"),
+        }
+    }
+
+    let session = match (host, join) {
+        (Some(addr), _) => host_battle(&addr, seed, topology).map(Some),
+        // The battle is built from the host's seed and map
+        (None, Some(addr)) => NetSession::join(&addr).map(|(session, host_seed, host_topology)| {
+            (seed, topology) = (host_seed, host_topology);
+            Some(session)
+        }),
+        (None, None) => Ok(None),
+    };
+    let session = match session {
+        Ok(session) => session,
+        Err(err) => {
+            eprintln!("Could not start the online battle: {err}");
+            return;
+        }
+    };
+
+    let mut rng = Rng::new(seed);
+    let map = Map::random(30, 20, topology, &mut rng);
+    let mut engine = Engine::new(map, rng.next_u64(), TextureStore::new(), load_dialogues());
+    if let Some(session) = session {
+        engine.start_online(session);
+    } else if versus {
+        engine.seat_humans(&[Faction::Player, Faction::Enemy]);
+    }
+    if let Some(path) = resume
+        && let Err(err) = engine.resume_file(&path)
+    {
+        eprintln!("Could not resume {path}: {err}");
+        return;
+    }
+    settle(&mut engine);
+
+    println!("Seed {seed}\n{HELP}");
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut notes = String::new();
+    loop {
+        let screen = engine.render_text();
+        if colored {
+            print!("\x1b[2J\x1b[H");
+        }
+        print!("{}", screen.to_text(colored));
+        println!("{}", status(&engine, &screen));
+        print!("{}", std::mem::take(&mut notes));
+        print!("> ");
+        let _ = std::io::stdout().flush();
+
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+        if let Some(command) = line.strip_prefix(':') {
+            notes = debug_command(engine.world(), command);
+            continue;
+        }
+        for key in line.chars() {
+            match key {
+                'q' => return,
+                '?' => println!("{HELP}"),
+                _ => {
+                    let Some(input) = key_input(key) else {
+                        continue;
+                    };
+                    engine.step(input, FRAME_TIME);
+                    if !settle(&mut engine) {
+                        println!("The battle no longer waits for input");
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
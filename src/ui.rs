@@ -1,4 +1,5 @@
 use crate::render::RenderContext;
+use crate::tty::TextScreen;
 use std::fmt::Debug;
 
 use input_lib::Controller;
@@ -51,6 +52,13 @@ impl<T: MenuItem> Menu<T> {
             draw_text(item.menu_label(), x, y + ((i + 1) as f32 * h), h, BLACK);
         });
     }
+
+    pub fn render_text(&self, screen: &mut TextScreen) {
+        for (i, item) in self.items.iter().enumerate() {
+            let marker = if self.selected == i { '>' } else { ' ' };
+            screen.line(format!("{marker} {}", item.menu_label()));
+        }
+    }
 }
 
 pub trait MenuItem: Copy + PartialEq + Eq + Debug {
@@ -0,0 +1,106 @@
+use luminara::bot::handle_line;
+use luminara::scenario::Scenario;
+use luminara_core::action::{self, Action};
+use luminara_core::class::UnitClass;
+use luminara_core::math::Point;
+use luminara_core::unit::UnitId;
+use luminara_core::world::{Faction, WorldState};
+
+use serde_json::{Value, json};
+
+/// A fighter at the left end facing an archer three tiles away
+fn world() -> WorldState {
+    Scenario::new(&["....", "^^^^"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Archer, Faction::Enemy, (3, 0))
+        .world()
+}
+
+fn request(method: &str, params: &Value) -> String {
+    json!({ "jsonrpc": "2.0", "id": 7, "method": method, "params": params }).to_string()
+}
+
+#[test]
+fn legal_actions_cover_moves_attacks_and_ending_the_turn() {
+    let unit = UnitId::new(0);
+    let target = UnitId::new(1);
+    let at = |x| Point::new(x, 0);
+
+    assert_eq!(
+        action::legal_actions(&world(), Faction::Player),
+        vec![
+            Action::Wait { unit },
+            Action::Move { unit, to: at(1) },
+            Action::Move { unit, to: at(2) },
+            Action::Attack {
+                unit,
+                to: at(2),
+                target
+            },
+            Action::EndTurn,
+        ]
+    );
+    // The archer shoots from both tiles it can reach, the one it stands on is too far
+    let attacks = action::legal_actions(&world(), Faction::Enemy)
+        .into_iter()
+        .filter(|action| matches!(action, Action::Attack { .. }))
+        .count();
+    assert_eq!(attacks, 2);
+}
+
+#[test]
+fn illegal_actions_are_rejected() {
+    let world = world();
+    let out_of_reach =
+        json!({ "type": "attack", "unit": 0, "to": { "x": 1, "y": 0 }, "target": 1 });
+    let handled = handle_line(&request("act", &out_of_reach), &world, Faction::Player);
+    assert_eq!(handled.action, None);
+    assert_eq!(handled.response.unwrap()["error"]["code"], 1);
+
+    let enemy_unit = json!({ "type": "wait", "unit": 1 });
+    let handled = handle_line(&request("act", &enemy_unit), &world, Faction::Player);
+    assert_eq!(handled.action, None);
+
+    let attack = json!({ "type": "attack", "unit": 0, "to": { "x": 2, "y": 0 }, "target": 1 });
+    let handled = handle_line(&request("act", &attack), &world, Faction::Player);
+    assert_eq!(
+        handled.action,
+        Some(Action::Attack {
+            unit: UnitId::new(0),
+            to: Point::new(2, 0),
+            target: UnitId::new(1)
+        })
+    );
+    assert_eq!(handled.response.unwrap()["result"], Value::Null);
+}
+
+#[test]
+fn observe_shows_every_unit() {
+    let handled = handle_line(&request("observe", &Value::Null), &world(), Faction::Enemy);
+    let result = &handled.response.unwrap()["result"];
+
+    assert_eq!(result["faction"], "Enemy");
+    assert_eq!(result["map"], json!(["....", "^^^^"]));
+    assert_eq!(result["units"][0]["class"], "Fighter");
+    assert_eq!(result["units"][1]["weapon"]["range"], 2);
+}
+
+#[test]
+fn malformed_messages_get_protocol_errors() {
+    let world = world();
+    let code = |line: &str| {
+        handle_line(line, &world, Faction::Player).response.unwrap()["error"]["code"].clone()
+    };
+
+    assert_eq!(code("{"), -32700);
+    assert_eq!(code(r#"{"id": 1, "method": "observe"}"#), -32600);
+    assert_eq!(code(&request("surrender", &Value::Null)), -32601);
+    assert_eq!(code(&request("act", &json!({ "type": "fly" }))), -32602);
+
+    let notification = json!({ "jsonrpc": "2.0", "method": "observe" }).to_string();
+    assert!(
+        handle_line(&notification, &world, Faction::Player)
+            .response
+            .is_none()
+    );
+}
@@ -0,0 +1,32 @@
+use luminara_core::grid::GridTopology;
+use luminara_core::math::Point;
+
+use macroquad::math::Vec2;
+
+#[test]
+fn tile_at_undoes_the_layout() {
+    for topology in [GridTopology::Square, GridTopology::Hex] {
+        for (x, y) in [(0, 0), (3, 1), (5, 2), (7, 7)] {
+            let tile = Point::new(x, y);
+            // Anywhere inside the laid out tile, not only its corner
+            let layout = topology.layout_pos(tile.into()) + Vec2::new(0.9, 0.5);
+            assert_eq!(topology.tile_at(layout), tile, "{topology:?}");
+        }
+    }
+}
+
+#[test]
+fn shifted_hex_rows_start_half_a_tile_late() {
+    assert_eq!(
+        GridTopology::Hex.tile_at(Vec2::new(0.4, 1.5)),
+        Point::new(-1, 1)
+    );
+    assert_eq!(
+        GridTopology::Hex.tile_at(Vec2::new(0.6, 1.5)),
+        Point::new(0, 1)
+    );
+    assert_eq!(
+        GridTopology::Square.tile_at(Vec2::new(0.4, 1.5)),
+        Point::new(0, 1)
+    );
+}
@@ -0,0 +1,259 @@
+use input_lib::{
+    Bindings, ButtonKind, ButtonState, Buttons, Cluster, Controller, DPadButtons, Frame, Gesture,
+    Gestures, Layout, Physical,
+};
+use macroquad::input::KeyCode;
+use macroquad::math::Vec2;
+use shade::input::{Binding, Button};
+
+fn pressing(dpad_x: i32, dpad_y: i32, buttons: &[Buttons]) -> ButtonState {
+    let mut state = ButtonState {
+        dpad_x,
+        dpad_y,
+        ..ButtonState::default()
+    };
+    state.buttons.extend(buttons.iter().copied());
+    state
+}
+
+#[test]
+fn merged_sources_press_everything_either_presses() {
+    let touch = pressing(1, 0, &[Buttons::A]);
+    let keyboard = pressing(1, 1, &[Buttons::Start]);
+
+    let merged = touch.merge(keyboard);
+    assert_eq!(merged, pressing(1, 1, &[Buttons::A, Buttons::Start]));
+}
+
+#[test]
+fn opposite_directions_cancel_out() {
+    let merged = pressing(-1, 1, &[]).merge(pressing(1, 1, &[]));
+    assert_eq!((merged.dpad_x, merged.dpad_y), (0, 1));
+}
+
+#[test]
+fn merged_input_clicks_like_any_other() {
+    let mut controller = Controller::new();
+    controller.feed(
+        pressing(0, 0, &[Buttons::B]).merge(ButtonState::default()),
+        0.016,
+    );
+    assert!(controller.clicked(Buttons::B));
+    controller.feed(pressing(0, 0, &[Buttons::B]), 0.016);
+    assert!(!controller.clicked(Buttons::B));
+}
+
+#[test]
+fn bindings_are_saved_as_name_pairs() {
+    let bindings = Bindings::<ButtonKind>::default();
+    let json = serde_json::to_string(&bindings).unwrap();
+    assert!(
+        json.starts_with(r#"[["Up","Up"],["Down","Down"]"#),
+        "{json}"
+    );
+    assert_eq!(
+        serde_json::from_str::<Bindings<ButtonKind>>(&json).unwrap(),
+        bindings
+    );
+}
+
+#[test]
+fn shade_reads_the_same_table() {
+    let mut bindings = Bindings::<ButtonKind>::default();
+    bindings
+        .rebind(ButtonKind::Action(Buttons::L), Physical::Key(KeyCode::K))
+        .unwrap();
+    let json = serde_json::to_string(&bindings).unwrap();
+
+    let shade: Bindings<Binding> = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        shade.target_of(Physical::Key(KeyCode::K)),
+        Some(Binding::Button(Button::L))
+    );
+    assert_eq!(shade.entries().count(), bindings.entries().count());
+}
+
+#[test]
+fn tables_with_a_key_bound_twice_are_rejected() {
+    let json = r#"[["Z", "A"], ["Z", "B"]]"#;
+    let err = serde_json::from_str::<Bindings<ButtonKind>>(json).unwrap_err();
+    assert!(err.to_string().contains("Z drives both A and B"), "{err}");
+}
+
+#[test]
+fn a_short_touch_is_a_tap() {
+    let mut gestures = Gestures::new();
+    gestures.feed(&[(1, Vec2::new(100.0, 100.0))]);
+    gestures.feed(&[(1, Vec2::new(104.0, 102.0))]);
+    assert!(gestures.gestures().is_empty());
+    gestures.feed(&[]);
+    assert_eq!(gestures.gestures(), [Gesture::Tap(Vec2::new(104.0, 102.0))]);
+}
+
+#[test]
+fn a_long_touch_drags_and_taps_nothing() {
+    let mut gestures = Gestures::new();
+    gestures.feed(&[(1, Vec2::new(100.0, 100.0))]);
+    gestures.feed(&[(1, Vec2::new(130.0, 100.0))]);
+    assert_eq!(gestures.gestures(), [Gesture::Drag(Vec2::new(30.0, 0.0))]);
+    gestures.feed(&[(1, Vec2::new(130.0, 90.0))]);
+    assert_eq!(gestures.gestures(), [Gesture::Drag(Vec2::new(0.0, -10.0))]);
+    gestures.feed(&[]);
+    assert!(gestures.gestures().is_empty());
+}
+
+#[test]
+fn two_fingers_pinch() {
+    let mut gestures = Gestures::new();
+    gestures.feed(&[(1, Vec2::new(100.0, 100.0)), (2, Vec2::new(200.0, 100.0))]);
+    gestures.feed(&[(1, Vec2::new(50.0, 100.0)), (2, Vec2::new(250.0, 100.0))]);
+    assert_eq!(
+        gestures.gestures(),
+        [Gesture::Pinch {
+            center: Vec2::new(150.0, 100.0),
+            scale: 2.0
+        }]
+    );
+    // Lifting one finger after a pinch is no tap
+    gestures.feed(&[(2, Vec2::new(250.0, 100.0))]);
+    gestures.feed(&[]);
+    assert!(gestures.gestures().is_empty());
+}
+
+#[test]
+fn the_default_layout_keeps_the_clusters_apart() {
+    let layout = Layout::default();
+    let screen = Vec2::new(1920.0, 1080.0);
+    let dpad = layout.cluster_rect(Cluster::DPad, screen);
+    let actions = layout.cluster_rect(Cluster::Actions, screen);
+    assert!(dpad.right() < actions.left());
+    assert!(!dpad.overlaps(&layout.cluster_rect(Cluster::Bar, screen)));
+
+    let a = layout.rect(ButtonKind::Action(Buttons::A), screen);
+    assert_eq!(
+        layout.cluster_at(a.center(), screen),
+        Some(Cluster::Actions)
+    );
+    assert_eq!(layout.cluster_at(Vec2::new(960.0, 100.0), screen), None);
+}
+
+#[test]
+fn left_handed_layouts_mirror_and_moves_follow_the_screen() {
+    let screen = Vec2::new(1000.0, 500.0);
+    let mut layout = Layout::default();
+    let up = ButtonKind::DPad(DPadButtons::Up);
+    let right_handed = layout.rect(up, screen);
+
+    layout.left_handed = true;
+    let left_handed = layout.rect(up, screen);
+    assert!((left_handed.center().x - (1000.0 - right_handed.center().x)).abs() < 1e-3);
+
+    // Dragging right moves the d-pad right on screen, whichever way the layout is stored
+    layout.move_cluster(Cluster::DPad, Vec2::new(0.1, 0.0));
+    assert!((layout.rect(up, screen).x - left_handed.x - 100.0).abs() < 1e-3);
+    layout.move_cluster(Cluster::DPad, Vec2::new(5.0, 5.0));
+    assert_eq!(layout.center(Cluster::DPad), Vec2::new(1.0, 1.0));
+}
+
+#[test]
+fn shoulders_stay_on_their_sides_when_mirrored() {
+    let screen = Vec2::new(1000.0, 500.0);
+    let layout = Layout {
+        left_handed: true,
+        ..Layout::default()
+    };
+    let (l, r) = (
+        ButtonKind::Action(Buttons::L),
+        ButtonKind::Action(Buttons::R),
+    );
+    assert!(layout.rect(l, screen).center().x < 500.0);
+    assert!(layout.rect(r, screen).center().x > 500.0);
+
+    // They move with the cluster below them
+    assert_eq!(layout.cluster_of(r), Cluster::DPad);
+    assert_eq!(
+        layout.cluster_at(layout.rect(l, screen).center(), screen),
+        Some(Cluster::Actions)
+    );
+    let actions = layout.cluster_rect(Cluster::Actions, screen);
+    assert!(actions.right() < 500.0);
+}
+
+#[test]
+fn layouts_round_trip_and_fill_in_missing_fields() {
+    let mut layout = Layout::default();
+    layout.set_scale(10.0);
+    layout.set_opacity(0.5);
+    let json = serde_json::to_string(&layout).unwrap();
+    assert_eq!(serde_json::from_str::<Layout>(&json).unwrap(), layout);
+    assert!((layout.scale - input_lib::MAX_SCALE).abs() < f32::EPSILON);
+
+    let partial: Layout = serde_json::from_str(r#"{"left_handed":true}"#).unwrap();
+    assert!(partial.left_handed);
+    assert_eq!(partial.dpad, Layout::default().dpad);
+}
+
+#[test]
+fn sanitized_layouts_stay_on_the_screen() {
+    let layout: Layout = serde_json::from_str(
+        r#"{"dpad":[-3.0,0.5],"actions":[0.9,7.0],"scale":0.0,"opacity":40.0}"#,
+    )
+    .unwrap();
+    let mut layout = layout.sanitized();
+    assert_eq!(layout.center(Cluster::DPad), Vec2::new(0.0, 0.5));
+    assert_eq!(layout.center(Cluster::Actions), Vec2::new(0.9, 1.0));
+    assert!((layout.scale - input_lib::MIN_SCALE).abs() < f32::EPSILON);
+    assert!((layout.opacity - 1.0).abs() < f32::EPSILON);
+
+    // Json has no NaN, but a layout built in code can
+    layout.scale = f32::NAN;
+    layout.bar[0] = f32::NAN;
+    let layout = layout.sanitized();
+    assert!((layout.scale - Layout::default().scale).abs() < f32::EPSILON);
+    assert_eq!(layout.bar, Layout::default().bar);
+}
+
+#[test]
+fn frames_round_trip_through_the_controller_and_json() {
+    let mut state = ButtonState::default();
+    state.set(ButtonKind::Action(Buttons::Start));
+    state.set(ButtonKind::DPad(DPadButtons::Left));
+    let frame = Frame {
+        state,
+        frame_time: 0.016,
+        pressed: Some(Physical::Key(KeyCode::Enter)),
+        pointers: vec![(3, [10.0, 20.0])],
+        wheel: Some(([5.0, 6.0], -1.0)),
+    };
+
+    let mut controller = Controller::new();
+    controller.feed_frame(&frame);
+    assert_eq!(controller.frame(), frame);
+    assert_eq!(controller.pointers(), [(3, Vec2::new(10.0, 20.0))]);
+    assert!(controller.clicked(Buttons::Start));
+
+    let json = serde_json::to_string(&frame).unwrap();
+    assert_eq!(serde_json::from_str::<Frame>(&json).unwrap(), frame);
+    // Idle frames stay short
+    let idle = serde_json::to_string(&Frame::default()).unwrap();
+    assert!(!idle.contains("pointers") && !idle.contains("pressed"));
+}
+
+#[test]
+fn frames_played_on_another_screen_keep_their_pointers_in_place() {
+    let frame = Frame {
+        frame_time: 0.016,
+        pointers: vec![(3, [100.0, 50.0])],
+        wheel: Some(([400.0, 300.0], 1.0)),
+        ..Frame::default()
+    };
+    let (recorded, played) = (Vec2::new(800.0, 600.0), Vec2::new(1600.0, 900.0));
+
+    let rescaled = frame.rescaled(recorded, played);
+    assert_eq!(rescaled.pointers, [(3, [200.0, 75.0])]);
+    assert_eq!(rescaled.wheel, Some(([800.0, 450.0], 1.0)));
+    assert_eq!(rescaled.frame_time, frame.frame_time);
+
+    // Recordings without a screen size play as they are
+    assert_eq!(frame.rescaled(Vec2::ZERO, played), frame);
+}
@@ -0,0 +1,258 @@
+use luminara::net::NetSession;
+use luminara::scenario::Scenario;
+use luminara_core::class::UnitClass;
+use luminara_core::command::Command;
+use luminara_core::grid::GridTopology;
+use luminara_core::unit::UnitId;
+use luminara_core::world::{Faction, WorldState};
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+fn world() -> WorldState {
+    Scenario::new(&["......"])
+        .seed(7)
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Fighter, Faction::Enemy, (5, 0))
+        .world()
+}
+
+/// A host and a guest connected over localhost
+fn sessions() -> (NetSession, NetSession) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let host = std::thread::spawn(move || NetSession::host(listener, 42, GridTopology::Hex));
+
+    let (guest, seed, topology) = NetSession::join(&addr).unwrap();
+    assert_eq!((seed, topology), (42, GridTopology::Hex));
+    (host.join().unwrap().unwrap(), guest)
+}
+
+fn poll_until(session: &mut NetSession, mut done: impl FnMut(&mut NetSession) -> bool) {
+    let start = Instant::now();
+    while !done(session) {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "Nothing arrived in time"
+        );
+        session.update();
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Sends the host's player phase and applies it on both worlds
+fn play_player_phase(host: &mut NetSession, guest: &mut NetSession) -> (WorldState, WorldState) {
+    let (mut host_world, mut guest_world) = (world(), world());
+    let batches = [
+        vec![Command::DamageUnit(UnitId::new(1), 3)],
+        vec![Command::SetupTurn(Faction::Enemy)],
+    ];
+    for batch in &batches {
+        host.send_commands(batch);
+        for command in batch.clone() {
+            host_world.apply_command(command);
+        }
+    }
+    host.phase_started(&host_world);
+
+    for expected in &batches {
+        let mut batch = None;
+        poll_until(guest, |guest| {
+            batch = guest.next_commands(&guest_world);
+            batch.is_some()
+        });
+        let batch = batch.unwrap();
+        assert_eq!(batch.len(), expected.len());
+        for command in batch {
+            guest_world.apply_command(command);
+        }
+    }
+    (host_world, guest_world)
+}
+
+#[test]
+fn host_plays_the_player_and_guest_the_enemy() {
+    let (host, guest) = sessions();
+    assert_eq!(host.local(), Faction::Player);
+    assert_eq!(guest.local(), Faction::Enemy);
+    assert_eq!(guest.remote(), Faction::Player);
+}
+
+#[test]
+fn guest_waits_until_the_host_sent_something() {
+    let (_host, mut guest) = sessions();
+    guest.update();
+    assert!(guest.next_commands(&world()).is_none());
+    assert!(guest.is_waiting());
+}
+
+#[test]
+fn commands_arrive_in_batches_and_checksums_agree() {
+    let (mut host, mut guest) = sessions();
+    let (host_world, guest_world) = play_player_phase(&mut host, &mut guest);
+    assert_eq!(host_world.checksum(), guest_world.checksum());
+
+    guest.phase_started(&guest_world);
+    poll_until(&mut host, |host| host.agreed().is_some());
+    poll_until(&mut guest, |guest| guest.agreed().is_some());
+    assert_eq!(guest.agreed(), host.agreed());
+    assert_eq!(host.agreed().unwrap().phase, Faction::Enemy);
+    assert!(host.desync().is_none());
+}
+
+#[test]
+fn diverging_worlds_are_reported_as_a_desync() {
+    let (mut host, mut guest) = sessions();
+    let (_, mut guest_world) = play_player_phase(&mut host, &mut guest);
+    // Something only the guest applied
+    guest_world.apply_command(Command::DamageUnit(UnitId::new(0), 1));
+
+    guest.phase_started(&guest_world);
+    poll_until(&mut host, |host| host.desync().is_some());
+    poll_until(&mut guest, |guest| guest.desync().is_some());
+    assert!(host.agreed().is_none());
+    assert_eq!(guest.desync().unwrap().turn, 1);
+}
+
+/// Polls both sides, each needs to notice the drop and pick the connection up again
+fn poll_both(
+    host: &mut NetSession,
+    guest: &mut NetSession,
+    mut done: impl FnMut(&mut NetSession, &mut NetSession) -> bool,
+) {
+    let start = Instant::now();
+    while !done(host, guest) {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "Nothing arrived in time"
+        );
+        host.update();
+        guest.update();
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn batches_missed_while_disconnected_are_resent() {
+    let (mut host, mut guest) = sessions();
+    let (mut host_world, mut guest_world) = play_player_phase(&mut host, &mut guest);
+    guest.phase_started(&guest_world);
+    poll_both(&mut host, &mut guest, |host, guest| {
+        host.agreed().is_some() && guest.agreed().is_some()
+    });
+
+    // The guest's phase, the connection drops after its first batch arrived
+    let batches = [
+        vec![Command::DamageUnit(UnitId::new(0), 2)],
+        vec![Command::SetupTurn(Faction::Player)],
+    ];
+    guest.send_commands(&batches[0]);
+    let mut batch = None;
+    poll_until(&mut host, |host| {
+        batch = host.next_commands(&host_world);
+        batch.is_some()
+    });
+    guest.drop_connection();
+    // Neither side redials before both noticed, the other one is not polled meanwhile
+    poll_until(&mut host, |host| !host.is_connected());
+    poll_until(&mut guest, |guest| !guest.is_connected());
+
+    // Sent while nobody is listening
+    guest.send_commands(&batches[1]);
+    for command in batches.iter().flatten().cloned() {
+        guest_world.apply_command(command);
+    }
+    guest.phase_started(&guest_world);
+
+    for command in batch.unwrap() {
+        host_world.apply_command(command);
+    }
+    let mut resent = None;
+    poll_both(&mut host, &mut guest, |host, _| {
+        resent = host.next_commands(&host_world);
+        resent.is_some()
+    });
+    assert!(matches!(
+        resent.as_deref(),
+        Some([Command::SetupTurn(Faction::Player)])
+    ));
+    for command in resent.unwrap() {
+        host_world.apply_command(command);
+    }
+    assert!(host.next_commands(&host_world).is_none());
+
+    host.phase_started(&host_world);
+    poll_both(&mut host, &mut guest, |host, guest| {
+        host.agreed().is_some_and(|agreed| agreed.turn == 2)
+            && guest.agreed().is_some_and(|agreed| agreed.turn == 2)
+    });
+    assert!(host.is_connected() && guest.is_connected());
+    assert_eq!(host.agreed(), guest.agreed());
+    assert!(host.desync().is_none() && guest.desync().is_none());
+}
+
+#[test]
+fn commands_the_opponent_could_not_give_are_rejected() {
+    let (mut host, mut guest) = sessions();
+    let guest_world = world();
+    let mut moved = guest_world.get_unit(UnitId::new(1)).unwrap().clone();
+    moved.pos = (4, 0).into();
+    let mut off_map = guest_world.get_unit(UnitId::new(0)).unwrap().clone();
+    off_map.pos = (40, 0).into();
+    host.send_commands(&[
+        // The guest's own unit, a unit nobody has and a tile off the map
+        Command::CommitUnit(moved),
+        Command::DamageUnit(UnitId::new(9), 5),
+        Command::CommitUnit(off_map),
+        Command::DamageUnit(UnitId::new(1), 3),
+    ]);
+
+    let mut batch = None;
+    poll_until(&mut guest, |guest| {
+        batch = guest.next_commands(&guest_world);
+        batch.is_some()
+    });
+    assert!(matches!(
+        batch.as_deref(),
+        Some([Command::DamageUnit(id, 3)]) if *id == UnitId::new(1)
+    ));
+    assert_eq!(guest.desync().unwrap().turn, 1);
+}
+
+#[test]
+fn a_gap_in_the_batches_asks_for_a_resend() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let host = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let batch = |seq: u64| {
+            serde_json::json!({
+                "type": "commands",
+                "seq": seq,
+                "commands": [Command::SetupTurn(Faction::Enemy)],
+            })
+        };
+        let start = serde_json::json!({"type": "start", "seed": 1, "topology": "Square"});
+        // Batch 1 got lost on the way
+        for message in [start, batch(0), batch(2), batch(3)] {
+            writeln!(stream, "{message}").unwrap();
+        }
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply).unwrap();
+        reply
+    });
+
+    let (mut guest, _, _) = NetSession::join(&addr).unwrap();
+    let guest_world = world();
+    poll_until(&mut guest, |guest| {
+        guest.next_commands(&guest_world).is_some()
+    });
+    let reply: serde_json::Value = serde_json::from_str(&host.join().unwrap()).unwrap();
+    assert_eq!(reply["type"], "resume");
+    assert_eq!(reply["received"], 1);
+
+    // The batches after the gap wait for the resend
+    guest.update();
+    assert!(guest.next_commands(&guest_world).is_none());
+}
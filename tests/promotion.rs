@@ -0,0 +1,92 @@
+use luminara::scenario::Scenario;
+use luminara_core::class::{MAX_LEVEL, PROMOTION_LEVEL, UnitClass};
+use luminara_core::unit::{EXP_PER_LEVEL, Item, Unit, UnitId};
+use luminara_core::world::Faction;
+
+/// A player unit of the class at the level, holding a Master Seal
+fn unit(class: UnitClass, level: u32) -> Unit {
+    Scenario::new(&["."])
+        .unit(class, Faction::Player, (0, 0))
+        .with(|unit| {
+            unit.level = level;
+            unit.items.push(Item::MasterSeal);
+        })
+        .world()
+        .get_unit(UnitId::new(0))
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn reaching_the_max_level_promotes() {
+    let mut unit = unit(UnitClass::Fighter, MAX_LEVEL - 1);
+    let before = unit.stats;
+    unit.exp = EXP_PER_LEVEL - 10;
+
+    let gains = unit.gain_exp(30).expect("The unit levels up");
+    assert_eq!(unit.class(), UnitClass::Warrior);
+    assert_eq!((unit.level, unit.exp), (1, 0));
+    let promotion = UnitClass::Warrior.data().base_stats - UnitClass::Fighter.data().base_stats;
+    assert_eq!(unit.stats, before + gains + promotion);
+    // The seal was not needed
+    assert_eq!(unit.items, [Item::MasterSeal]);
+}
+
+#[test]
+fn promoted_classes_stop_at_the_max_level() {
+    let mut unit = unit(UnitClass::Warrior, MAX_LEVEL);
+    let before = unit.stats;
+
+    assert!(unit.gain_exp(EXP_PER_LEVEL).is_none());
+    assert_eq!((unit.level, unit.exp), (MAX_LEVEL, 0));
+    assert_eq!(unit.class(), UnitClass::Warrior);
+    assert_eq!(unit.stats, before);
+}
+
+#[test]
+fn the_master_seal_needs_the_promotion_level() {
+    let mut unit = unit(UnitClass::Fighter, PROMOTION_LEVEL - 1);
+    assert!(!unit.can_use_item(Item::MasterSeal));
+    assert!(!unit.use_item(Item::MasterSeal));
+    assert_eq!(unit.class(), UnitClass::Fighter);
+    assert_eq!(unit.items, [Item::MasterSeal]);
+}
+
+#[test]
+fn the_master_seal_needs_a_class_to_promote_to() {
+    let mut unit = unit(UnitClass::Sage, PROMOTION_LEVEL);
+    assert!(!unit.use_item(Item::MasterSeal));
+    assert_eq!(
+        (unit.class(), unit.level),
+        (UnitClass::Sage, PROMOTION_LEVEL)
+    );
+    assert_eq!(unit.items, [Item::MasterSeal]);
+}
+
+#[test]
+fn the_master_seal_promotes_and_is_used_up() {
+    let mut unit = unit(UnitClass::Mage, PROMOTION_LEVEL);
+    unit.exp = 50;
+    let before = unit.stats;
+
+    assert!(unit.use_item(Item::MasterSeal));
+    assert_eq!(unit.class(), UnitClass::Sage);
+    assert_eq!((unit.level, unit.exp), (1, 0));
+    assert!(unit.items.is_empty());
+    let promotion = UnitClass::Sage.data().base_stats - UnitClass::Mage.data().base_stats;
+    assert_eq!(unit.stats, before + promotion);
+    assert!(!unit.use_item(Item::MasterSeal));
+}
+
+#[test]
+fn promotion_gains_stop_at_the_new_caps() {
+    let mut unit = unit(UnitClass::Cavalier, PROMOTION_LEVEL);
+    let caps = UnitClass::Paladin.data().stat_caps;
+    unit.stats.hp = caps.hp - 2;
+    unit.curr_health = unit.stats.hp - 5;
+
+    assert!(unit.use_item(Item::MasterSeal));
+    assert_eq!(unit.stats.hp, caps.hp);
+    // Healed by as much as the maximum grew
+    assert_eq!(unit.curr_health, caps.hp - 5);
+}
@@ -0,0 +1,489 @@
+use luminara::scenario::{Battle, Scenario};
+use luminara::tty::TextColor;
+use luminara_core::class::UnitClass;
+use luminara_core::command::Command;
+use luminara_core::math::Point;
+use luminara_core::pathfinding::DijkstraMap;
+use luminara_core::unit::{Skill, UnitId};
+use luminara_core::world::Faction;
+
+use input_lib::{ButtonKind, Buttons, Layout, Physical};
+use macroquad::input::KeyCode;
+
+#[test]
+fn infantry_walks_its_movement() {
+    Scenario::new(&["........"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .start()
+        .assert_reachables(0, &[(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (5, 0)]);
+}
+
+#[test]
+fn forest_slows_mounted_units_only() {
+    let battle = Scenario::new(&[".TTTT....", ".TTTT...."])
+        .unit(UnitClass::Cavalier, Faction::Player, (0, 0))
+        .unit(UnitClass::Fighter, Faction::Player, (0, 1))
+        .start();
+
+    assert!(battle.reachables(0).contains(&(3, 0).into()));
+    assert!(!battle.reachables(0).contains(&(4, 0).into()));
+    assert!(battle.reachables(1).contains(&(5, 1).into()));
+}
+
+#[test]
+fn rivers_stop_walkers_but_not_flyers() {
+    let battle = Scenario::new(&["..~..", "....."])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::PegasusKnight, Faction::Player, (0, 1))
+        .start();
+
+    assert!(!battle.reachables(0).contains(&(2, 0).into()));
+    assert!(battle.reachables(0).contains(&(3, 0).into()));
+    assert!(battle.reachables(1).contains(&(2, 0).into()));
+
+    Scenario::new(&["..~^^"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .start()
+        .assert_reachables(0, &[(0, 0), (1, 0)]);
+}
+
+#[test]
+fn paths_to_tiles_out_of_reach_are_empty() {
+    let battle = Scenario::new(&["..~..", "..~.."])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .start();
+    let dijkstra_map = DijkstraMap::new(battle.world(), battle.unit(0));
+
+    let to = |pt: (i32, i32)| dijkstra_map.get_path_to(pt);
+    assert_eq!(to((1, 1)).len(), 3);
+    assert!(to((3, 0)).is_empty());
+    assert!(to((9, 0)).is_empty());
+    assert!(to((0, 5)).is_empty());
+}
+
+#[test]
+fn allies_can_be_passed_but_not_stopped_on() {
+    Scenario::new(&["......."])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Archer, Faction::Player, (2, 0))
+        .start()
+        .assert_reachables(0, &[(0, 0), (1, 0), (3, 0), (4, 0), (5, 0)]);
+}
+
+#[test]
+fn enemies_block_movement() {
+    Scenario::new(&["......."])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Fighter, Faction::Enemy, (2, 0))
+        .start()
+        .assert_reachables(0, &[(0, 0), (1, 0)]);
+}
+
+#[test]
+fn zone_of_control_stops_next_to_enemies() {
+    Scenario::new(&["......", "......"])
+        .zone_of_control()
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Fighter, Faction::Enemy, (2, 1))
+        .start()
+        .assert_reachables(0, &[(0, 0), (1, 0), (2, 0), (0, 1), (1, 1)]);
+}
+
+#[test]
+fn pass_ignores_zone_of_control_and_enemies() {
+    Scenario::new(&["......", "......"])
+        .zone_of_control()
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .with(|unit| unit.skills.push(Skill::Pass))
+        .unit(UnitClass::Fighter, Faction::Enemy, (2, 1))
+        .start()
+        .assert_reachables(
+            0,
+            &[
+                (0, 0),
+                (1, 0),
+                (2, 0),
+                (3, 0),
+                (4, 0),
+                (5, 0),
+                (0, 1),
+                (1, 1),
+                (3, 1),
+                (4, 1),
+            ],
+        );
+}
+
+#[test]
+fn hex_movement_covers_a_hexagon() {
+    let rows = vec!["..........."; 11];
+    let hex = Scenario::new(&rows)
+        .hex()
+        .unit(UnitClass::Fighter, Faction::Player, (5, 5))
+        .start();
+    let square = Scenario::new(&rows)
+        .unit(UnitClass::Fighter, Faction::Player, (5, 5))
+        .start();
+
+    // 1 + 6 + 12 + 18 + 24 + 30 tiles within 5 steps
+    assert_eq!(hex.reachables(0).len(), 91);
+    // 2 * 5 * (5 + 1) + 1 tiles within 5 steps
+    assert_eq!(square.reachables(0).len(), 61);
+}
+
+#[test]
+fn move_then_wait_keeps_the_phase() {
+    let mut battle = Scenario::new(&["........", "........"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Fighter, Faction::Player, (0, 1))
+        .start();
+
+    battle.keys("z").assert_state("Player Move");
+    battle.keys("ddz").assert_state("Player Action");
+    // Attack, Skill, Wait
+    battle.keys("ssz").assert_state("Player Select");
+    battle.assert_pos(0, (2, 0));
+    assert!(battle.unit(0).turn_complete);
+    assert_eq!(battle.world().turn, 1);
+}
+
+#[test]
+fn cancelling_leaves_the_unit_in_place() {
+    let mut battle = Scenario::new(&["........"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .start();
+
+    battle.keys("zdd").assert_state("Player Move");
+    battle.button(Buttons::B).assert_state("Player Select");
+    battle.assert_pos(0, (0, 0));
+    assert!(!battle.unit(0).turn_complete);
+}
+
+#[test]
+fn last_unit_waiting_ends_the_turn() {
+    let mut battle = Scenario::new(&["........"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .start();
+
+    battle.keys("zzssz").assert_state("Player Select");
+    assert_eq!(battle.world().turn, 2);
+    assert!(!battle.unit(0).turn_complete);
+}
+
+#[test]
+fn attack_and_counter_phase() {
+    let mut battle = Scenario::new(&["......"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Archer, Faction::Enemy, (2, 0))
+        .start();
+    let dealt = battle.unit(0).attack_damage(battle.unit(1));
+    let taken = battle.unit(1).attack_damage(battle.unit(0));
+
+    battle.keys("zdz").assert_state("Player Action");
+    battle.keys("z").assert_state("Player Attack");
+    // The archer shoots back from where it stands during its phase
+    battle.keys("z").assert_state("Player Select");
+    battle
+        .assert_pos(0, (1, 0))
+        .assert_hp(1, 18 - dealt)
+        .assert_hp(0, 20 - taken);
+    assert_eq!(battle.world().turn, 2);
+}
+
+#[test]
+fn commands_reach_the_world() {
+    let mut battle = Scenario::new(&["......"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Fighter, Faction::Enemy, (5, 0))
+        .start();
+
+    battle
+        .command(Command::DamageUnit(UnitId::new(1), 5))
+        .assert_hp(1, 15)
+        .assert_state("Player Select");
+    battle
+        .command(Command::DamageUnit(UnitId::new(1), 15))
+        .assert_defeated(1);
+    assert!(battle.world().unit_at((5, 0)).is_none());
+}
+
+#[test]
+fn versus_hands_the_device_over_between_phases() {
+    let mut battle = Scenario::new(&["......"])
+        .versus()
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Fighter, Faction::Enemy, (5, 0))
+        .start();
+
+    battle.assert_state("Pass Device");
+    battle.keys("z").assert_state("Player Select");
+    battle.keys("zdzssz").assert_state("Pass Device");
+    battle.assert_pos(0, (1, 0));
+
+    // The cursor starts on the enemy's own unit
+    battle.keys("z").keys("zazssz").assert_state("Pass Device");
+    battle.assert_pos(1, (4, 0));
+    assert_eq!(battle.world().turn, 2);
+}
+
+#[test]
+fn x_opens_the_unit_info_of_any_unit() {
+    let mut battle = Scenario::new(&["......"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Mage, Faction::Player, (1, 0))
+        .unit(UnitClass::Archer, Faction::Enemy, (5, 0))
+        .start();
+
+    // The info screen puts the cursor on the unit it shows
+    let shows = |battle: &mut Battle, keys: &str, pos: (i32, i32)| {
+        battle.keys(keys).assert_state("Unit Info");
+        assert_eq!(battle.screen().cursor(), Some(Point::from(pos)));
+    };
+    shows(&mut battle, "c", (0, 0));
+    assert!(battle.screen().to_text(false).contains("Player Fighter"));
+    // Paging wraps around the player's units and never shows the enemy
+    shows(&mut battle, "d", (1, 0));
+    assert!(battle.screen().to_text(false).contains("Player Mage"));
+    shows(&mut battle, "d", (0, 0));
+    shows(&mut battle, "a", (1, 0));
+    shows(&mut battle, "s", (0, 0));
+    battle.keys("x").assert_state("Player Select");
+
+    // The enemy pages through its own faction, which is only itself
+    shows(&mut battle, "dddddc", (5, 0));
+    shows(&mut battle, "d", (5, 0));
+    battle.keys("c").assert_state("Player Select");
+
+    battle.keys("a").keys("c").assert_state("Player Select");
+}
+
+#[test]
+fn end_turn_from_the_map_menu() {
+    let mut battle = Scenario::new(&["......"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Fighter, Faction::Player, (1, 0))
+        .unit(UnitClass::Fighter, Faction::Enemy, (5, 0))
+        .start();
+
+    battle.keys("e").assert_state("Map Menu");
+    battle.keys("e").assert_state("Player Select");
+    assert_eq!(battle.world().turn, 1);
+
+    battle.keys("ez").assert_state("Player Select");
+    assert_eq!(battle.world().turn, 2);
+    battle.assert_pos(0, (0, 0)).assert_pos(1, (1, 0));
+}
+
+#[test]
+fn retiring_needs_a_confirmation() {
+    let mut battle = Scenario::new(&["......"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .start();
+
+    // End Turn, Objectives, Unit List, Suspend, Options, Retire
+    battle.keys("esssssz").assert_state("Map Menu");
+    battle.keys("x").assert_state("Map Menu");
+    battle.keys("zz").assert_state("Retired");
+    battle.keys("zxe").assert_state("Retired");
+}
+
+#[test]
+fn shoulder_buttons_cycle_through_unmoved_units() {
+    let mut battle = Scenario::new(&["..........", ".........."])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Fighter, Faction::Player, (3, 1))
+        .unit(UnitClass::Fighter, Faction::Player, (6, 0))
+        .unit(UnitClass::Fighter, Faction::Enemy, (9, 1))
+        .start();
+
+    // L wraps around from the first unit to the last one
+    battle
+        .keys("[")
+        .keys("zszssz")
+        .assert_state("Player Select");
+    battle.assert_pos(2, (6, 1));
+
+    // Moved units are skipped, R wraps around to the first one and goes on from there
+    battle
+        .keys("]]")
+        .keys("zzssz")
+        .assert_state("Player Select");
+    assert!(battle.unit(1).turn_complete);
+    assert!(!battle.unit(0).turn_complete);
+}
+
+#[test]
+fn keys_are_rebound_from_the_map_menu() {
+    let mut battle = Scenario::new(&["......"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Fighter, Faction::Player, (1, 0))
+        .start();
+    let a = ButtonKind::Action(Buttons::A);
+
+    // Options is the fifth entry of the map menu and A the fifth row after the d-pad
+    battle.keys("essssz").keys("ssssz").assert_state("Controls");
+    battle.physical(Physical::Key(KeyCode::K));
+    let bindings = &battle.settings().bindings;
+    assert_eq!(
+        bindings.inputs_of(a).collect::<Vec<_>>(),
+        [Physical::Key(KeyCode::K)]
+    );
+    assert_eq!(bindings.target_of(Physical::Key(KeyCode::Z)), None);
+
+    // The new key acts as A right away
+    battle
+        .physical(Physical::Key(KeyCode::Z))
+        .assert_state("Controls");
+    battle.keys("x").keys("x").assert_state("Player Select");
+    battle
+        .physical(Physical::Key(KeyCode::K))
+        .assert_state("Player Move");
+}
+
+#[test]
+fn taken_keys_swap_only_when_pressed_twice() {
+    let mut battle = Scenario::new(&["......"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .start();
+    let a = ButtonKind::Action(Buttons::A);
+    let b = ButtonKind::Action(Buttons::B);
+    let (z, x) = (Physical::Key(KeyCode::Z), Physical::Key(KeyCode::X));
+
+    battle.keys("essssz").keys("ssssz").assert_state("Controls");
+    battle.physical(x);
+    assert_eq!(battle.settings().bindings.target_of(x), Some(b));
+    battle.physical(x);
+    let bindings = &battle.settings().bindings;
+    assert_eq!(bindings.target_of(x), Some(a));
+    assert_eq!(bindings.target_of(z), Some(b));
+
+    // Escape cancels without touching anything
+    battle.keys("z").physical(Physical::Key(KeyCode::Escape));
+    battle.assert_state("Controls");
+    assert_eq!(battle.settings().bindings.target_of(z), Some(b));
+    battle.physical(z).assert_state("Map Menu");
+}
+
+#[test]
+fn tapping_moves_the_cursor_and_tapping_again_selects() {
+    let mut battle = Scenario::new(&["........", "........"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Fighter, Faction::Player, (4, 1))
+        .start();
+
+    battle.tap((4, 1)).assert_state("Player Select");
+    battle.tap((4, 1)).assert_state("Player Move");
+    battle.tap((6, 1)).assert_state("Player Move");
+    battle.tap((6, 1)).assert_state("Player Action");
+    battle.keys("ssz").assert_pos(1, (6, 1));
+}
+
+#[test]
+fn the_on_screen_layout_is_edited_from_the_controls() {
+    let mut battle = Scenario::new(&["......"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .start();
+
+    battle
+        .keys("essssz")
+        .keys("c")
+        .assert_state("Layout Editor");
+    // R picks the face buttons, the d-pad nudges them, X grows every button, Select mirrors
+    battle.keys("]").keys("dd").keys("c").keys("r");
+    assert!(battle.settings().editing_layout);
+    battle.keys("z").assert_state("Controls");
+
+    let layout = &battle.settings().layout;
+    let default = Layout::default();
+    assert!((layout.actions[0] - default.actions[0] - 0.02).abs() < 1e-4);
+    assert_eq!(layout.dpad, default.dpad);
+    assert!((layout.scale - 1.1).abs() < 1e-4);
+    assert!(layout.left_handed);
+    assert!(!battle.settings().editing_layout);
+}
+
+#[test]
+fn cancelling_the_layout_editor_keeps_the_old_layout() {
+    let mut battle = Scenario::new(&["......"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .start();
+
+    battle.keys("essssz").keys("c").keys("wwcv");
+    assert_ne!(battle.settings().layout, Layout::default());
+    battle.keys("x").assert_state("Controls");
+    assert_eq!(battle.settings().layout, Layout::default());
+}
+
+fn recording_path(name: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("luminara_{name}_{}.jsonl", std::process::id()))
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[test]
+fn a_recorded_session_replays_identically() {
+    let scenario = || {
+        Scenario::new(&["........", "........"])
+            .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+            .unit(UnitClass::Fighter, Faction::Enemy, (7, 1))
+    };
+    let path = recording_path("replay");
+
+    let mut recorded = scenario().start();
+    // Menus, an enemy phase and a move all go through the recording
+    recorded.record(&path).keys("ex").keys("ez").keys("zddz");
+    let mut replayed = scenario().start();
+    replayed.replay(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(replayed.state(), recorded.state());
+    for id in 0..2 {
+        let pos = recorded.unit(id).pos;
+        replayed.assert_pos(id, (pos.x, pos.y));
+    }
+}
+
+#[test]
+fn replays_keep_the_keys_pressed_while_rebinding() {
+    let scenario = || {
+        Scenario::new(&["......"])
+            .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+            .start()
+    };
+    let path = recording_path("rebinding");
+
+    let mut recorded = scenario();
+    recorded
+        .record(&path)
+        .keys("essssz")
+        .keys("ssssz")
+        .physical(Physical::Key(KeyCode::K));
+    let mut replayed = scenario();
+    replayed.replay(&path).assert_state("Controls");
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        replayed
+            .settings()
+            .bindings
+            .inputs_of(ButtonKind::Action(Buttons::A))
+            .collect::<Vec<_>>(),
+        [Physical::Key(KeyCode::K)]
+    );
+}
+
+#[test]
+fn pass_keeps_enemies_it_moves_through_in_the_attack_preview() {
+    let mut battle = Scenario::new(&["........"])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .with(|unit| unit.skills.push(Skill::Pass))
+        .unit(UnitClass::Fighter, Faction::Player, (1, 0))
+        .unit(UnitClass::Fighter, Faction::Enemy, (2, 0))
+        .start();
+
+    battle.keys("z").assert_state("Player Move");
+    let targets = battle.screen().marked(TextColor::Red);
+    assert!(targets.contains(&Point::new(2, 0)));
+    assert!(!targets.contains(&Point::new(1, 0)));
+}
@@ -0,0 +1,106 @@
+use luminara::scenario::Scenario;
+use luminara_core::class::UnitClass;
+use luminara_core::command::Command;
+use luminara_core::math::Point;
+use luminara_core::snapshot::WorldSnapshot;
+use luminara_core::unit::{ErasedUnit, UnitId};
+use luminara_core::world::{Faction, WorldState};
+
+fn world() -> WorldState {
+    Scenario::new(&["........", "..T....."])
+        .seed(3)
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Mage, Faction::Player, (1, 1))
+        .unit(UnitClass::Archer, Faction::Enemy, (6, 0))
+        .unit(UnitClass::Cavalier, Faction::Enemy, (7, 1))
+        .world()
+}
+
+#[test]
+fn equal_worlds_have_equal_checksums() {
+    // Every world hashes its unit map with its own random state
+    let checksums: Vec<u64> = (0..8).map(|_| world().checksum()).collect();
+    assert!(checksums.windows(2).all(|pair| pair[0] == pair[1]));
+}
+
+#[test]
+fn rng_state_changes_the_checksum() {
+    let mut world = world();
+    let checksum = world.checksum();
+    world.rng.next_u64();
+    assert_ne!(world.checksum(), checksum);
+}
+
+#[test]
+fn snapshots_survive_a_round_trip() {
+    let snapshot = world().snapshot();
+    let loaded = WorldSnapshot::from_json(&snapshot.to_json()).unwrap();
+    assert_eq!(loaded.checksum(), snapshot.checksum());
+    assert!(loaded.diff(&snapshot).is_empty());
+}
+
+#[test]
+fn diff_names_the_changed_fields() {
+    let before = world();
+    let mut after = world();
+    after.apply_command(Command::DamageUnit(UnitId::new(2), 5));
+    after.apply_command(Command::DamageUnit(UnitId::new(1), 100));
+
+    let diffs: Vec<String> = before
+        .snapshot()
+        .diff(&after.snapshot())
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert!(diffs.contains(&"units[id=2].curr_health: 18 -> 13".to_string()));
+    assert!(
+        diffs
+            .iter()
+            .any(|diff| diff.starts_with("units[id=1]: {") && diff.ends_with("-> missing"))
+    );
+    assert_eq!(diffs.len(), 2, "{diffs:#?}");
+}
+
+#[test]
+fn restoring_a_snapshot_continues_the_battle() {
+    let mut played = world();
+    played.apply_command(Command::DamageUnit(UnitId::new(2), 5));
+    played.apply_command(Command::SetupTurn(Faction::Enemy));
+
+    let mut restored = world();
+    restored.restore(&played.snapshot()).unwrap();
+    assert!(restored.snapshot().diff(&played.snapshot()).is_empty());
+    assert_eq!(restored.unit_at((6, 0)).unwrap().curr_health, 13);
+}
+
+#[test]
+fn broken_snapshots_leave_the_world_alone() {
+    let mut world = world();
+    let checksum = world.checksum();
+    let breakages: [fn(&mut WorldSnapshot); 3] = [
+        |snapshot| snapshot.units[0].pos = Point::new(40, 0),
+        |snapshot| snapshot.units[1].pos = snapshot.units[0].pos,
+        |snapshot| {
+            let copy = snapshot.units[0].clone();
+            snapshot.units.push(copy);
+        },
+    ];
+    for breakage in breakages {
+        let mut snapshot = world.snapshot();
+        snapshot.turn += 1;
+        breakage(&mut snapshot);
+        assert!(world.restore(&snapshot).is_err());
+        assert_eq!(world.checksum(), checksum);
+    }
+}
+
+#[test]
+fn units_spawned_off_the_map_are_not_placed() {
+    let mut world = world();
+    let units = world.units().count();
+    for pos in [(40, 2), (3, 99), (-1, 0)] {
+        let unit = ErasedUnit::with_class(UnitClass::Fighter, Faction::Enemy, pos, "");
+        assert_eq!(world.spawn_units(&unit), None);
+    }
+    assert_eq!(world.units().count(), units);
+}
@@ -0,0 +1,130 @@
+use luminara::scenario::Scenario;
+use luminara_core::class::UnitClass;
+use luminara_core::command::Command;
+use luminara_core::status::{POISON_DAMAGE, StatusEffect, StatusKind};
+use luminara_core::unit::{Unit, UnitId};
+use luminara_core::world::{Faction, WorldState};
+
+fn world() -> WorldState {
+    Scenario::new(&["....", "...."])
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Mage, Faction::Player, (1, 0))
+        .unit(UnitClass::Fighter, Faction::Enemy, (3, 1))
+        .world()
+}
+
+fn unit(world: &WorldState, id: u32) -> &Unit {
+    world.get_unit(UnitId::new(id)).unwrap()
+}
+
+fn apply(world: &mut WorldState, id: u32, kind: StatusKind, turns: u32) {
+    world.apply_command(Command::ApplyStatus(
+        UnitId::new(id),
+        StatusEffect::new(kind, turns),
+    ));
+}
+
+/// The enemy phase and the next player phase
+fn next_turn(world: &mut WorldState) {
+    world.apply_command(Command::SetupTurn(Faction::Enemy));
+    world.apply_command(Command::SetupTurn(Faction::Player));
+}
+
+#[test]
+fn poison_ticks_at_the_start_of_the_phase() {
+    let mut world = world();
+    let hp = unit(&world, 0).curr_health;
+    apply(&mut world, 0, StatusKind::Poison, 3);
+
+    next_turn(&mut world);
+    assert_eq!(unit(&world, 0).curr_health, hp - POISON_DAMAGE);
+}
+
+#[test]
+fn poison_never_takes_the_last_point_of_health() {
+    let mut world = world();
+    let hp = unit(&world, 0).curr_health;
+    world.apply_command(Command::DamageUnit(UnitId::new(0), hp - 2));
+    apply(&mut world, 0, StatusKind::Poison, 3);
+
+    next_turn(&mut world);
+    assert_eq!(unit(&world, 0).curr_health, 1);
+    next_turn(&mut world);
+    assert_eq!(unit(&world, 0).curr_health, 1);
+}
+
+#[test]
+fn effects_only_tick_in_their_units_phase() {
+    let mut world = world();
+    let hp = unit(&world, 2).curr_health;
+    apply(&mut world, 2, StatusKind::Poison, 3);
+
+    world.apply_command(Command::SetupTurn(Faction::Player));
+    assert_eq!(unit(&world, 2).curr_health, hp);
+    world.apply_command(Command::SetupTurn(Faction::Enemy));
+    assert_eq!(unit(&world, 2).curr_health, hp - POISON_DAMAGE);
+}
+
+#[test]
+fn sleep_and_stun_skip_the_turn() {
+    let mut world = world();
+    apply(&mut world, 0, StatusKind::Sleep, 2);
+    apply(&mut world, 1, StatusKind::Stun, 2);
+    next_turn(&mut world);
+    assert!(unit(&world, 0).turn_complete);
+    assert!(unit(&world, 1).turn_complete);
+    assert!(!unit(&world, 2).turn_complete);
+}
+
+#[test]
+fn rooted_units_cannot_move() {
+    let mut world = world();
+    assert!(unit(&world, 0).movement() > 0);
+    apply(&mut world, 0, StatusKind::Rooted, 2);
+    assert_eq!(unit(&world, 0).movement(), 0);
+    // Still free to act
+    next_turn(&mut world);
+    assert!(!unit(&world, 0).turn_complete);
+}
+
+#[test]
+fn damage_wakes_sleeping_units() {
+    let mut world = world();
+    apply(&mut world, 0, StatusKind::Sleep, 3);
+    world.apply_command(Command::DamageUnit(UnitId::new(0), 0));
+    assert!(unit(&world, 0).has_status(StatusKind::Sleep));
+
+    apply(&mut world, 1, StatusKind::Stun, 3);
+    for id in [0, 1] {
+        world.apply_command(Command::DamageUnit(UnitId::new(id), 1));
+    }
+    assert!(!unit(&world, 0).has_status(StatusKind::Sleep));
+    assert!(unit(&world, 1).has_status(StatusKind::Stun));
+}
+
+#[test]
+fn effects_wear_off_after_their_turns() {
+    let mut world = world();
+    apply(&mut world, 0, StatusKind::Stun, 2);
+
+    next_turn(&mut world);
+    assert!(unit(&world, 0).has_status(StatusKind::Stun));
+    // The last turn still counts
+    next_turn(&mut world);
+    assert!(!unit(&world, 0).has_status(StatusKind::Stun));
+    assert!(unit(&world, 0).turn_complete);
+
+    next_turn(&mut world);
+    assert!(!unit(&world, 0).turn_complete);
+}
+
+#[test]
+fn reapplying_an_effect_refreshes_its_turns() {
+    let mut world = world();
+    apply(&mut world, 0, StatusKind::Rooted, 3);
+    apply(&mut world, 0, StatusKind::Rooted, 1);
+    assert_eq!(
+        unit(&world, 0).status_effects,
+        [StatusEffect::new(StatusKind::Rooted, 3)]
+    );
+}
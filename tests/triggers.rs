@@ -0,0 +1,160 @@
+use luminara::scenario::{Battle, Scenario};
+use luminara_core::class::UnitClass;
+use luminara_core::command::Command;
+use luminara_core::event::{Condition, Reinforcement, TriggerAction};
+use luminara_core::map::Terrain;
+use luminara_core::math::{Point, TileRect};
+use luminara_core::unit::{ErasedUnit, Item, UnitId};
+use luminara_core::world::{Faction, Objective};
+
+/// The river keeps the enemy side from reaching the player
+const MAP: [&str; 2] = ["...~..", "...~.."];
+
+fn scenario() -> Scenario {
+    Scenario::new(&MAP)
+        .unit(UnitClass::Fighter, Faction::Player, (0, 0))
+        .unit(UnitClass::Fighter, Faction::Enemy, (5, 0))
+}
+
+/// Each firing hands the player's unit another tonic
+fn reward() -> TriggerAction {
+    TriggerAction::GiveItem(UnitId::new(0), Item::AttackTonic)
+}
+
+fn rewards(battle: &Battle) -> usize {
+    battle.unit(0).items.len()
+}
+
+fn end_turn(battle: &mut Battle) {
+    battle.keys("ez").assert_state("Player Select");
+}
+
+fn move_to(battle: &mut Battle, id: u32, pos: (i32, i32)) {
+    let mut unit = battle.unit(id).clone();
+    unit.pos = pos.into();
+    battle.command(Command::CommitUnit(unit));
+}
+
+#[test]
+fn turn_start_fires_in_its_phase_only() {
+    let objective = Objective::Survive { turns: 5 };
+    let mut battle = scenario()
+        .trigger(
+            Condition::TurnStart {
+                turn: 2,
+                faction: Faction::Player,
+            },
+            vec![reward(), TriggerAction::ChangeObjective(objective)],
+        )
+        .start();
+    assert_eq!(rewards(&battle), 0);
+    assert_eq!(battle.world().objective, Objective::Rout);
+
+    end_turn(&mut battle);
+    assert_eq!(battle.world().turn, 2);
+    assert_eq!(rewards(&battle), 1);
+    assert_eq!(battle.world().objective, objective);
+
+    end_turn(&mut battle);
+    assert_eq!(rewards(&battle), 1);
+}
+
+#[test]
+fn entering_a_region_fires_once() {
+    let mut battle = scenario()
+        .trigger(
+            Condition::UnitEntersRegion {
+                faction: Faction::Player,
+                region: TileRect {
+                    x: 1,
+                    y: 1,
+                    w: 1,
+                    h: 1,
+                },
+            },
+            vec![
+                reward(),
+                TriggerAction::ChangeTerrain((2, 1).into(), Terrain::Forest),
+            ],
+        )
+        .start();
+
+    // Units of the other faction do not count
+    move_to(&mut battle, 1, (4, 1));
+    move_to(&mut battle, 0, (1, 0));
+    assert_eq!(rewards(&battle), 0);
+    assert_eq!(battle.world().map.get_terrain((2, 1)), Terrain::Ground);
+
+    move_to(&mut battle, 0, (1, 1));
+    assert_eq!(rewards(&battle), 1);
+    assert_eq!(battle.world().map.get_terrain((2, 1)), Terrain::Forest);
+
+    move_to(&mut battle, 0, (0, 1));
+    move_to(&mut battle, 0, (1, 1));
+    assert_eq!(rewards(&battle), 1);
+}
+
+#[test]
+fn defeating_a_unit_brings_in_its_replacement_once() {
+    let replacement = ErasedUnit::with_class(UnitClass::Fighter, Faction::Enemy, (5, 0), "");
+    let mut battle = scenario()
+        .trigger(
+            Condition::UnitDefeated(UnitId::new(1)),
+            vec![reward(), TriggerAction::SpawnUnits(vec![replacement])],
+        )
+        .start();
+
+    battle
+        .command(Command::DamageUnit(UnitId::new(1), 100))
+        .assert_defeated(1);
+    assert_eq!(rewards(&battle), 1);
+    // Spawned where the defeated unit stood, the next id in line
+    assert_eq!(battle.unit(2).pos, Point::new(5, 0));
+
+    end_turn(&mut battle);
+    assert_eq!(rewards(&battle), 1);
+    assert_eq!(battle.world().units().count(), 2);
+}
+
+#[test]
+fn health_below_fires_once_while_it_stays_low() {
+    let mut battle = scenario()
+        .trigger(
+            Condition::HealthBelow {
+                unit: UnitId::new(0),
+                threshold: 10,
+            },
+            vec![reward()],
+        )
+        .start();
+
+    battle.command(Command::DamageUnit(UnitId::new(0), 10));
+    assert_eq!(rewards(&battle), 0);
+
+    battle.command(Command::DamageUnit(UnitId::new(0), 1));
+    assert_eq!(rewards(&battle), 1);
+    battle.command(Command::DamageUnit(UnitId::new(0), 1));
+    end_turn(&mut battle);
+    assert_eq!(rewards(&battle), 1);
+}
+
+#[test]
+fn reinforcements_arrive_in_their_phase() {
+    let mut battle = scenario()
+        .reinforcement(Reinforcement {
+            turn: 2,
+            unit: ErasedUnit::with_class(UnitClass::Fighter, Faction::Enemy, (5, 0), ""),
+            ambush: false,
+        })
+        .start();
+
+    end_turn(&mut battle);
+    assert_eq!(battle.world().units().count(), 2);
+
+    end_turn(&mut battle);
+    // On the given tile or the nearest free one, the river stays between
+    let arrived = battle.unit(2);
+    assert_eq!(arrived.faction, Faction::Enemy);
+    assert!(arrived.pos.x > 3);
+    assert_eq!(battle.world().units().count(), 3);
+}